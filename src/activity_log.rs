@@ -1,14 +1,105 @@
 //! Activity log — bash-history-style append-only audit trail.
 //!
 //! Log file: `~/.config/zen/zen.log`
-//! Format:   `YYYY-MM-DD HH:MM:SS [source] action details`
+//! Format:   `YYYY-MM-DD HH:MM:SS [source] action details`, or one JSON
+//!           object per line when the `log_format` config is `json`
+//!
+//! Past the size cap the file is rotated to `zen.log.1`, then `zen.log.2`;
+//! older generations are dropped.
 
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+/// Size cap before rotating when `log_max_size` is not configured (1 MB).
+pub const DEFAULT_MAX_BYTES: u64 = 1_000_000;
+
+/// Rotated generations kept next to the live log (`zen.log.1`, `zen.log.2`).
+const GENERATIONS: usize = 2;
+
+static MAX_BYTES: AtomicU64 = AtomicU64::new(DEFAULT_MAX_BYTES);
+static JSON_FORMAT: AtomicBool = AtomicBool::new(false);
+
+/// Sets the size cap for this process (from the `log_max_size` config).
+pub fn set_max_bytes(bytes: u64) {
+    MAX_BYTES.store(bytes, Ordering::Relaxed);
+}
+
+/// Writes JSON lines instead of text for this process (`log_format = json`).
+pub fn set_json_format(json: bool) {
+    JSON_FORMAT.store(json, Ordering::Relaxed);
+}
+
+/// One activity log line, in either on-disk format.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub source: String,
+    pub action: String,
+    pub detail: String,
+}
+
+impl LogEntry {
+    /// Parses a text or JSON log line. Lines in neither shape keep their
+    /// text in `detail` with the other fields empty.
+    pub fn parse(line: &str) -> Self {
+        if line.starts_with('{')
+            && let Ok(entry) = serde_json::from_str::<LogEntry>(line)
+        {
+            return entry;
+        }
+        let parsed = (|| {
+            let (date, rest) = line.split_once(' ')?;
+            let (time, rest) = rest.split_once(' ')?;
+            let (source, rest) = rest.strip_prefix('[')?.split_once(']')?;
+            let rest = rest.trim_start();
+            let (action, detail) = rest.split_once(' ').unwrap_or((rest, ""));
+            Some(LogEntry {
+                timestamp: format!("{} {}", date, time),
+                source: source.to_string(),
+                action: action.to_string(),
+                detail: detail.to_string(),
+            })
+        })();
+        parsed.unwrap_or_else(|| LogEntry {
+            timestamp: String::new(),
+            source: String::new(),
+            action: String::new(),
+            detail: line.to_string(),
+        })
+    }
 
-/// Max lines to keep before rotating.
-const MAX_LINES: usize = 1000;
+    /// The entry in the text log format.
+    pub fn to_text(&self) -> String {
+        if self.timestamp.is_empty() {
+            return self.detail.clone();
+        }
+        format!(
+            "{} [{}] {} {}",
+            self.timestamp, self.source, self.action, self.detail
+        )
+    }
+}
+
+/// Parses a size like `1MB`, `512KB`, `2M` or a plain byte count.
+pub fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim().to_ascii_uppercase();
+    let value = value.strip_suffix('B').unwrap_or(&value);
+    let (number, factor) = match value.strip_suffix('K') {
+        Some(n) => (n, 1_000),
+        None => match value.strip_suffix('M') {
+            Some(n) => (n, 1_000_000),
+            None => (value, 1),
+        },
+    };
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(factor))
+        .filter(|n| *n > 0)
+}
 
 /// Returns the path to the log file (`~/.config/zen/zen.log`).
 fn log_path() -> PathBuf {
@@ -18,64 +109,144 @@ fn log_path() -> PathBuf {
     dir.join("zen.log")
 }
 
+/// Path of rotated generation `n` (`zen.log.1`, ...).
+fn generation_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
 /// Append a single line to the activity log.
 ///
 /// `source` is `"cli"` or `"mcp"`.
 /// `action` is a verb like `create`, `rm`, `install`, etc.
 /// `details` is free-form context (env name, packages, etc.).
 ///
-/// Automatically trims the log to [`MAX_LINES`] when it grows past threshold.
+/// Rotates the log once it grows past the size cap.
 pub fn log_activity(source: &str, action: &str, details: &str) {
     let path = log_path();
-    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-    let line = format!("{} [{}] {} {}\n", now, source, action, details);
+    let entry = LogEntry {
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        source: source.to_string(),
+        action: action.to_string(),
+        detail: details.to_string(),
+    };
+    let mut line = if JSON_FORMAT.load(Ordering::Relaxed) {
+        serde_json::to_string(&entry).unwrap_or_else(|_| entry.to_text())
+    } else {
+        entry.to_text()
+    };
+    line.push('\n');
 
     if let Ok(mut f) = OpenOptions::new().create(true).append(true).open(&path) {
         let _ = f.write_all(line.as_bytes());
     }
 
-    // Rotate when file exceeds ~100KB (avg ~80 bytes/line × 1000 ≈ 80KB)
     if let Ok(meta) = std::fs::metadata(&path)
-        && meta.len() > 100_000
+        && meta.len() > MAX_BYTES.load(Ordering::Relaxed)
     {
-        rotate(&path);
+        rotate_file(&path);
     }
 }
 
-/// Keep only the last MAX_LINES lines in the log file.
-fn rotate(path: &PathBuf) {
-    if let Ok(content) = std::fs::read_to_string(path) {
-        let lines: Vec<&str> = content.lines().collect();
-        if lines.len() > MAX_LINES {
-            let keep = &lines[lines.len() - MAX_LINES..];
-            let _ = std::fs::write(path, keep.join("\n") + "\n");
+/// Rotates the log now; returns false when there was nothing to rotate.
+pub fn rotate() -> bool {
+    rotate_file(&log_path())
+}
+
+/// Shifts `zen.log` → `zen.log.1` → `zen.log.2`, dropping the oldest.
+fn rotate_file(path: &Path) -> bool {
+    if std::fs::metadata(path).map(|m| m.len()).unwrap_or(0) == 0 {
+        return false;
+    }
+    for n in (1..GENERATIONS).rev() {
+        let from = generation_path(path, n);
+        if from.exists() {
+            let _ = std::fs::rename(&from, generation_path(path, n + 1));
         }
     }
+    std::fs::rename(path, generation_path(path, 1)).is_ok()
 }
 
-/// Delete the log file contents.
+/// Delete the log file contents, rotated generations included.
 pub fn clear_log() {
-    let _ = std::fs::write(log_path(), "");
+    let path = log_path();
+    let _ = std::fs::write(&path, "");
+    for n in 1..=GENERATIONS {
+        let _ = std::fs::remove_file(generation_path(&path, n));
+    }
+}
+
+/// Which entries [`read_entries`] returns. Unset fields match everything.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LogFilter<'a> {
+    /// Substring of the entry's text form, case-insensitive.
+    pub keyword: Option<&'a str>,
+    /// Exact origin: `cli` or `mcp`.
+    pub source: Option<&'a str>,
+    /// Action, including its sub-actions (`install` matches `install:retry`).
+    pub action: Option<&'a str>,
 }
 
-/// Read the last `n` lines from the log, optionally filtering by a keyword.
-pub fn read_log(n: usize, filter: Option<&str>) -> Vec<String> {
+impl LogFilter<'_> {
+    fn matches(&self, entry: &LogEntry) -> bool {
+        let source_ok = self
+            .source
+            .is_none_or(|s| entry.source.eq_ignore_ascii_case(s));
+        let action_ok = self.action.is_none_or(|a| {
+            let action = entry.action.to_lowercase();
+            let a = a.to_lowercase();
+            action == a || action.starts_with(&format!("{}:", a))
+        });
+        let keyword_ok = self
+            .keyword
+            .is_none_or(|k| entry.to_text().to_lowercase().contains(&k.to_lowercase()));
+        source_ok && action_ok && keyword_ok
+    }
+}
+
+/// Read the last `n` entries from the log that pass `filter`.
+///
+/// Both formats are parsed, so a log that switched `log_format` midway reads
+/// as one. Rotated generations are read, newest first, only while the live
+/// log holds fewer than `n` matches.
+pub fn read_entries(n: usize, filter: &LogFilter) -> Vec<LogEntry> {
     let path = log_path();
-    let content = std::fs::read_to_string(&path).unwrap_or_default();
-    let lines: Vec<&str> = content.lines().collect();
-
-    let filtered: Vec<String> = if let Some(keyword) = filter {
-        let kw = keyword.to_lowercase();
-        lines
-            .iter()
-            .filter(|l| l.to_lowercase().contains(&kw))
-            .map(|l| l.to_string())
-            .collect()
-    } else {
-        lines.iter().map(|l| l.to_string()).collect()
-    };
+    let mut entries: Vec<LogEntry> = Vec::new();
+
+    for generation in 0..=GENERATIONS {
+        if entries.len() >= n {
+            break;
+        }
+        let file = match generation {
+            0 => path.clone(),
+            g => generation_path(&path, g),
+        };
+        let content = std::fs::read_to_string(&file).unwrap_or_default();
+        let mut older: Vec<LogEntry> = content
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(LogEntry::parse)
+            .filter(|e| filter.matches(e))
+            .collect();
+        older.append(&mut entries);
+        entries = older;
+    }
 
     // Return last N
-    let skip = filtered.len().saturating_sub(n);
-    filtered.into_iter().skip(skip).collect()
+    let skip = entries.len().saturating_sub(n);
+    entries.into_iter().skip(skip).collect()
+}
+
+/// Last `n` entries matching a keyword, rendered as text lines whatever the
+/// on-disk format.
+pub fn read_log(n: usize, keyword: Option<&str>) -> Vec<String> {
+    let filter = LogFilter {
+        keyword,
+        ..Default::default()
+    };
+    read_entries(n, &filter)
+        .iter()
+        .map(LogEntry::to_text)
+        .collect()
 }
@@ -5,13 +5,27 @@
 //! This module handles all interactions with the SQLite database, including
 //! schema initialization, environment registry, project-environment association,
 //! template storage, and project history (chat) logging.
-use rusqlite::{Connection, OptionalExtension, params};
+use rusqlite::{Connection, MAIN_DB, OpenFlags, OptionalExtension, Transaction, params};
+use std::collections::HashMap;
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
+/// A template package row: (package_name, version, is_pinned, install_type,
+/// install_args, step, extras, version_spec).
+pub type TemplatePackageRow = (
+    String,
+    String,
+    bool,
+    String,
+    Option<String>,
+    i64,
+    Option<String>,
+    Option<String>,
+);
+
 /// Metadata for a single package in an environment.
 #[derive(Debug, Clone, Default)]
 pub struct PackageMetadata {
@@ -20,10 +34,75 @@ pub struct PackageMetadata {
     pub installer: Option<String>,      // uv, pip
     pub install_source: Option<String>, // pypi, git, local
     pub is_editable: bool,
-    pub source_url: Option<String>,  // git URL or file path
-    pub commit_id: Option<String>,   // for git installs
-    pub import_name: Option<String>, // primary Python import name (only if differs from pip name)
-    pub installed_at: Option<i64>,   // epoch seconds from .dist-info mtime
+    pub source_url: Option<String>, // git URL or file path
+    pub commit_id: Option<String>,  // for git installs
+    pub import_names: Vec<String>,  // importable top-level modules (empty if just the pip name)
+    pub installed_at: Option<i64>,  // epoch seconds from .dist-info mtime
+}
+
+/// One package captured by an environment snapshot.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotPackage {
+    pub name: String,
+    pub version: String,
+    /// What reinstalls this exact build: `name==version`, a direct
+    /// reference for git/local installs, or `-e path` for editables.
+    pub requirement: String,
+}
+
+/// Dependent rows cleaned up when an environment is deleted.
+///
+/// Links and labels cascade away; notes survive with their env_id set NULL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnvCleanup {
+    pub links: usize,
+    pub labels: usize,
+    pub notes_orphaned: usize,
+}
+
+impl EnvCleanup {
+    /// Short human summary, e.g. `3 links, 2 labels cleaned`. `None` if nothing was touched.
+    pub fn describe(&self) -> Option<String> {
+        let plural =
+            |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+        let mut parts = Vec::new();
+        if self.links > 0 {
+            parts.push(plural(self.links, "link"));
+        }
+        if self.labels > 0 {
+            parts.push(plural(self.labels, "label"));
+        }
+        let mut out = if parts.is_empty() {
+            String::new()
+        } else {
+            format!("{} cleaned", parts.join(", "))
+        };
+        if self.notes_orphaned > 0 {
+            if !out.is_empty() {
+                out.push_str(", ");
+            }
+            out.push_str(&format!("{} orphaned", plural(self.notes_orphaned, "note")));
+        }
+        (!out.is_empty()).then_some(out)
+    }
+}
+
+/// Counts rows in `table` that reference an environment.
+fn count_for_env(conn: &Connection, table: &str, env_id: i64) -> Result<usize> {
+    let count: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM {} WHERE env_id = ?1", table),
+        params![env_id],
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+/// Escapes `\`, `%` and `_` for a `LIKE ... ESCAPE '\'` pattern, so they
+/// match literally instead of acting as wildcards.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 /// The central database handle for Zen.
@@ -34,25 +113,136 @@ pub struct Database {
     conn: Arc<Mutex<Connection>>,
 }
 
+/// Default database location: `~/.config/zen/zen.db`.
+///
+/// Single source of truth for the path — `open`, `zen reset` and `zen status`
+/// all resolve through here (or through the `--db-path`/`ZEN_DOJO` override).
+pub fn default_db_path() -> PathBuf {
+    PathBuf::from(std::env::var("HOME").unwrap_or_default())
+        .join(".config")
+        .join("zen")
+        .join("zen.db")
+}
+
+/// Normalizes a project directory the way links are stored and looked up.
+///
+/// Existing paths are canonicalized (symlinks resolved). Paths that don't
+/// exist (any more) are made absolute against the current directory and
+/// cleaned lexically, so `./proj/`, `proj` and `/abs/proj` all compare equal.
+pub fn normalize_project_path(path: &str) -> String {
+    if let Ok(canonical) = Path::new(path).canonicalize() {
+        return canonical.to_string_lossy().to_string();
+    }
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+    let mut cleaned = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                cleaned.pop();
+            }
+            c => cleaned.push(c),
+        }
+    }
+    cleaned.to_string_lossy().to_string()
+}
+
+/// Puts a pre-upgrade backup back in place of the database file.
+///
+/// The connection must be closed first. WAL side files belong to the failed
+/// upgrade and are removed so SQLite doesn't replay them onto the backup.
+fn restore_backup(backup: &Path, db_path: &Path) -> Result<()> {
+    for suffix in ["-wal", "-shm"] {
+        let mut side = db_path.as_os_str().to_owned();
+        side.push(suffix);
+        std::fs::remove_file(PathBuf::from(side)).ok();
+    }
+    std::fs::copy(backup, db_path)
+        .map_err(|e| format!("Restoring {} failed: {}", backup.display(), e))?;
+    Ok(())
+}
+
+/// Configuration keys Zen reads. `zen config` warns on anything else.
+pub const KNOWN_CONFIG_KEYS: &[&str] = &[
+    "default_python",
+    "env_home",
+    "install_retries",
+    "log_format",
+    "log_max_size",
+    "mode",
+    "prefer_uv",
+    "schema_version",
+    "stack_info",
+    "theme",
+];
+
 /// Current schema version. Increment when making schema changes.
 /// - v1: Initial schema
 /// - v2: Added project_environments, comments tables (v0.3.0)
 /// - v3: Added labels table, removed dead tables
 /// - v4: Added activation history columns to project_environments (v0.6.5)
-const SCHEMA_VERSION: i32 = 4;
+/// - v5: Added packages / package_index tables for indexed `find`
+/// - v6: Added env_vars table for per-environment variables
+/// - v7: Added install_groups table for `zen install --group`
+/// - v8: Added environments.last_used_at (activate/run/install)
+/// - v9: Added environments.cuda_variant (PyTorch index the env was built against)
+/// - v10: Added aliases table for short environment names
+/// - v11: Added snapshots table for `zen snapshot`
+/// - v12: Backfilled environments.last_used_at from project activation history
+/// - v13: Added environments.source_template (templates an env was created from)
+const SCHEMA_VERSION: i32 = 13;
+
+/// A schema migration step: `(from_version, step)`.
+///
+/// The step runs in its own transaction, which also records `from_version + 1`
+/// as the schema version, so a failed step leaves the database at the last
+/// version that fully applied.
+type Migration = (i32, fn(&Transaction) -> Result<()>);
+
+/// Migrations that rewrite or backfill existing data, in version order.
+///
+/// New tables and columns stay additive in `init_schema`, which runs first,
+/// so a step can rely on the current table shapes. Databases from before the
+/// runner existed start at their stored version and pick up every later step.
+const MIGRATIONS: &[Migration] = &[(11, backfill_last_used_at)];
+
+/// v12: environments activated through project links before `last_used_at`
+/// existed take their most recent activation as last use.
+fn backfill_last_used_at(tx: &Transaction) -> Result<()> {
+    tx.execute(
+        "UPDATE environments SET last_used_at = (
+             SELECT MAX(pe.last_activated_at) FROM project_environments pe
+             WHERE pe.env_id = environments.id
+         )
+         WHERE last_used_at IS NULL",
+        [],
+    )?;
+    Ok(())
+}
 
 impl Database {
     /// Opens the Zen database at the specified path, or the default `~/.config/zen/zen.db`.
     ///
     /// Automatically initializes the schema if necessary.
     pub fn open(custom_path: Option<&Path>) -> Result<Self> {
-        let db_path = if let Some(path) = custom_path {
-            path.to_path_buf()
-        } else {
-            let config_dir = std::path::PathBuf::from(std::env::var("HOME").unwrap_or_default())
-                .join(".config/zen");
-            std::fs::create_dir_all(&config_dir).ok();
-            config_dir.join("zen.db")
+        Self::open_with_migrations(custom_path, MIGRATIONS)
+    }
+
+    /// [`Database::open`] with an explicit migration list.
+    ///
+    /// An existing database older than [`SCHEMA_VERSION`] is copied to
+    /// `<file>.bak-v<old>` before anything touches it, and copied back if the
+    /// upgrade fails.
+    fn open_with_migrations(custom_path: Option<&Path>, migrations: &[Migration]) -> Result<Self> {
+        let db_path = match custom_path {
+            Some(path) => path.to_path_buf(),
+            None => {
+                let path = default_db_path();
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir).ok();
+                }
+                path
+            }
         };
         let conn = Connection::open(&db_path)?;
 
@@ -68,25 +258,143 @@ impl Database {
         let db = Database {
             conn: Arc::new(Mutex::new(conn)),
         };
-        db.init_schema()?;
-        db.check_schema_version()?;
+        // None: a brand-new file, nothing to back up or upgrade
+        let stored_version = db.stored_schema_version();
+        let backup = match stored_version {
+            Some(version) if version < SCHEMA_VERSION => {
+                eprintln!(
+                    "⚠️  Database schema outdated (v{} → v{}). Upgrading...",
+                    version, SCHEMA_VERSION
+                );
+                Some(db.backup_before_upgrade(&db_path, version)?)
+            }
+            _ => None,
+        };
+
+        let upgraded = db.init_schema().and_then(|_| {
+            if stored_version.is_none() {
+                db.set_config("schema_version", &SCHEMA_VERSION.to_string())?;
+            }
+            db.check_schema_version(migrations)
+        });
+        if let Err(e) = upgraded {
+            let Some(backup) = backup else {
+                return Err(e);
+            };
+            drop(db);
+            restore_backup(&backup, &db_path)?;
+            return Err(format!("{} (database restored from {})", e, backup.display()).into());
+        }
+        Ok(db)
+    }
+
+    /// The schema version of an existing database, or `None` for a new file.
+    fn stored_schema_version(&self) -> Option<i32> {
+        let conn = self.conn.lock().unwrap();
+        let has_config: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'configuration'",
+                [],
+                |row| row.get::<_, i32>(0),
+            )
+            .map(|n| n > 0)
+            .unwrap_or(false);
+        if !has_config {
+            return None;
+        }
+        let version: Option<String> = conn
+            .query_row(
+                "SELECT value FROM configuration WHERE key = 'schema_version'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten();
+        Some(version.and_then(|v| v.parse().ok()).unwrap_or(1))
+    }
+
+    /// Copies the database to `<file>.bak-v<version>` ahead of a schema upgrade.
+    fn backup_before_upgrade(&self, db_path: &Path, version: i32) -> Result<PathBuf> {
+        let mut name = db_path.as_os_str().to_owned();
+        name.push(format!(".bak-v{}", version));
+        let backup = PathBuf::from(name);
+        // VACUUM INTO refuses to overwrite; a leftover from an earlier attempt is stale
+        std::fs::remove_file(&backup).ok();
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute(
+                "VACUUM INTO ?1",
+                params![backup.to_string_lossy().to_string()],
+            )?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&backup, std::fs::Permissions::from_mode(0o600)).ok();
+        }
+        eprintln!("  Backed up the database to {}", backup.display());
+        Ok(backup)
+    }
+
+    /// Opens the database without write access, for read-only commands.
+    ///
+    /// Skips schema setup, so it fails when the file is missing or needs an
+    /// upgrade; callers fall back to [`Database::open`] in that case.
+    pub fn open_readonly(custom_path: Option<&Path>) -> Result<Self> {
+        let db_path = custom_path
+            .map(Path::to_path_buf)
+            .unwrap_or_else(default_db_path);
+        let conn = Connection::open_with_flags(
+            &db_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        let db = Database {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        let version = db.schema_version()?;
+        if version != SCHEMA_VERSION {
+            return Err(format!("Database schema v{} needs a read-write open", version).into());
+        }
+        Ok(db)
+    }
+
+    /// Whether this handle was opened with [`Database::open_readonly`].
+    pub fn is_read_only(&self) -> bool {
+        let conn = self.conn.lock().unwrap();
+        conn.is_readonly(MAIN_DB).unwrap_or(false)
+    }
+
+    /// A handle that can write to the same database file.
+    ///
+    /// Shares the connection when it is already writable; a read-only handle
+    /// opens a second, read-write connection. Fails if the file can't be written.
+    pub fn writable(&self) -> Result<Database> {
+        if !self.is_read_only() {
+            return Ok(Database {
+                conn: Arc::clone(&self.conn),
+            });
+        }
+        let path = {
+            let conn = self.conn.lock().unwrap();
+            conn.path().map(PathBuf::from)
+        };
+        let path = path.ok_or("Database has no file path")?;
+        let db = Database::open(Some(&path))?;
+        if db.is_read_only() {
+            return Err(format!("Database '{}' is not writable", path.display()).into());
+        }
         Ok(db)
     }
 
     /// Check and handle schema version mismatch
-    fn check_schema_version(&self) -> Result<()> {
-        let stored_version = self
-            .get_config("schema_version")?
-            .and_then(|v| v.parse::<i32>().ok())
-            .unwrap_or(1); // Assume v1 if not set
+    fn check_schema_version(&self, migrations: &[Migration]) -> Result<()> {
+        let stored_version = self.schema_version()?; // Assume v1 if not set
 
         if stored_version < SCHEMA_VERSION {
-            eprintln!(
-                "⚠️  Database schema outdated (v{} → v{}). Upgrading...",
-                stored_version, SCHEMA_VERSION
-            );
-            // Auto-migrate: currently all migrations are additive (CREATE TABLE IF NOT EXISTS)
-            // so init_schema already handles them. Just update version.
+            // init_schema already applied the additive changes; run the data steps,
+            // then stamp the version for upgrades that were additive only
+            self.run_migrations(stored_version, migrations)?;
             self.set_config("schema_version", &SCHEMA_VERSION.to_string())?;
             eprintln!("✓ Schema upgraded to v{}.", SCHEMA_VERSION);
         } else if stored_version > SCHEMA_VERSION {
@@ -101,6 +409,65 @@ impl Database {
         Ok(())
     }
 
+    /// Applies each migration at or above `stored_version`, in order.
+    ///
+    /// Returns the version reached. Steps below `stored_version` were applied by
+    /// an earlier upgrade and are skipped.
+    fn run_migrations(&self, stored_version: i32, migrations: &[Migration]) -> Result<i32> {
+        let mut version = stored_version;
+        let mut conn = self.conn.lock().unwrap();
+        for (from, step) in migrations {
+            if *from < version {
+                continue;
+            }
+            let tx = conn.transaction()?;
+            step(&tx)
+                .map_err(|e| format!("Schema migration v{} → v{} failed: {}", from, from + 1, e))?;
+            version = from + 1;
+            tx.execute(
+                "INSERT OR REPLACE INTO configuration (key, value) VALUES ('schema_version', ?1)",
+                params![version.to_string()],
+            )?;
+            tx.commit()?;
+        }
+        Ok(version)
+    }
+
+    /// Rebuilds the database file to drop free pages, then truncates the WAL.
+    pub fn vacuum(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("VACUUM")?;
+        let _: (i64, i64, i64) = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })?;
+        Ok(())
+    }
+
+    /// Runs `PRAGMA integrity_check` and returns the problems it reports.
+    ///
+    /// An empty list means the database is healthy.
+    pub fn integrity_check(&self) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut problems = Vec::new();
+        for row in rows {
+            let row = row?;
+            if row != "ok" {
+                problems.push(row);
+            }
+        }
+        Ok(problems)
+    }
+
+    /// Returns the schema version recorded in the database.
+    pub fn schema_version(&self) -> Result<i32> {
+        Ok(self
+            .get_config("schema_version")?
+            .and_then(|v| v.parse::<i32>().ok())
+            .unwrap_or(1))
+    }
+
     /// Initializes all database tables and runs additive migrations.
     fn init_schema(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -137,15 +504,19 @@ impl Database {
             "ALTER TABLE environments ADD COLUMN is_favorite INTEGER DEFAULT 0",
             [],
         );
-        // Migration: Add install_args column for pip arguments (--index-url, etc.)
+
+        // v8: Last activate/run/install, for `zen list --sort recent`
         let _ = conn.execute(
-            "ALTER TABLE template_packages ADD COLUMN install_args TEXT",
+            "ALTER TABLE environments ADD COLUMN last_used_at DATETIME",
             [],
         );
 
-        // v0.7.0: Add step column for layer tracking (Docker-style)
+        // v9: PyTorch build variant ("cu130", "cpu"), reused by later installs
+        let _ = conn.execute("ALTER TABLE environments ADD COLUMN cuda_variant TEXT", []);
+
+        // v13: `name:version[,name:version]` of the templates `zen create --from` applied
         let _ = conn.execute(
-            "ALTER TABLE template_packages ADD COLUMN step INTEGER DEFAULT 0",
+            "ALTER TABLE environments ADD COLUMN source_template TEXT",
             [],
         );
 
@@ -215,6 +586,29 @@ impl Database {
             [],
         )?;
 
+        // Column migrations must run after the CREATE, or a fresh database
+        // only gains these columns on its second open.
+        // Migration: Add install_args column for pip arguments (--index-url, etc.)
+        let _ = conn.execute(
+            "ALTER TABLE template_packages ADD COLUMN install_args TEXT",
+            [],
+        );
+
+        // v0.7.0: Add step column for layer tracking (Docker-style)
+        let _ = conn.execute(
+            "ALTER TABLE template_packages ADD COLUMN step INTEGER DEFAULT 0",
+            [],
+        );
+
+        // Requested extras ("cuda,audio" for `pkg[cuda,audio]`), re-applied on install
+        let _ = conn.execute("ALTER TABLE template_packages ADD COLUMN extras TEXT", []);
+
+        // Version specifier (">=1.26,<2"); applied instead of the frozen `version` when set
+        let _ = conn.execute(
+            "ALTER TABLE template_packages ADD COLUMN version_spec TEXT",
+            [],
+        );
+
         conn.execute(
             "CREATE TABLE IF NOT EXISTS active_sessions (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -230,99 +624,617 @@ impl Database {
         conn.execute_batch("ALTER TABLE active_sessions ADD COLUMN pid INTEGER")
             .ok(); // silently ignore if column already exists
 
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS config (
-                key TEXT PRIMARY KEY,
-                value TEXT
-            )",
-            [],
-        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS config (
+                key TEXT PRIMARY KEY,
+                value TEXT
+            )",
+            [],
+        )?;
+
+        // Labels table for environment tagging (v0.5.0)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS labels (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                env_id INTEGER NOT NULL,
+                label TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE(env_id, label),
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_labels_env ON labels(env_id)",
+            [],
+        )?;
+
+        // v5: Package index — a cache of each env's site-packages so `find`
+        // can answer with one query instead of scanning every env on disk.
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS packages (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                env_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                version TEXT,
+                installed_at INTEGER,
+                UNIQUE(env_id, name),
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_packages_name ON packages(name)",
+            [],
+        )?;
+
+        // One row per indexed env: site-packages mtime at scan time (freshness key)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS package_index (
+                env_id INTEGER PRIMARY KEY,
+                site_mtime INTEGER,
+                indexed_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // v6: Per-environment variables, exported on activation and injected by `zen run`
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS env_vars (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                env_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                UNIQUE(env_id, key),
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // v7: Named install groups ("dev", "runtime") within an environment
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS install_groups (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                env_id INTEGER NOT NULL,
+                group_name TEXT NOT NULL,
+                package_name TEXT NOT NULL,
+                UNIQUE(env_id, group_name, package_name),
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // v10: Short alternative names for environments ("px" → "project-x-torch")
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS aliases (
+                alias TEXT PRIMARY KEY,
+                env_id INTEGER NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // v11: Package-set snapshots of an environment, restorable by label
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                env_id INTEGER NOT NULL,
+                label TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                packages TEXT NOT NULL,
+                UNIQUE(env_id, label),
+                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // v4: Activation history columns (safe to re-run — ALTER ignores existing columns)
+        // SQLite doesn't support IF NOT EXISTS for ALTER, so we check pragma first
+        let has_link_type: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('project_environments') WHERE name = 'link_type'",
+                [],
+                |row| row.get::<_, i32>(0),
+            )
+            .unwrap_or(0) > 0;
+
+        if !has_link_type {
+            conn.execute_batch(
+                "ALTER TABLE project_environments ADD COLUMN link_type TEXT DEFAULT 'user';
+                 ALTER TABLE project_environments ADD COLUMN last_activated_at DATETIME;
+                 ALTER TABLE project_environments ADD COLUMN activation_count INTEGER DEFAULT 0;",
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a new environment in the database.
+    ///
+    /// If an environment with the same name already exists, it is updated.
+    pub fn register_env(&self, name: &str, path: &str, python_version: &str) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO environments (name, path, python_version, updated_at)
+             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+            params![name, path, python_version],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Logs a package installation event to the audit log.
+    pub fn log_package(
+        &self,
+        env_id: i64,
+        name: &str,
+        version: &str,
+        install_type: &str,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO audit_log (env_id, package_name, version, install_type)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![env_id, name, version, install_type],
+        )?;
+        Ok(())
+    }
+
+    /// Distinct package names recorded in the audit log for an environment,
+    /// i.e. the ones installed explicitly through zen.
+    pub fn get_logged_packages(&self, env_id: i64) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT package_name FROM audit_log WHERE env_id = ?1 ORDER BY package_name",
+        )?;
+        let names = stmt
+            .query_map(params![env_id], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(names)
+    }
+
+    /// Gets the database ID for an environment by name.
+    pub fn get_env_id(&self, name: &str) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id FROM environments WHERE name = ?1")?;
+        let mut rows = stmt.query(params![name])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(row.get(0)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Deletes an environment from the database.
+    ///
+    /// Dependent rows are counted in the same transaction as the delete, so the
+    /// returned `EnvCleanup` reflects exactly what the cascade removed.
+    pub fn delete_env(&self, name: &str) -> Result<EnvCleanup> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let env_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM environments WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let cleanup = match env_id {
+            Some(id) => EnvCleanup {
+                links: count_for_env(&tx, "project_environments", id)?,
+                labels: count_for_env(&tx, "labels", id)?,
+                notes_orphaned: count_for_env(&tx, "comments", id)?,
+            },
+            None => EnvCleanup::default(),
+        };
+        tx.execute("DELETE FROM environments WHERE name = ?1", params![name])?;
+        tx.commit()?;
+        Ok(cleanup)
+    }
+
+    /// Number of project links for an environment.
+    pub fn count_env_links(&self, env_id: i64) -> Result<usize> {
+        count_for_env(&self.conn.lock().unwrap(), "project_environments", env_id)
+    }
+
+    /// Number of labels attached to an environment.
+    pub fn count_env_labels(&self, env_id: i64) -> Result<usize> {
+        count_for_env(&self.conn.lock().unwrap(), "labels", env_id)
+    }
+
+    /// Number of notes (comments) attached to an environment.
+    pub fn count_env_comments(&self, env_id: i64) -> Result<usize> {
+        count_for_env(&self.conn.lock().unwrap(), "comments", env_id)
+    }
+
+    // =========================================================================
+    // Package index (v5)
+    // =========================================================================
+
+    /// Replaces the indexed package list for an environment.
+    ///
+    /// `site_mtime` is the site-packages mtime observed when `packages` was
+    /// scanned; `get_package_index_mtime` returns it to decide freshness.
+    pub fn index_packages(
+        &self,
+        env_name: &str,
+        site_mtime: Option<i64>,
+        packages: &[PackageMetadata],
+    ) -> Result<()> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM packages WHERE env_id = ?1", params![env_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO packages (env_id, name, version, installed_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for pkg in packages {
+                stmt.execute(params![env_id, pkg.name, pkg.version, pkg.installed_at])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO package_index (env_id, site_mtime, indexed_at)
+             VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+            params![env_id, site_mtime],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns the site-packages mtime recorded at the last index, if indexed.
+    pub fn get_package_index_mtime(&self, env_name: &str) -> Result<Option<i64>> {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .query_row(
+                "SELECT pi.site_mtime FROM package_index pi
+                 JOIN environments e ON pi.env_id = e.id
+                 WHERE e.name = ?1",
+                params![env_name],
+                |row| row.get::<_, Option<i64>>(0),
+            )
+            .optional()?;
+        Ok(result.flatten())
+    }
+
+    /// Searches the package index across all environments.
+    ///
+    /// Names are compared pip-style (case-insensitive, `-` == `_`). With
+    /// `exact` the whole name must match, otherwise `pattern` is a substring.
+    /// Returns (env_name, package_name, version) sorted by env then package.
+    pub fn search_packages(
+        &self,
+        pattern: &str,
+        exact: bool,
+    ) -> Result<Vec<(String, String, Option<String>)>> {
+        let escaped = escape_like(&pattern.to_lowercase().replace('-', "_"));
+        let like = if exact {
+            escaped
+        } else {
+            format!("%{}%", escaped)
+        };
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT e.name, p.name, p.version FROM packages p
+             JOIN environments e ON p.env_id = e.id
+             WHERE REPLACE(LOWER(p.name), '-', '_') LIKE ?1 ESCAPE '\\'
+             ORDER BY e.name, LOWER(p.name)",
+        )?;
+        let rows = stmt
+            .query_map(params![like], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+
+    // =========================================================================
+    // Environment variables (v6)
+    // =========================================================================
+
+    /// Sets (or replaces) a variable for an environment.
+    pub fn set_env_var(&self, env_name: &str, key: &str, value: &str) -> Result<()> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO env_vars (env_id, key, value) VALUES (?1, ?2, ?3)",
+            params![env_id, key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Gets a single variable for an environment.
+    pub fn get_env_var(&self, env_name: &str, key: &str) -> Result<Option<String>> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let conn = self.conn.lock().unwrap();
+        let value = conn
+            .query_row(
+                "SELECT value FROM env_vars WHERE env_id = ?1 AND key = ?2",
+                params![env_id, key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value)
+    }
+
+    /// Removes a variable. Returns true if it existed.
+    pub fn unset_env_var(&self, env_name: &str, key: &str) -> Result<bool> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute(
+            "DELETE FROM env_vars WHERE env_id = ?1 AND key = ?2",
+            params![env_id, key],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Lists all variables for an environment as (key, value), sorted by key.
+    pub fn list_env_vars(&self, env_name: &str) -> Result<Vec<(String, String)>> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT key, value FROM env_vars WHERE env_id = ?1 ORDER BY key")?;
+        let vars = stmt
+            .query_map(params![env_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(vars)
+    }
+
+    // =========================================================================
+    // Aliases (v10)
+    // =========================================================================
 
-        // Labels table for environment tagging (v0.5.0)
+    /// Points `alias` at an environment. Aliases can't shadow environment names
+    /// and aren't silently re-pointed.
+    pub fn add_alias(&self, alias: &str, env_name: &str) -> Result<()> {
+        if self.get_env_id(alias)?.is_some() {
+            return Err(format!("'{}' is already an environment name", alias).into());
+        }
+        if let Some(existing) = self.resolve_alias(alias)? {
+            return Err(format!(
+                "Alias '{}' already points to '{}' (remove it first)",
+                alias, existing
+            )
+            .into());
+        }
+        let env_id = self
+            .get_env_id(env_name)?
+            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+        let conn = self.conn.lock().unwrap();
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS labels (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                env_id INTEGER NOT NULL,
-                label TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                UNIQUE(env_id, label),
-                FOREIGN KEY(env_id) REFERENCES environments(id) ON DELETE CASCADE
-            )",
-            [],
+            "INSERT INTO aliases (alias, env_id) VALUES (?1, ?2)",
+            params![alias, env_id],
         )?;
+        Ok(())
+    }
 
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_labels_env ON labels(env_id)",
-            [],
+    /// Removes an alias; returns whether it existed.
+    pub fn remove_alias(&self, alias: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let removed = conn.execute("DELETE FROM aliases WHERE alias = ?1", params![alias])?;
+        Ok(removed > 0)
+    }
+
+    /// All aliases as (alias, env_name), sorted by alias.
+    pub fn list_aliases(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT a.alias, e.name FROM aliases a
+             JOIN environments e ON a.env_id = e.id
+             ORDER BY a.alias",
         )?;
+        let aliases = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(aliases)
+    }
 
-        // v4: Activation history columns (safe to re-run — ALTER ignores existing columns)
-        // SQLite doesn't support IF NOT EXISTS for ALTER, so we check pragma first
-        let has_link_type: bool = conn
+    /// The environment an alias stands for.
+    ///
+    /// `None` when `name` isn't an alias, or when an environment now carries
+    /// that exact name (real names always win).
+    pub fn resolve_alias(&self, name: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let target = conn
             .query_row(
-                "SELECT COUNT(*) FROM pragma_table_info('project_environments') WHERE name = 'link_type'",
-                [],
-                |row| row.get::<_, i32>(0),
+                "SELECT e.name FROM aliases a
+                 JOIN environments e ON a.env_id = e.id
+                 WHERE a.alias = ?1
+                   AND NOT EXISTS (SELECT 1 FROM environments WHERE name = ?1)",
+                params![name],
+                |row| row.get(0),
             )
-            .unwrap_or(0) > 0;
+            .optional()?;
+        Ok(target)
+    }
 
-        if !has_link_type {
-            conn.execute_batch(
-                "ALTER TABLE project_environments ADD COLUMN link_type TEXT DEFAULT 'user';
-                 ALTER TABLE project_environments ADD COLUMN last_activated_at DATETIME;
-                 ALTER TABLE project_environments ADD COLUMN activation_count INTEGER DEFAULT 0;",
-            )?;
-        }
+    /// Aliases pointing at one environment, sorted.
+    pub fn get_env_aliases(&self, env_name: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT a.alias FROM aliases a
+             JOIN environments e ON a.env_id = e.id
+             WHERE e.name = ?1
+             ORDER BY a.alias",
+        )?;
+        let aliases = stmt
+            .query_map(params![env_name], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(aliases)
+    }
+
+    // =========================================================================
+    // Snapshots (v11)
+    // =========================================================================
 
+    /// Stores an environment's package set under `label`. Labels are unique per
+    /// environment and never overwritten.
+    pub fn create_snapshot(
+        &self,
+        env_name: &str,
+        label: &str,
+        packages: &[SnapshotPackage],
+    ) -> Result<()> {
+        let env_id = self
+            .get_env_id(env_name)?
+            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+        let json = serde_json::to_string(packages)?;
+        let conn = self.conn.lock().unwrap();
+        let exists: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM snapshots WHERE env_id = ?1 AND label = ?2",
+            params![env_id, label],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Err(format!("Snapshot '{}' already exists for '{}'", label, env_name).into());
+        }
+        conn.execute(
+            "INSERT INTO snapshots (env_id, label, packages) VALUES (?1, ?2, ?3)",
+            params![env_id, label, json],
+        )?;
         Ok(())
     }
 
-    /// Registers a new environment in the database.
-    ///
-    /// If an environment with the same name already exists, it is updated.
-    pub fn register_env(&self, name: &str, path: &str, python_version: &str) -> Result<i64> {
+    /// Snapshots of an environment as (label, created_at, package_count), oldest first.
+    pub fn list_snapshots(&self, env_name: &str) -> Result<Vec<(String, String, usize)>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT OR REPLACE INTO environments (name, path, python_version, updated_at)
-             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
-            params![name, path, python_version],
+        let mut stmt = conn.prepare(
+            "SELECT s.label, s.created_at, s.packages FROM snapshots s
+             JOIN environments e ON s.env_id = e.id
+             WHERE e.name = ?1
+             ORDER BY s.created_at, s.id",
         )?;
-        Ok(conn.last_insert_rowid())
+        let snapshots = stmt
+            .query_map(params![env_name], |row| {
+                let packages: String = row.get(2)?;
+                let count = serde_json::from_str::<Vec<SnapshotPackage>>(&packages)
+                    .map(|p| p.len())
+                    .unwrap_or(0);
+                Ok((row.get(0)?, row.get(1)?, count))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(snapshots)
     }
 
-    /// Logs a package installation event to the audit log.
-    pub fn log_package(
+    /// Packages captured by a snapshot.
+    pub fn get_snapshot(
         &self,
-        env_id: i64,
-        name: &str,
-        version: &str,
-        install_type: &str,
+        env_name: &str,
+        label: &str,
+    ) -> Result<Option<Vec<SnapshotPackage>>> {
+        let conn = self.conn.lock().unwrap();
+        let json: Option<String> = conn
+            .query_row(
+                "SELECT s.packages FROM snapshots s
+                 JOIN environments e ON s.env_id = e.id
+                 WHERE e.name = ?1 AND s.label = ?2",
+                params![env_name, label],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(match json {
+            Some(json) => Some(serde_json::from_str(&json)?),
+            None => None,
+        })
+    }
+
+    // =========================================================================
+    // Install groups (v7)
+    // =========================================================================
+
+    /// Records packages as members of an environment's install group.
+    pub fn add_group_packages(
+        &self,
+        env_name: &str,
+        group: &str,
+        packages: &[String],
     ) -> Result<()> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for package in packages {
+            tx.execute(
+                "INSERT OR IGNORE INTO install_groups (env_id, group_name, package_name) VALUES (?1, ?2, ?3)",
+                params![env_id, group.to_lowercase(), package],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Members of one install group, sorted by name.
+    pub fn get_group_packages(&self, env_name: &str, group: &str) -> Result<Vec<String>> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "INSERT INTO audit_log (env_id, package_name, version, install_type)
-             VALUES (?1, ?2, ?3, ?4)",
-            params![env_id, name, version, install_type],
+        let mut stmt = conn.prepare(
+            "SELECT package_name FROM install_groups WHERE env_id = ?1 AND group_name = ?2 ORDER BY package_name",
         )?;
-        Ok(())
+        let packages = stmt
+            .query_map(params![env_id, group.to_lowercase()], |row| row.get(0))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(packages)
     }
 
-    /// Gets the database ID for an environment by name.
-    pub fn get_env_id(&self, name: &str) -> Result<Option<i64>> {
+    /// All install groups of an environment as (group, members), sorted by group.
+    pub fn list_groups(&self, env_name: &str) -> Result<Vec<(String, Vec<String>)>> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT id FROM environments WHERE name = ?1")?;
-        let mut rows = stmt.query(params![name])?;
-        if let Some(row) = rows.next()? {
-            Ok(Some(row.get(0)?))
-        } else {
-            Ok(None)
+        let mut stmt = conn.prepare(
+            "SELECT group_name, package_name FROM install_groups WHERE env_id = ?1 ORDER BY group_name, package_name",
+        )?;
+        let rows: Vec<(String, String)> = stmt
+            .query_map(params![env_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .filter_map(|r| r.ok())
+            .collect();
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for (group, package) in rows {
+            match groups.last_mut() {
+                Some((name, members)) if *name == group => members.push(package),
+                _ => groups.push((group, vec![package])),
+            }
         }
+        Ok(groups)
     }
 
-    /// Deletes an environment from the database.
-    pub fn delete_env(&self, name: &str) -> Result<()> {
+    /// Deletes an install group. Returns the number of members it had.
+    pub fn delete_group(&self, env_name: &str, group: &str) -> Result<usize> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM environments WHERE name = ?1", params![name])?;
+        let deleted = conn.execute(
+            "DELETE FROM install_groups WHERE env_id = ?1 AND group_name = ?2",
+            params![env_id, group.to_lowercase()],
+        )?;
+        Ok(deleted)
+    }
+
+    /// Drops uninstalled packages from every group of an environment.
+    pub fn remove_group_packages(&self, env_name: &str, packages: &[String]) -> Result<()> {
+        let env_id = self.get_env_id(env_name)?.ok_or("Environment not found")?;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        for package in packages {
+            tx.execute(
+                "DELETE FROM install_groups WHERE env_id = ?1 AND LOWER(REPLACE(package_name, '-', '_')) = LOWER(REPLACE(?2, '-', '_'))",
+                params![env_id, package],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
@@ -437,7 +1349,106 @@ impl Database {
         Ok(rows > 0)
     }
 
-    /// Lists all environments with basic info (name, path, python_version, updated_at, is_favorite).
+    /// Stars or unstars an environment. Returns false if it doesn't exist.
+    pub fn set_favorite(&self, name: &str, favorite: bool) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let rows = conn.execute(
+            "UPDATE environments SET is_favorite = ?1 WHERE name = ?2",
+            params![favorite as i32, name],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Replaces the stored Python version (e.g. once "unknown" gets resolved).
+    pub fn set_python_version(&self, name: &str, python_version: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE environments SET python_version = ?1 WHERE name = ?2",
+            params![python_version, name],
+        )?;
+        Ok(())
+    }
+
+    /// Marks an environment as used now (activate, run, install).
+    pub fn touch_env(&self, name: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE environments SET last_used_at = CURRENT_TIMESTAMP WHERE name = ?1",
+            params![name],
+        )?;
+        Ok(())
+    }
+
+    /// Records the PyTorch build variant ("cu130", "cpu") an environment uses.
+    pub fn set_cuda_variant(&self, name: &str, variant: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE environments SET cuda_variant = ?1 WHERE name = ?2",
+            params![variant, name],
+        )?;
+        Ok(())
+    }
+
+    /// The PyTorch build variant recorded for an environment, if any.
+    pub fn get_cuda_variant(&self, name: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let variant = conn
+            .query_row(
+                "SELECT cuda_variant FROM environments WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(variant.flatten())
+    }
+
+    /// Records the templates (`name:version[,name:version]`) an environment was created from.
+    pub fn set_source_template(&self, name: &str, templates: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE environments SET source_template = ?1 WHERE name = ?2",
+            params![templates, name],
+        )?;
+        Ok(())
+    }
+
+    /// The templates an environment was created from, if it was created from any.
+    pub fn get_source_template(&self, name: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        let source = conn
+            .query_row(
+                "SELECT source_template FROM environments WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(source.flatten())
+    }
+
+    /// Last-used timestamps by environment name; envs never used are absent.
+    pub fn get_last_used(&self) -> Result<HashMap<String, String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name, last_used_at FROM environments WHERE last_used_at IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut result = HashMap::new();
+        for row in rows {
+            let (name, ts) = row?;
+            result.insert(name, ts);
+        }
+        Ok(result)
+    }
+
+    /// Number of registered environments, without loading the rows.
+    pub fn count_envs(&self) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let count = conn.query_row("SELECT COUNT(*) FROM environments", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Lists all environments with basic info (name, path, python_version, updated_at, is_favorite),
+    /// ordered by name.
     pub fn list_envs(
         &self,
     ) -> Result<
@@ -451,7 +1462,8 @@ impl Database {
     > {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT name, path, python_version, updated_at, is_favorite FROM environments",
+            "SELECT name, path, python_version, updated_at, is_favorite FROM environments
+             ORDER BY name",
         )?;
         let rows = stmt.query_map([], |row| {
             let is_fav: i32 = row.get(4)?;
@@ -478,9 +1490,10 @@ impl Database {
         version: &str,
         python_version: &str,
     ) -> Result<(i64, bool)> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
         // Check if template already exists
-        let existing: Option<i64> = conn
+        let existing: Option<i64> = tx
             .query_row(
                 "SELECT id FROM templates WHERE name = ?1 AND version = ?2",
                 params![name, version],
@@ -489,18 +1502,21 @@ impl Database {
             .optional()?;
         if let Some(id) = existing {
             // Update timestamp only
-            conn.execute(
+            tx.execute(
                 "UPDATE templates SET python_version = ?1, updated_at = CURRENT_TIMESTAMP WHERE id = ?2",
                 params![python_version, id],
             )?;
+            tx.commit()?;
             Ok((id, false))
         } else {
-            conn.execute(
+            tx.execute(
                 "INSERT INTO templates (name, version, python_version, updated_at)
                  VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
                 params![name, version, python_version],
             )?;
-            Ok((conn.last_insert_rowid(), true))
+            let id = tx.last_insert_rowid();
+            tx.commit()?;
+            Ok((id, true))
         }
     }
 
@@ -515,13 +1531,15 @@ impl Database {
         install_type: &str,
         install_args: Option<&str>,
         step: i64,
+        extras: Option<&str>,
+        version_spec: Option<&str>,
     ) -> Result<()> {
         let conn = self.conn.lock().unwrap();
         let pinned = if is_pinned { 1 } else { 0 };
         conn.execute(
-            "INSERT OR REPLACE INTO template_packages (template_id, package_name, version, is_pinned, install_type, install_args, step)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![template_id, name, version, pinned, install_type, install_args, step],
+            "INSERT OR REPLACE INTO template_packages (template_id, package_name, version, is_pinned, install_type, install_args, step, extras, version_spec)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![template_id, name, version, pinned, install_type, install_args, step, extras, version_spec],
         )?;
         Ok(())
     }
@@ -601,13 +1619,10 @@ impl Database {
     }
 
     /// Returns all packages defined in a template, ordered by step and insertion order.
-    /// Returns: (package_name, version, is_pinned, install_type, install_args, step)
-    pub fn get_template_packages(
-        &self,
-        template_id: i64,
-    ) -> Result<Vec<(String, String, bool, String, Option<String>, i64)>> {
+    /// Returns: (package_name, version, is_pinned, install_type, install_args, step, extras, version_spec)
+    pub fn get_template_packages(&self, template_id: i64) -> Result<Vec<TemplatePackageRow>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare("SELECT package_name, version, is_pinned, install_type, install_args, COALESCE(step, 0) FROM template_packages WHERE template_id = ?1 ORDER BY step, id")?;
+        let mut stmt = conn.prepare("SELECT package_name, version, is_pinned, install_type, install_args, COALESCE(step, 0), extras, version_spec FROM template_packages WHERE template_id = ?1 ORDER BY step, id")?;
         let rows = stmt.query_map(params![template_id], |row| {
             let is_pinned: i32 = row.get(2)?;
             Ok((
@@ -617,6 +1632,8 @@ impl Database {
                 row.get(3)?,
                 row.get(4)?,
                 row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
             ))
         })?;
         let mut results = Vec::new();
@@ -640,9 +1657,10 @@ impl Database {
 
     /// Deletes a template and its associated packages by name.
     pub fn delete_template(&self, name: &str) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
         // First get the template ID
-        let template_id: Option<i64> = conn
+        let template_id: Option<i64> = tx
             .query_row(
                 "SELECT id FROM templates WHERE name = ?1",
                 params![name],
@@ -652,12 +1670,13 @@ impl Database {
 
         if let Some(id) = template_id {
             // Delete associated packages first
-            conn.execute(
+            tx.execute(
                 "DELETE FROM template_packages WHERE template_id = ?1",
                 params![id],
             )?;
             // Then delete the template
-            conn.execute("DELETE FROM templates WHERE id = ?1", params![id])?;
+            tx.execute("DELETE FROM templates WHERE id = ?1", params![id])?;
+            tx.commit()?;
             Ok(true)
         } else {
             Ok(false)
@@ -666,15 +1685,58 @@ impl Database {
 
     /// Deletes a template by its database ID (packages + template row).
     pub fn delete_template_by_id(&self, id: i64) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
             "DELETE FROM template_packages WHERE template_id = ?1",
             params![id],
         )?;
-        conn.execute("DELETE FROM templates WHERE id = ?1", params![id])?;
+        tx.execute("DELETE FROM templates WHERE id = ?1", params![id])?;
+        tx.commit()?;
         Ok(())
     }
 
+    /// Replaces template `name:version` with a new definition, creating it if
+    /// missing. The old packages are deleted and the new ones written in one
+    /// transaction, so a failed insert leaves the existing template intact.
+    /// Returns the new template ID.
+    pub fn replace_template(
+        &self,
+        name: &str,
+        version: &str,
+        python_version: &str,
+        packages: &[TemplatePackageRow],
+    ) -> Result<i64> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM template_packages WHERE template_id IN
+             (SELECT id FROM templates WHERE name = ?1 AND version = ?2)",
+            params![name, version],
+        )?;
+        tx.execute(
+            "DELETE FROM templates WHERE name = ?1 AND version = ?2",
+            params![name, version],
+        )?;
+        tx.execute(
+            "INSERT INTO templates (name, version, python_version, updated_at)
+             VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)",
+            params![name, version, python_version],
+        )?;
+        let id = tx.last_insert_rowid();
+        for (pkg, pkg_version, is_pinned, install_type, install_args, step, extras, spec) in
+            packages
+        {
+            tx.execute(
+                "INSERT OR REPLACE INTO template_packages (template_id, package_name, version, is_pinned, install_type, install_args, step, extras, version_spec)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![id, pkg, pkg_version, is_pinned, install_type, install_args, step, extras, spec],
+            )?;
+        }
+        tx.commit()?;
+        Ok(id)
+    }
+
     /// Removes a single package from a template by name.
     pub fn remove_template_package(&self, template_id: i64, package_name: &str) -> Result<bool> {
         let conn = self.conn.lock().unwrap();
@@ -720,31 +1782,35 @@ impl Database {
         tag: Option<&str>,
         is_default: bool,
     ) -> Result<()> {
+        let project_path = &normalize_project_path(project_path);
         let env_id = self
             .get_env_id(env_name)?
             .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
 
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        // Unset-defaults, dedupe and insert must land together or not at all
+        let tx = conn.transaction()?;
         // If setting as default, unset other defaults for this project
         if is_default {
-            conn.execute(
+            tx.execute(
                 "UPDATE project_environments SET is_default = 0 WHERE project_path = ?1",
                 params![project_path],
             )?;
         }
 
         // Remove existing association for same project+env (prevent duplicates)
-        conn.execute(
+        tx.execute(
             "DELETE FROM project_environments WHERE project_path = ?1 AND env_id = ?2",
             params![project_path, env_id],
         )?;
 
         // Insert new association with link_type='user' (explicit zen link)
-        conn.execute(
+        tx.execute(
             "INSERT INTO project_environments (project_path, env_id, tag, is_default, link_type)
              VALUES (?1, ?2, ?3, ?4, 'user')",
             params![project_path, env_id, tag, is_default as i32],
         )?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -753,6 +1819,7 @@ impl Database {
         &self,
         project_path: &str,
     ) -> Result<Vec<(String, String, Option<String>, bool)>> {
+        let project_path = &normalize_project_path(project_path);
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT e.name, e.path, pe.tag, pe.is_default
@@ -783,14 +1850,21 @@ impl Database {
     /// If the link already exists, increments `activation_count` and updates `last_activated_at`.
     /// If no link exists, creates one with `link_type='activated'`.
     pub fn record_activation(&self, project_path: &str, env_name: &str) -> Result<()> {
+        let project_path = &normalize_project_path(project_path);
         let env_id = self
             .get_env_id(env_name)?
             .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
 
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE environments SET last_used_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![env_id],
+        )?;
 
         // Try to update existing link
-        let updated = conn.execute(
+        let updated = tx.execute(
             "UPDATE project_environments
              SET activation_count = activation_count + 1,
                  last_activated_at = CURRENT_TIMESTAMP
@@ -800,13 +1874,14 @@ impl Database {
 
         // No existing link — create one from activation
         if updated == 0 {
-            conn.execute(
+            tx.execute(
                 "INSERT INTO project_environments
                  (project_path, env_id, link_type, activation_count, last_activated_at)
                  VALUES (?1, ?2, 'activated', 1, CURRENT_TIMESTAMP)",
                 params![project_path, env_id],
             )?;
         }
+        tx.commit()?;
         Ok(())
     }
 
@@ -946,12 +2021,11 @@ impl Database {
         Ok(result)
     }
 
-    /// Removes stale project-environment links where the env no longer exists on disk.
+    /// Finds stale project-environment links without removing them.
     ///
-    /// Returns the list of pruned (project_path, env_name) pairs.
-    pub fn prune_stale_links(&self) -> Result<Vec<(String, String, String)>> {
-        // First, collect all links with their paths
-        let conn = self.conn.lock().unwrap();
+    /// A link is stale when either the env directory or the project directory
+    /// is gone. Returns (link_id, project_path, env_name, reason) tuples.
+    fn collect_stale_links(conn: &Connection) -> Result<Vec<(i64, String, String, String)>> {
         let mut stmt = conn.prepare(
             "SELECT pe.id, pe.project_path, e.name, e.path
              FROM project_environments pe
@@ -970,23 +2044,48 @@ impl Database {
             .filter_map(|r| r.ok())
             .collect();
 
-        let mut pruned = Vec::new();
-        for (id, project_path, env_name, env_path) in &links {
-            let env_gone = !std::path::Path::new(env_path).exists();
-            let project_gone = !std::path::Path::new(project_path).exists();
+        let mut stale = Vec::new();
+        for (id, project_path, env_name, env_path) in links {
+            let env_gone = !std::path::Path::new(&env_path).exists();
+            let project_gone = !std::path::Path::new(&project_path).exists();
             if env_gone || project_gone {
-                conn.execute(
-                    "DELETE FROM project_environments WHERE id = ?1",
-                    params![id],
-                )?;
                 let reason = if env_gone {
                     "env deleted"
                 } else {
                     "project dir missing"
                 };
-                pruned.push((project_path.clone(), env_name.clone(), reason.to_string()));
+                stale.push((id, project_path, env_name, reason.to_string()));
             }
         }
+        Ok(stale)
+    }
+
+    /// Lists stale project-environment links without modifying the database.
+    ///
+    /// Returns (project_path, env_name, reason) tuples, same shape as `prune_stale_links`.
+    pub fn find_stale_links(&self) -> Result<Vec<(String, String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(Self::collect_stale_links(&conn)?
+            .into_iter()
+            .map(|(_, project_path, env_name, reason)| (project_path, env_name, reason))
+            .collect())
+    }
+
+    /// Removes stale project-environment links where the env no longer exists on disk.
+    ///
+    /// Returns the list of pruned (project_path, env_name) pairs.
+    pub fn prune_stale_links(&self) -> Result<Vec<(String, String, String)>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut pruned = Vec::new();
+        for (id, project_path, env_name, reason) in Self::collect_stale_links(&tx)? {
+            tx.execute(
+                "DELETE FROM project_environments WHERE id = ?1",
+                params![id],
+            )?;
+            pruned.push((project_path, env_name, reason));
+        }
+        tx.commit()?;
         Ok(pruned)
     }
 
@@ -1035,6 +2134,7 @@ impl Database {
     ///
     /// Returns the number of deleted links.
     pub fn remove_links_for_path(&self, project_path: &str) -> Result<usize> {
+        let project_path = &normalize_project_path(project_path);
         let conn = self.conn.lock().unwrap();
         let count = conn.execute(
             "DELETE FROM project_environments WHERE project_path = ?1",
@@ -1060,6 +2160,7 @@ impl Database {
             Option<String>,
         )>,
     > {
+        let project_path = &normalize_project_path(project_path);
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
             "SELECT e.name, e.path, pe.tag, pe.is_default,
@@ -1091,8 +2192,46 @@ impl Database {
         Ok(result)
     }
 
+    /// Returns the projects linked to an environment, the reverse of
+    /// [`Self::get_project_links_with_stats`].
+    ///
+    /// Each result: (project_path, tag, is_default, link_type, activation_count, last_activated_at).
+    pub fn get_projects_for_env(
+        &self,
+        env_id: i64,
+    ) -> Result<Vec<(String, Option<String>, bool, String, i64, Option<String>)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT project_path, tag, is_default,
+                    COALESCE(link_type, 'user'),
+                    COALESCE(activation_count, 0),
+                    last_activated_at
+             FROM project_environments
+             WHERE env_id = ?1
+             ORDER BY is_default DESC, activation_count DESC, project_path",
+        )?;
+
+        let rows = stmt.query_map(params![env_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, i32>(2)? == 1,
+                row.get::<_, String>(3)?,
+                row.get::<_, i64>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            result.push(row?);
+        }
+        Ok(result)
+    }
+
     /// Returns the default environment name for a project, if one is set.
     pub fn get_default_environment(&self, project_path: &str) -> Result<Option<String>> {
+        let project_path = &normalize_project_path(project_path);
         let conn = self.conn.lock().unwrap();
         let result = conn
             .query_row(
@@ -1107,14 +2246,38 @@ impl Database {
         Ok(result)
     }
 
-    /// Remove a project-environment association
-    pub fn remove_project_association(&self, project_path: &str, env_id: i64) -> Result<()> {
+    /// Remove a project-environment association; returns whether a link existed.
+    pub fn remove_project_association(&self, project_path: &str, env_id: i64) -> Result<bool> {
+        let project_path = &normalize_project_path(project_path);
         let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let removed = conn.execute(
             "DELETE FROM project_environments WHERE project_path = ?1 AND env_id = ?2",
             params![project_path, env_id],
         )?;
-        Ok(())
+        Ok(removed > 0)
+    }
+
+    /// Makes an already-linked environment the project's only default.
+    ///
+    /// Returns false (and changes nothing) when the env isn't linked to the project.
+    pub fn set_default_environment(&self, project_path: &str, env_id: i64) -> Result<bool> {
+        let project_path = &normalize_project_path(project_path);
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let linked: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM project_environments WHERE project_path = ?1 AND env_id = ?2",
+            params![project_path, env_id],
+            |row| row.get(0),
+        )?;
+        if linked == 0 {
+            return Ok(false);
+        }
+        tx.execute(
+            "UPDATE project_environments SET is_default = (env_id = ?2) WHERE project_path = ?1",
+            params![project_path, env_id],
+        )?;
+        tx.commit()?;
+        Ok(true)
     }
 
     /// Get all unique project paths that have environment associations
@@ -1168,6 +2331,25 @@ impl Database {
         Ok(res)
     }
 
+    /// Deletes a configuration key. Returns false if the key was not set.
+    pub fn unset_config(&self, key: &str) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count = conn.execute("DELETE FROM configuration WHERE key = ?1", params![key])?;
+        Ok(count > 0)
+    }
+
+    /// Deletes all user configuration values (the schema version is kept).
+    ///
+    /// Returns the number of keys removed.
+    pub fn clear_config(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let count = conn.execute(
+            "DELETE FROM configuration WHERE key != 'schema_version'",
+            [],
+        )?;
+        Ok(count)
+    }
+
     /// Lists all configuration key-value pairs.
     pub fn list_all_config(&self) -> Result<Vec<(String, String)>> {
         let conn = self.conn.lock().unwrap();
@@ -1185,14 +2367,7 @@ impl Database {
     /// Returns all templates with their full package lists (for export/display).
     pub fn get_all_templates_with_packages(
         &self,
-    ) -> Result<
-        Vec<(
-            String,
-            String,
-            String,
-            Vec<(String, String, bool, String, Option<String>, i64)>,
-        )>,
-    > {
+    ) -> Result<Vec<(String, String, String, Vec<TemplatePackageRow>)>> {
         let (templates, _packages_map) = {
             let conn = self.conn.lock().unwrap();
             let mut stmt =
@@ -1238,11 +2413,14 @@ impl Database {
         Ok(())
     }
 
-    /// Lists comments filtered by project path and/or environment ID.
+    /// Lists comments filtered by project path, environment ID and/or tag.
+    ///
+    /// The tag match is case-insensitive.
     pub fn list_comments(
         &self,
         project_path: Option<&str>,
         env_id: Option<i64>,
+        tag: Option<&str>,
     ) -> Result<Vec<(String, String, Option<i64>, String, Option<String>, String)>> {
         let conn = self.conn.lock().unwrap();
         let mut query =
@@ -1258,6 +2436,10 @@ impl Database {
             filters.push("env_id = ?");
             params_vec.push(Box::new(eid));
         }
+        if let Some(t) = tag {
+            filters.push("tag = ? COLLATE NOCASE");
+            params_vec.push(Box::new(t.to_string()));
+        }
 
         if !filters.is_empty() {
             query.push_str(" WHERE ");
@@ -1266,12 +2448,7 @@ impl Database {
         query.push_str(" ORDER BY created_at DESC");
 
         let mut stmt = conn.prepare(&query)?;
-
-        // Convert Params to something stmt.query can use
-        // Note: rusqlite params! works with slices, but dynamic params are trickier.
-        // For simplicity, let's just handle the common cases manually since we only have 2 filters.
-
-        let mapper = |row: &rusqlite::Row| {
+        let rows = stmt.query_map(rusqlite::params_from_iter(params_vec.iter()), |row| {
             Ok((
                 row.get(0)?,
                 row.get(1)?,
@@ -1280,15 +2457,7 @@ impl Database {
                 row.get(4)?,
                 row.get(5)?,
             ))
-        };
-
-        let rows = if filters.len() == 2 {
-            stmt.query_map(params![params_vec[0], params_vec[1]], mapper)?
-        } else if filters.len() == 1 {
-            stmt.query_map(params![params_vec[0]], mapper)?
-        } else {
-            stmt.query_map([], mapper)?
-        };
+        })?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -1297,6 +2466,36 @@ impl Database {
         Ok(results)
     }
 
+    /// Case-insensitive substring search over comment text across all environments.
+    ///
+    /// Same row shape as `list_comments`, newest first.
+    pub fn search_comments(
+        &self,
+        term: &str,
+    ) -> Result<Vec<(String, String, Option<i64>, String, Option<String>, String)>> {
+        let escaped = escape_like(term);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT uuid, project_path, env_id, message, tag, created_at FROM comments
+             WHERE message LIKE ?1 ESCAPE '\\'
+             ORDER BY created_at DESC",
+        )?;
+        let rows = stmt
+            .query_map(params![format!("%{}%", escaped)], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            })?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+
     /// Removes a comment by UUID or UUID prefix.
     pub fn remove_comment(&self, uuid_prefix: &str) -> Result<usize> {
         let conn = self.conn.lock().unwrap();
@@ -1390,4 +2589,653 @@ mod tests {
         let result = db.add_label("nonexistent", "ml");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_find_stale_links_is_read_only() {
+        let (db, tmp) = create_test_db();
+
+        let project = tmp.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let project_str = project.to_str().unwrap();
+        db.register_env("gone_env", "/nonexistent/gone_env", "3.12")
+            .unwrap();
+        db.associate_project(project_str, "gone_env", None, true)
+            .unwrap();
+
+        let stale = db.find_stale_links().unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].1, "gone_env");
+        assert_eq!(stale[0].2, "env deleted");
+        // Finding must not delete anything
+        assert_eq!(db.get_project_environments(project_str).unwrap().len(), 1);
+
+        let pruned = db.prune_stale_links().unwrap();
+        assert_eq!(pruned, stale);
+        assert!(db.get_project_environments(project_str).unwrap().is_empty());
+        assert!(db.find_stale_links().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_env_dependent_counts() {
+        let (db, tmp) = create_test_db();
+        let id = db.register_env("counted", "/tmp/counted", "3.12").unwrap();
+        let project = tmp.path().to_str().unwrap();
+
+        assert_eq!(db.count_env_links(id).unwrap(), 0);
+        db.associate_project(project, "counted", None, true)
+            .unwrap();
+        db.add_label("counted", "ml").unwrap();
+        db.add_label("counted", "dev").unwrap();
+        db.add_comment("u1", project, Some(id), "note", None)
+            .unwrap();
+
+        assert_eq!(db.count_env_links(id).unwrap(), 1);
+        assert_eq!(db.count_env_labels(id).unwrap(), 2);
+        assert_eq!(db.count_env_comments(id).unwrap(), 1);
+
+        let cleanup = db.delete_env("counted").unwrap();
+        assert_eq!(
+            cleanup,
+            EnvCleanup {
+                links: 1,
+                labels: 2,
+                notes_orphaned: 1
+            }
+        );
+        assert_eq!(
+            cleanup.describe().unwrap(),
+            "1 link, 2 labels cleaned, 1 note orphaned"
+        );
+        assert_eq!(EnvCleanup::default().describe(), None);
+    }
+
+    #[test]
+    fn test_associate_project_is_atomic() {
+        let (db, tmp) = create_test_db();
+        let project = tmp.path().to_str().unwrap();
+        db.register_env("first", "/tmp/first", "3.12").unwrap();
+        db.register_env("second", "/tmp/second", "3.12").unwrap();
+        db.associate_project(project, "first", None, true).unwrap();
+
+        // Make the final INSERT fail after the default-unset UPDATE has run
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER fail_link BEFORE INSERT ON project_environments
+                 BEGIN SELECT RAISE(ABORT, 'simulated failure'); END;",
+            )
+            .unwrap();
+        assert!(db.associate_project(project, "second", None, true).is_err());
+
+        // The earlier default must survive the failed call untouched
+        let links = db.get_project_environments(project).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].0, "first");
+        assert!(links[0].3, "default was unset by a half-applied call");
+    }
+
+    #[test]
+    fn test_set_default_and_unlink_project() {
+        let (db, tmp) = create_test_db();
+        let project = tmp.path().to_str().unwrap();
+        db.register_env("first", "/tmp/first", "3.12").unwrap();
+        db.register_env("second", "/tmp/second", "3.12").unwrap();
+        db.register_env("other", "/tmp/other", "3.12").unwrap();
+        db.associate_project(project, "first", None, true).unwrap();
+        db.associate_project(project, "second", None, false)
+            .unwrap();
+        let second = db.get_env_id("second").unwrap().unwrap();
+        let other = db.get_env_id("other").unwrap().unwrap();
+
+        // Unlinked envs can't become the default
+        assert!(!db.set_default_environment(project, other).unwrap());
+        assert_eq!(
+            db.get_default_environment(project).unwrap().as_deref(),
+            Some("first")
+        );
+
+        assert!(db.set_default_environment(project, second).unwrap());
+        let links = db.get_project_environments(project).unwrap();
+        let defaults: Vec<_> = links.iter().filter(|l| l.3).map(|l| l.0.as_str()).collect();
+        assert_eq!(defaults, vec!["second"]);
+
+        assert!(db.remove_project_association(project, second).unwrap());
+        assert!(!db.remove_project_association(project, second).unwrap());
+        assert_eq!(db.get_default_environment(project).unwrap(), None);
+    }
+
+    #[test]
+    fn test_migrations_run_in_order_and_stop_on_failure() {
+        let (db, _tmp) = create_test_db();
+        fn add_marker(tx: &Transaction) -> Result<()> {
+            tx.execute("CREATE TABLE marker (step INTEGER)", [])?;
+            Ok(())
+        }
+        fn insert_step(tx: &Transaction) -> Result<()> {
+            tx.execute("INSERT INTO marker VALUES (2)", [])?;
+            Ok(())
+        }
+        fn fail(tx: &Transaction) -> Result<()> {
+            tx.execute("INSERT INTO marker VALUES (3)", [])?;
+            Err("boom".into())
+        }
+        let migrations: &[Migration] = &[(1, add_marker), (2, insert_step), (3, fail)];
+        db.set_config("schema_version", "1").unwrap();
+
+        let err = db.run_migrations(1, migrations).unwrap_err().to_string();
+        assert!(err.contains("v3 → v4") && err.contains("boom"), "{}", err);
+        // The first two steps stuck; the failed one rolled back entirely
+        assert_eq!(db.schema_version().unwrap(), 3);
+        let rows: Vec<i64> = {
+            let conn = db.conn.lock().unwrap();
+            let mut stmt = conn.prepare("SELECT step FROM marker").unwrap();
+            stmt.query_map([], |r| r.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect()
+        };
+        assert_eq!(rows, vec![2]);
+
+        // Already-applied steps are skipped on the next run
+        assert_eq!(db.run_migrations(3, &migrations[..2]).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_upgrade_backs_up_and_restores_on_failure() {
+        let tmp = tempdir().unwrap();
+        let db_path = tmp.path().join("zen.db");
+        {
+            let db = Database::open(Some(&db_path)).unwrap();
+            db.register_env("precious", "/tmp/precious", "3.12")
+                .unwrap();
+            db.set_config("schema_version", "11").unwrap();
+        }
+
+        fn wipe_then_fail(tx: &Transaction) -> Result<()> {
+            tx.execute("DELETE FROM environments", [])?;
+            Err("boom".into())
+        }
+        let err = Database::open_with_migrations(Some(&db_path), &[(11, wipe_then_fail)])
+            .err()
+            .unwrap()
+            .to_string();
+        let backup = tmp.path().join("zen.db.bak-v11");
+        assert!(backup.exists());
+        assert!(err.contains("boom") && err.contains("restored"), "{}", err);
+
+        // The restored file is the pre-upgrade database, still at v11
+        let conn = Connection::open(&db_path).unwrap();
+        let (version, envs): (String, i64) = conn
+            .query_row(
+                "SELECT (SELECT value FROM configuration WHERE key = 'schema_version'),
+                        (SELECT COUNT(*) FROM environments)",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!((version.as_str(), envs), ("11", 1));
+        drop(conn);
+
+        // A successful upgrade keeps its backup; a fresh database gets none
+        let db = Database::open(Some(&db_path)).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        assert_eq!(db.list_envs().unwrap().len(), 1);
+        let fresh = tmp.path().join("fresh.db");
+        let db = Database::open(Some(&fresh)).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        let backups = std::fs::read_dir(tmp.path())
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("fresh.db.bak")
+            })
+            .count();
+        assert_eq!(backups, 0);
+    }
+
+    #[test]
+    fn test_vacuum_and_integrity_check() {
+        let (db, _tmp) = create_test_db();
+        for i in 0..50 {
+            db.register_env(&format!("env{}", i), &format!("/tmp/env{}", i), "3.12")
+                .unwrap();
+        }
+        for i in 0..50 {
+            db.delete_env(&format!("env{}", i)).unwrap();
+        }
+        db.vacuum().unwrap();
+        assert!(db.integrity_check().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_upgrade_backfills_last_used_at() {
+        let tmp = tempdir().unwrap();
+        let db_path = tmp.path().join("old.db");
+        {
+            let db = Database::open(Some(&db_path)).unwrap();
+            db.register_env("linked", "/tmp/linked", "3.12").unwrap();
+            db.register_env("idle", "/tmp/idle", "3.12").unwrap();
+            db.associate_project("/work/a", "linked", None, true)
+                .unwrap();
+            let conn = db.conn.lock().unwrap();
+            // What an activation recorded before last_used_at existed
+            conn.execute_batch(
+                "UPDATE project_environments SET last_activated_at = '2024-05-01 10:00:00';
+                 UPDATE environments SET last_used_at = NULL;
+                 UPDATE configuration SET value = '7' WHERE key = 'schema_version';",
+            )
+            .unwrap();
+        }
+
+        let db = Database::open(Some(&db_path)).unwrap();
+        assert_eq!(db.schema_version().unwrap(), SCHEMA_VERSION);
+        let last_used = db.get_last_used().unwrap();
+        assert_eq!(
+            last_used.get("linked").map(String::as_str),
+            Some("2024-05-01 10:00:00")
+        );
+        assert!(!last_used.contains_key("idle"));
+    }
+
+    #[test]
+    fn test_project_paths_are_normalized() {
+        let (db, tmp) = create_test_db();
+        let project = tmp.path().join("project");
+        std::fs::create_dir_all(&project).unwrap();
+        let canonical = project.canonicalize().unwrap();
+        let canonical_str = canonical.to_str().unwrap();
+        let link = tmp.path().join("shortcut");
+        std::os::unix::fs::symlink(&project, &link).unwrap();
+        db.register_env("norm", "/tmp/norm", "3.12").unwrap();
+
+        // Stored through a trailing slash, found through the plain path
+        db.associate_project(&format!("{}/", canonical_str), "norm", None, true)
+            .unwrap();
+        assert_eq!(
+            db.get_default_environment(canonical_str)
+                .unwrap()
+                .as_deref(),
+            Some("norm")
+        );
+        // ...and through a symlink to the same directory
+        assert_eq!(
+            db.get_default_environment(link.to_str().unwrap())
+                .unwrap()
+                .as_deref(),
+            Some("norm")
+        );
+
+        // Re-linking via the symlink replaces the link instead of adding one
+        db.associate_project(link.to_str().unwrap(), "norm", None, true)
+            .unwrap();
+        db.record_activation(&format!("{}/./", link.display()), "norm")
+            .unwrap();
+        let env_id = db.get_env_id("norm").unwrap().unwrap();
+        let projects = db.get_projects_for_env(env_id).unwrap();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].0, canonical_str);
+        assert_eq!(projects[0].4, 1);
+
+        // Paths that don't exist are still cleaned up lexically
+        assert_eq!(normalize_project_path("/work/app/"), "/work/app");
+        assert_eq!(normalize_project_path("/work/./lib/../app"), "/work/app");
+    }
+
+    #[test]
+    fn test_get_projects_for_env() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("shared", "/tmp/shared", "3.12").unwrap();
+        db.register_env("lonely", "/tmp/lonely", "3.12").unwrap();
+        db.associate_project("/work/a", "shared", Some("gpu"), false)
+            .unwrap();
+        db.associate_project("/work/b", "shared", None, true)
+            .unwrap();
+        db.record_activation("/work/a", "shared").unwrap();
+        db.record_activation("/work/a", "shared").unwrap();
+
+        let shared = db.get_env_id("shared").unwrap().unwrap();
+        let links = db.get_projects_for_env(shared).unwrap();
+        let paths: Vec<_> = links.iter().map(|l| l.0.as_str()).collect();
+        // Default first, then by activation count
+        assert_eq!(paths, vec!["/work/b", "/work/a"]);
+        assert!(links[0].2);
+        assert_eq!(links[1].1.as_deref(), Some("gpu"));
+        assert_eq!(links[1].4, 2);
+        assert!(links[1].5.is_some());
+
+        let lonely = db.get_env_id("lonely").unwrap().unwrap();
+        assert!(db.get_projects_for_env(lonely).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_delete_template_is_atomic() {
+        let (db, _tmp) = create_test_db();
+        let (id, _) = db.create_template("tpl", "1.0", "3.12").unwrap();
+        db.add_template_package(id, "numpy", "2.0", false, "pypi", None, 1, None, None)
+            .unwrap();
+
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER fail_tpl BEFORE DELETE ON templates
+                 BEGIN SELECT RAISE(ABORT, 'simulated failure'); END;",
+            )
+            .unwrap();
+        assert!(db.delete_template("tpl").is_err());
+        assert_eq!(db.get_template_packages(id).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_replace_template_is_atomic() {
+        let (db, _tmp) = create_test_db();
+        let (id, _) = db.create_template("tpl", "1.0", "3.12").unwrap();
+        db.add_template_package(id, "numpy", "2.0", false, "pypi", None, 1, None, None)
+            .unwrap();
+        let row = |name: &str| -> TemplatePackageRow {
+            (
+                name.to_string(),
+                "1.0".to_string(),
+                true,
+                "pypi".to_string(),
+                None,
+                1,
+                None,
+                None,
+            )
+        };
+
+        db.conn
+            .lock()
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER fail_pkg BEFORE INSERT ON template_packages
+                 WHEN NEW.package_name = 'bad'
+                 BEGIN SELECT RAISE(ABORT, 'simulated failure'); END;",
+            )
+            .unwrap();
+        assert!(
+            db.replace_template("tpl", "1.0", "3.13", &[row("torch"), row("bad")])
+                .is_err()
+        );
+        let kept = db.get_template_id("tpl", "1.0").unwrap().unwrap();
+        let packages = db.get_template_packages(kept).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].0, "numpy");
+
+        let new_id = db
+            .replace_template("tpl", "1.0", "3.13", &[row("torch")])
+            .unwrap();
+        let packages = db.get_template_packages(new_id).unwrap();
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].0, "torch");
+        assert_eq!(db.get_template_by_id(new_id).unwrap().unwrap().2, "3.13");
+    }
+
+    #[test]
+    fn test_count_envs_and_name_order() {
+        let (db, _tmp) = create_test_db();
+        assert_eq!(db.count_envs().unwrap(), 0);
+        for name in ["zeta", "alpha", "mid"] {
+            db.register_env(name, &format!("/tmp/{}", name), "3.12")
+                .unwrap();
+        }
+        assert_eq!(db.count_envs().unwrap(), 3);
+        let names: Vec<String> = db.list_envs().unwrap().into_iter().map(|e| e.0).collect();
+        assert_eq!(names, ["alpha", "mid", "zeta"]);
+    }
+
+    #[test]
+    fn test_env_vars_crud() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("vars", "/tmp/vars", "3.12").unwrap();
+
+        db.set_env_var("vars", "HF_HOME", "/data/hf").unwrap();
+        db.set_env_var("vars", "CUDA_VISIBLE_DEVICES", "0").unwrap();
+        db.set_env_var("vars", "CUDA_VISIBLE_DEVICES", "1").unwrap();
+        assert_eq!(
+            db.get_env_var("vars", "CUDA_VISIBLE_DEVICES").unwrap(),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            db.list_env_vars("vars").unwrap(),
+            vec![
+                ("CUDA_VISIBLE_DEVICES".to_string(), "1".to_string()),
+                ("HF_HOME".to_string(), "/data/hf".to_string()),
+            ]
+        );
+
+        assert!(db.unset_env_var("vars", "HF_HOME").unwrap());
+        assert!(!db.unset_env_var("vars", "HF_HOME").unwrap());
+        assert_eq!(db.get_env_var("vars", "HF_HOME").unwrap(), None);
+        assert!(db.set_env_var("missing", "A", "b").is_err());
+    }
+
+    #[test]
+    fn test_touch_env_sets_last_used() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("used", "/tmp/used", "3.12").unwrap();
+        db.register_env("idle", "/tmp/idle", "3.12").unwrap();
+        assert!(db.get_last_used().unwrap().is_empty());
+
+        db.touch_env("used").unwrap();
+        db.record_activation("/tmp/project", "idle").unwrap();
+        let last_used = db.get_last_used().unwrap();
+        assert!(last_used.contains_key("used"));
+        assert!(last_used.contains_key("idle"));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("project-x-torch", "/tmp/px", "3.12")
+            .unwrap();
+        db.register_env("web", "/tmp/web", "3.12").unwrap();
+
+        db.add_alias("px", "project-x-torch").unwrap();
+        assert_eq!(
+            db.resolve_alias("px").unwrap().as_deref(),
+            Some("project-x-torch")
+        );
+        assert_eq!(db.resolve_alias("web").unwrap(), None);
+        assert_eq!(db.get_env_aliases("project-x-torch").unwrap(), vec!["px"]);
+
+        // No shadowing env names, no silent re-pointing, no dangling targets
+        assert!(db.add_alias("web", "project-x-torch").is_err());
+        assert!(db.add_alias("px", "web").is_err());
+        assert!(db.add_alias("w", "missing").is_err());
+
+        // Renames carry aliases along; deletes take them away
+        db.rename_environment("project-x-torch", "px-torch")
+            .unwrap();
+        assert_eq!(db.resolve_alias("px").unwrap().as_deref(), Some("px-torch"));
+        db.delete_env("px-torch").unwrap();
+        assert!(db.list_aliases().unwrap().is_empty());
+
+        db.add_alias("w", "web").unwrap();
+        assert!(db.remove_alias("w").unwrap());
+        assert!(!db.remove_alias("w").unwrap());
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("ml", "/tmp/ml", "3.12").unwrap();
+        let pkg = |name: &str, version: &str| SnapshotPackage {
+            name: name.to_string(),
+            version: version.to_string(),
+            requirement: format!("{}=={}", name, version),
+        };
+        let packages = vec![pkg("numpy", "1.26.4"), pkg("torch", "2.5.0")];
+
+        db.create_snapshot("ml", "before-upgrade", &packages)
+            .unwrap();
+        assert!(db.create_snapshot("ml", "before-upgrade", &[]).is_err());
+        assert!(db.create_snapshot("missing", "x", &[]).is_err());
+
+        let listed = db.list_snapshots("ml").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].0, "before-upgrade");
+        assert_eq!(listed[0].2, 2);
+        assert_eq!(
+            db.get_snapshot("ml", "before-upgrade").unwrap(),
+            Some(packages)
+        );
+        assert_eq!(db.get_snapshot("ml", "nope").unwrap(), None);
+
+        db.delete_env("ml").unwrap();
+        assert!(db.list_snapshots("ml").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_open_readonly() {
+        let (db, tmp) = create_test_db();
+        let db_path = tmp.path().join("test.db");
+        db.register_env("first", "/tmp/first", "3.12").unwrap();
+        assert!(!db.is_read_only());
+
+        let ro = Database::open_readonly(Some(&db_path)).unwrap();
+        assert!(ro.is_read_only());
+        assert_eq!(ro.list_envs().unwrap().len(), 1);
+        assert!(ro.register_env("second", "/tmp/second", "3.12").is_err());
+
+        // Writes go through a separate read-write handle and are visible to readers
+        ro.writable()
+            .unwrap()
+            .register_env("second", "/tmp/second", "3.12")
+            .unwrap();
+        assert_eq!(ro.list_envs().unwrap().len(), 2);
+
+        assert!(Database::open_readonly(Some(&tmp.path().join("missing.db"))).is_err());
+    }
+
+    #[test]
+    fn test_cuda_variant_roundtrip() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("gpu", "/tmp/gpu", "3.12").unwrap();
+        assert_eq!(db.get_cuda_variant("gpu").unwrap(), None);
+        assert_eq!(db.get_cuda_variant("missing").unwrap(), None);
+
+        db.set_cuda_variant("gpu", "cu130").unwrap();
+        assert_eq!(
+            db.get_cuda_variant("gpu").unwrap().as_deref(),
+            Some("cu130")
+        );
+        db.set_cuda_variant("gpu", "cpu").unwrap();
+        assert_eq!(db.get_cuda_variant("gpu").unwrap().as_deref(), Some("cpu"));
+    }
+
+    #[test]
+    fn test_source_template_roundtrip() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("ml", "/tmp/ml", "3.12").unwrap();
+        assert_eq!(db.get_source_template("ml").unwrap(), None);
+        assert_eq!(db.get_source_template("missing").unwrap(), None);
+
+        db.set_source_template("ml", "torch:2.10,extras:v1")
+            .unwrap();
+        assert_eq!(
+            db.get_source_template("ml").unwrap().as_deref(),
+            Some("torch:2.10,extras:v1")
+        );
+
+        // Re-registering the env (zen create --rm) starts over
+        db.register_env("ml", "/tmp/ml", "3.12").unwrap();
+        assert_eq!(db.get_source_template("ml").unwrap(), None);
+    }
+
+    #[test]
+    fn test_install_groups() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("grp", "/tmp/grp", "3.12").unwrap();
+
+        let dev = vec!["ruff".to_string(), "pytest".to_string()];
+        db.add_group_packages("grp", "dev", &dev).unwrap();
+        db.add_group_packages("grp", "Runtime", &["numpy".to_string()])
+            .unwrap();
+        assert_eq!(
+            db.get_group_packages("grp", "DEV").unwrap(),
+            vec!["pytest", "ruff"]
+        );
+        assert_eq!(
+            db.list_groups("grp").unwrap(),
+            vec![
+                (
+                    "dev".to_string(),
+                    vec!["pytest".to_string(), "ruff".to_string()]
+                ),
+                ("runtime".to_string(), vec!["numpy".to_string()]),
+            ]
+        );
+
+        db.remove_group_packages("grp", &["PyTest".to_string()])
+            .unwrap();
+        assert_eq!(db.get_group_packages("grp", "dev").unwrap(), vec!["ruff"]);
+        assert_eq!(db.delete_group("grp", "dev").unwrap(), 1);
+        assert_eq!(db.list_groups("grp").unwrap().len(), 1);
+        assert!(db.add_group_packages("missing", "dev", &dev).is_err());
+    }
+
+    #[test]
+    fn test_package_index_search() {
+        let (db, _tmp) = create_test_db();
+        db.register_env("env_a", "/tmp/env_a", "3.12").unwrap();
+        db.register_env("env_b", "/tmp/env_b", "3.12").unwrap();
+
+        let pkg = |name: &str, version: &str| PackageMetadata {
+            name: name.to_string(),
+            version: Some(version.to_string()),
+            ..Default::default()
+        };
+        db.index_packages(
+            "env_a",
+            Some(100),
+            &[
+                pkg("torch", "2.10.0+cu130"),
+                pkg("typing_extensions", "4.12.0"),
+            ],
+        )
+        .unwrap();
+        db.index_packages(
+            "env_b",
+            Some(200),
+            &[
+                pkg("torchvision", "0.25.0"),
+                pkg("typing-extensions", "4.11.0"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(db.get_package_index_mtime("env_a").unwrap(), Some(100));
+        assert_eq!(db.get_package_index_mtime("missing").unwrap(), None);
+
+        // Substring match spans envs
+        let hits = db.search_packages("torch", false).unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].1, "torch");
+        assert_eq!(hits[1].1, "torchvision");
+
+        // Exact match
+        let hits = db.search_packages("torch", true).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "env_a");
+
+        // `-` and `_` are equivalent, and `_` is not a LIKE wildcard
+        assert_eq!(
+            db.search_packages("typing-extensions", true).unwrap().len(),
+            2
+        );
+        assert!(
+            db.search_packages("typingXextensions", true)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Re-indexing replaces the previous package list
+        db.index_packages("env_a", Some(101), &[pkg("numpy", "2.1.0")])
+            .unwrap();
+        assert!(db.search_packages("torch", true).unwrap().is_empty());
+        assert_eq!(db.get_package_index_mtime("env_a").unwrap(), Some(101));
+    }
 }
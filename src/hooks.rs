@@ -18,7 +18,7 @@
 /// - Preserves `za` as a convenient shortcut for `zen activate`
 /// - Passes all other subcommands through to the real binary
 pub fn generate_hook(shell: &str) -> String {
-    match shell {
+    let base = match shell {
         "zsh" | "bash" => {
             // Find the real binary path at hook-eval time
             r#"
@@ -28,6 +28,14 @@ pub fn generate_hook(shell: &str) -> String {
 # Locate the real zen binary once
 __ZEN_BIN="$(command which zen 2>/dev/null)"
 
+# Drop variables exported for the previously activated env (zen env set)
+__zen_clear_vars() {
+    if [ -n "${__ZEN_ENV_VARS:-}" ]; then
+        eval "unset $__ZEN_ENV_VARS"
+        unset __ZEN_ENV_VARS
+    fi
+}
+
 zen() {
     local cmd="${1:-}"
 
@@ -47,7 +55,9 @@ zen() {
 
             if [ $rc -eq 0 ] && [ -n "$env_path" ] && [ -d "$env_path" ]; then
                 if [ -f "$env_path/bin/activate" ]; then
+                    __zen_clear_vars
                     source "$env_path/bin/activate"
+                    eval "$("$__ZEN_BIN" env export 2>/dev/null)"
                     echo "✓ Activated environment: $(basename $env_path)"
                 else
                     echo "Error: Activation script not found at $env_path/bin/activate"
@@ -60,6 +70,7 @@ zen() {
         deactivate)
             if [ -n "${VIRTUAL_ENV:-}" ]; then
                 local env_name=$(basename "$VIRTUAL_ENV")
+                __zen_clear_vars
                 deactivate 2>/dev/null
                 echo "✓ Deactivated environment: $env_name"
             else
@@ -90,6 +101,14 @@ zd() {
 
 set -g __ZEN_BIN (command which zen 2>/dev/null)
 
+# Drop variables exported for the previously activated env (zen env set)
+function __zen_clear_vars
+    for v in $__ZEN_ENV_VARS
+        set -e $v
+    end
+    set -e __ZEN_ENV_VARS
+end
+
 function zen --wraps zen
     set cmd $argv[1]
 
@@ -106,7 +125,9 @@ function zen --wraps zen
 
             if test $status -eq 0 -a -n "$env_path" -a -d "$env_path"
                 if test -f "$env_path/bin/activate.fish"
+                    __zen_clear_vars
                     source "$env_path/bin/activate.fish"
+                    $__ZEN_BIN env export --shell fish 2>/dev/null | source
                     echo "✓ Activated environment: "(basename $env_path)
                 else
                     echo "Error: Activation script not found at $env_path/bin/activate.fish"
@@ -116,6 +137,7 @@ function zen --wraps zen
         case deactivate
             if set -q VIRTUAL_ENV
                 set env_name (basename $VIRTUAL_ENV)
+                __zen_clear_vars
                 deactivate 2>/dev/null
                 echo "✓ Deactivated environment: $env_name"
             else
@@ -137,6 +159,223 @@ function zd
 end
 "#
         .to_string(),
-        _ => format!("echo \"Zen: Unsupported shell '{}'\"", shell),
+        _ => return format!("echo \"Zen: Unsupported shell '{}'\"", shell),
+    };
+    base + dynamic_completions(shell)
+}
+
+/// Subcommands whose first positional argument is an environment name.
+macro_rules! env_commands {
+    () => {
+        "activate rm rename info show run freeze clone favorite fav unfavorite unfav inspect diff health"
+    };
+}
+
+/// Template subcommands whose first positional argument is a template name.
+macro_rules! template_commands {
+    () => {
+        "inspect rm edit update drop export"
+    };
+}
+
+/// Generates completion functions that ask `zen __complete` for live
+/// candidates (env names, labels, templates) from the registry.
+fn dynamic_completions(shell: &str) -> &'static str {
+    match shell {
+        "bash" => concat!(
+            r#"
+# Dynamic completion: env names, labels and templates come from the registry
+__zen_candidates() {
+    "$__ZEN_BIN" __complete "$1" "$2" 2>/dev/null
+}
+
+_zen_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local sub="${COMP_WORDS[1]:-}"
+    local kind=""
+
+    case "$prev" in
+        -n|--name) kind=envs ;;
+        --label) kind=labels ;;
+        --template|--from) kind=templates ;;
+    esac
+
+    if [ -z "$kind" ] && [[ "$cur" != -* ]]; then
+        if [ "$COMP_CWORD" -eq 2 ] && [[ " "#,
+            env_commands!(),
+            r#" " == *" $sub "* ]]; then
+            kind=envs
+        elif [ "$sub" = diff ] && [ "$COMP_CWORD" -gt 2 ]; then
+            kind=envs
+        elif [ "$sub" = template ] && [ "$COMP_CWORD" -eq 3 ] && [[ " "#,
+            template_commands!(),
+            r#" " == *" ${COMP_WORDS[2]} "* ]]; then
+            kind=templates
+        elif [ "$sub" = label ] && [ "$COMP_CWORD" -eq 3 ]; then
+            kind=labels
+        elif [ "$sub" = label ] && [ "$COMP_CWORD" -eq 4 ]; then
+            kind=envs
+        fi
+    fi
+
+    if [ -n "$kind" ]; then
+        COMPREPLY=($(compgen -W "$(__zen_candidates "$kind" "$cur")" -- "$cur"))
+    elif declare -F _zen >/dev/null; then
+        _zen "$@"
+    fi
+}
+
+_za_dynamic() {
+    COMPREPLY=($(compgen -W "$(__zen_candidates envs "${COMP_WORDS[COMP_CWORD]}")" -- "${COMP_WORDS[COMP_CWORD]}"))
+}
+
+complete -F _zen_dynamic zen
+complete -F _za_dynamic za
+"#
+        ),
+        "zsh" => concat!(
+            r#"
+# Dynamic completion: env names, labels and templates come from the registry
+_zen_dynamic() {
+    local sub="${words[2]:-}"
+    local prev="${words[CURRENT-1]}"
+    local kind=""
+
+    case "$prev" in
+        -n|--name) kind=envs ;;
+        --label) kind=labels ;;
+        --template|--from) kind=templates ;;
+    esac
+
+    if [[ -z "$kind" && "$PREFIX" != -* ]]; then
+        if (( CURRENT == 3 )) && [[ " "#,
+            env_commands!(),
+            r#" " == *" $sub "* ]]; then
+            kind=envs
+        elif [[ "$sub" == diff ]] && (( CURRENT > 3 )); then
+            kind=envs
+        elif [[ "$sub" == template ]] && (( CURRENT == 4 )) && [[ " "#,
+            template_commands!(),
+            r#" " == *" ${words[3]} "* ]]; then
+            kind=templates
+        elif [[ "$sub" == label ]] && (( CURRENT == 4 )); then
+            kind=labels
+        elif [[ "$sub" == label ]] && (( CURRENT == 5 )); then
+            kind=envs
+        fi
+    fi
+
+    if [[ -n "$kind" ]]; then
+        compadd -- ${(f)"$("$__ZEN_BIN" __complete "$kind" "$PREFIX" 2>/dev/null)"}
+    elif (( $+functions[_zen] )); then
+        _zen "$@"
+    fi
+}
+
+_za_dynamic() {
+    compadd -- ${(f)"$("$__ZEN_BIN" __complete envs "$PREFIX" 2>/dev/null)"}
+}
+
+if (( $+functions[compdef] )); then
+    compdef _zen_dynamic zen
+    compdef _za_dynamic za
+fi
+"#
+        ),
+        "fish" => concat!(
+            r#"
+# Dynamic completion: env names, labels and templates come from the registry
+complete -c zen -n '__fish_seen_subcommand_from "#,
+            env_commands!(),
+            r#"; and not __fish_seen_subcommand_from template label' -f -a '(eval $__ZEN_BIN __complete envs (commandline -ct))'
+complete -c zen -n '__fish_seen_subcommand_from template; and __fish_seen_subcommand_from "#,
+            template_commands!(),
+            r#"' -f -a '(eval $__ZEN_BIN __complete templates (commandline -ct))'
+complete -c zen -n '__fish_seen_subcommand_from label; and __fish_seen_subcommand_from add rm' -f -a '(eval $__ZEN_BIN __complete labels (commandline -ct))'
+complete -c zen -l label -f -a '(eval $__ZEN_BIN __complete labels (commandline -ct))'
+complete -c zen -l template -f -a '(eval $__ZEN_BIN __complete templates (commandline -ct))'
+complete -c za -f -a '(eval $__ZEN_BIN __complete envs (commandline -ct))'
+"#
+        ),
+        _ => "",
+    }
+}
+
+/// Renders per-environment variables as shell code for the activation hook.
+///
+/// Values are single-quoted. `__ZEN_ENV_VARS` records the exported keys so
+/// the hook can unset them on deactivate or when switching envs.
+pub fn export_env_vars(shell: &str, vars: &[(String, String)]) -> String {
+    if vars.is_empty() {
+        return String::new();
+    }
+    let keys: Vec<&str> = vars.iter().map(|(k, _)| k.as_str()).collect();
+    let mut out = String::new();
+    if shell == "fish" {
+        for (key, value) in vars {
+            let quoted = value.replace('\\', "\\\\").replace('\'', "\\'");
+            out.push_str(&format!("set -gx {} '{}'\n", key, quoted));
+        }
+        out.push_str(&format!("set -g __ZEN_ENV_VARS {}\n", keys.join(" ")));
+    } else {
+        for (key, value) in vars {
+            let quoted = value.replace('\'', "'\\''");
+            out.push_str(&format!("export {}='{}'\n", key, quoted));
+        }
+        out.push_str(&format!("__ZEN_ENV_VARS='{}'\n", keys.join(" ")));
+    }
+    out
+}
+
+/// Startup files for `zen shell`, as (file name, contents) in a scratch dir.
+///
+/// bash gets a `--rcfile`; zsh gets a `ZDOTDIR` whose files chain to the
+/// user's own (`user_dir` is their `$ZDOTDIR` or `$HOME`). Both source the
+/// usual rc file, then the env's `bin/activate` so `deactivate` and the venv
+/// prompt work, then mark the prompt with `[zen]`. Other shells get nothing.
+pub fn subshell_files(
+    shell: &str,
+    user_dir: &std::path::Path,
+    env_path: &std::path::Path,
+) -> Vec<(&'static str, String)> {
+    let quote = |p: &std::path::Path| format!("'{}'", p.to_string_lossy().replace('\'', "'\\''"));
+    let bin = env_path.join("bin");
+    let activate = format!(
+        "if [ -f {act} ]; then\n    . {act}\nelse\n    export VIRTUAL_ENV={env}\n    export PATH={bin}:\"$PATH\"\nfi\n",
+        act = quote(&bin.join("activate")),
+        env = quote(env_path),
+        bin = quote(&bin),
+    );
+    match shell {
+        "bash" => {
+            let rc = user_dir.join(".bashrc");
+            vec![(
+                "bashrc",
+                format!(
+                    "[ -f {rc} ] && . {rc}\n{activate}PS1=\"[zen] $PS1\"\n",
+                    rc = quote(&rc),
+                ),
+            )]
+        }
+        "zsh" => {
+            let env_rc = user_dir.join(".zshenv");
+            let rc = user_dir.join(".zshrc");
+            vec![
+                (
+                    ".zshenv",
+                    format!("[ -f {rc} ] && . {rc}\n", rc = quote(&env_rc)),
+                ),
+                (
+                    ".zshrc",
+                    format!(
+                        "ZDOTDIR={dir}\n[ -f {rc} ] && . {rc}\n{activate}PROMPT=\"[zen] $PROMPT\"\n",
+                        dir = quote(user_dir),
+                        rc = quote(&rc),
+                    ),
+                ),
+            ]
+        }
+        _ => Vec::new(),
     }
 }
@@ -14,15 +14,36 @@ mod utils;
 mod validation;
 
 use crate::db::Database;
+use crate::printer::{OutputFormat, Themed, Tone};
 use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
-use owo_colors::OwoColorize;
 use std::path::{Path, PathBuf};
 
 #[derive(ValueEnum, Clone, Debug)]
 enum ListSort {
     Name,
     Date,
+    /// Most recently activated, run or installed into
+    Recent,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum FreezeFormatArg {
+    Requirements,
+    Pyproject,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum LogSourceArg {
+    Cli,
+    Mcp,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CompleteKind {
+    Envs,
+    Labels,
+    Templates,
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -33,6 +54,17 @@ enum ListFormatArg {
     Wide,
 }
 
+/// `--output`, shared by the commands that print rows of data.
+///
+/// Not a global flag: `zen freeze` and `zen template export` already use
+/// `--output` for a file path.
+#[derive(clap::Args, Clone, Copy, Debug)]
+struct OutputArgs {
+    /// Print rows as a table (default), JSON or CSV
+    #[arg(long, value_enum, default_value = "table")]
+    output: OutputFormat,
+}
+
 #[derive(Parser)]
 #[command(name = "zen")]
 #[command(version = env!("ZEN_VERSION"))]
@@ -53,6 +85,14 @@ struct Cli {
         hide = true
     )]
     home: PathBuf,
+
+    /// Disable colored output (also honored: NO_COLOR env, `zen config theme mono`)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Use pip even when uv is available (also: `zen config prefer_uv false`)
+    #[arg(long, global = true)]
+    no_uv: bool,
 }
 
 #[derive(Subcommand, Clone, Debug)]
@@ -70,7 +110,11 @@ enum Commands {
         #[arg(short, long, alias = "from")]
         template: Option<String>,
 
-        /// Use exact versions from template snapshots
+        /// Rebuild another environment's packages in the new one (not a directory copy)
+        #[arg(long, value_name = "ENV", conflicts_with = "template")]
+        copy_from: Option<String>,
+
+        /// Use exact versions from template snapshots or the --copy-from env
         #[arg(long)]
         strict: bool,
 
@@ -82,10 +126,22 @@ enum Commands {
         #[arg(long, requires = "ml")]
         cuda: Option<String>,
 
+        /// Proceed when templates declare different Python versions (the first template's wins)
+        #[arg(long)]
+        force_python: bool,
+
         /// Remove existing environment with the same name before creating
         #[arg(long)]
         rm: bool,
 
+        /// Skip installing pip/uv/setuptools into the new venv (air-gapped machines)
+        #[arg(long)]
+        no_bootstrap: bool,
+
+        /// Give the venv access to the base interpreter's installed packages
+        #[arg(long)]
+        system_site_packages: bool,
+
         /// Extra positional args (hidden, used for typo detection)
         #[arg(hide = true, trailing_var_arg = true)]
         rest: Vec<String>,
@@ -116,19 +172,36 @@ enum Commands {
         /// Filter by label (e.g., --label ml)
         #[arg(long)]
         label: Option<String>,
-        /// Output format
-        #[arg(long, default_value = "auto")]
+        /// Output format (auto picks by terminal width)
+        #[arg(long, default_value = "auto", env = "ZEN_LIST_FORMAT")]
         format: ListFormatArg,
+        /// Columns to show, e.g. name,py,health,torch,size (presets: minimal, compact, wide)
+        #[arg(long, value_name = "SPEC", conflicts_with_all = ["oneline", "output"])]
+        columns: Option<String>,
         /// Names only, one per line (like ls -1)
         #[arg(short = '1')]
         oneline: bool,
         /// Long format with paths (like ls -l)
         #[arg(short = 'l')]
         long_format: bool,
+        /// Only show starred environments (see `zen favorite`)
+        #[arg(long)]
+        favorites: bool,
+        /// Only show broken environments (health ✗)
+        #[arg(long)]
+        broken: bool,
+        /// Only show drifted environments (health !, e.g. conflicts or CUDA mismatch)
+        #[arg(long)]
+        drift: bool,
+        /// Only show environments unused for at least this many days
+        #[arg(long, value_name = "DAYS")]
+        stale: Option<i64>,
+        #[command(flatten)]
+        output: OutputArgs,
     },
     /// Remove an environment from the database and disk
     Rm {
-        /// Name of the environment to remove
+        /// Name of the environment to remove, or a glob like '*test*'
         name: String,
         /// Skip confirmation
         #[arg(short, long)]
@@ -136,6 +209,9 @@ enum Commands {
         /// Remove from database only, keep files on disk
         #[arg(long)]
         cached: bool,
+        /// Show what would be removed (path, links, labels, notes) without deleting
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Add packages to an environment (or active session)
     ///
@@ -143,6 +219,8 @@ enum Commands {
     ///   zen install numpy scipy          # install in active environment
     ///   zen install -n myenv requests    # install in a specific environment
     ///   zen install torch-cu130          # install PyTorch with CUDA 13.0
+    ///   zen install torch-cpu            # install the CPU-only PyTorch build
+    ///   zen install -e                   # editable install of the project in this directory
     Install {
         /// Packages to install (or paths for -e)
         packages: Vec<String>,
@@ -170,26 +248,126 @@ enum Commands {
         /// Show what would be installed without actually installing
         #[arg(long)]
         dry_run: bool,
+        /// Don't stream pip/uv output (errors are still shown)
+        #[arg(short, long)]
+        quiet: bool,
+        /// Record the packages under a named group (e.g. dev, runtime)
+        #[arg(short, long)]
+        group: Option<String>,
+        /// Constrain versions with a pip constraints file (like pip install -c)
+        #[arg(short, long, value_name = "FILE")]
+        constraint: Option<PathBuf>,
+        /// Retry this many times on network errors (default: install_retries config, else 2)
+        #[arg(long, value_name = "N")]
+        retries: Option<u32>,
+        /// Skip packages that are already installed (and satisfy any version spec)
+        #[arg(long, conflicts_with = "upgrade")]
+        only_if_missing: bool,
+        /// Don't check for dependency conflicts the install introduced
+        #[arg(long)]
+        no_check: bool,
     },
     /// Run a command inside an environment without activating it
+    ///
+    /// Examples:
+    ///   zen run myenv python -V
+    ///   zen run --all pip check                       # every environment
+    ///   zen run --all --label ml --continue-on-error pip check
     Run {
-        /// Environment name
+        /// Environment name (with --all, the first word of the command)
         name: String,
         /// Command and arguments to run
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         command: Vec<String>,
+        /// Run the command in every environment
+        #[arg(long)]
+        all: bool,
+        /// With --all, only environments carrying this label
+        #[arg(long, requires = "all")]
+        label: Option<String>,
+        /// With --all, keep going after a failing environment
+        #[arg(long, requires = "all")]
+        continue_on_error: bool,
+    },
+    /// Show which imports make a module slow to import
+    ///
+    /// Runs `python -X importtime -c "import MODULE"` in the environment and
+    /// lists the slowest modules it pulled in, by their own import time.
+    ///
+    /// Examples:
+    ///   zen time ml_env torch
+    ///   zen time ml_env transformers -n 30
+    Time {
+        /// Environment name
+        env: String,
+        /// Module to import (e.g. torch or matplotlib.pyplot)
+        module: String,
+        /// Number of imports to list
+        #[arg(short = 'n', long, default_value = "15")]
+        top: usize,
+    },
+    /// Open an interactive subshell with an environment activated
+    ///
+    /// Runs $SHELL with the env activated and its variables set. Exit the
+    /// subshell to get your original environment back.
+    ///
+    /// Examples:
+    ///   zen shell ml_env
+    Shell {
+        /// Environment name
+        name: String,
     },
     /// Uninstall packages from an environment
     ///
     /// Examples:
     ///   zen uninstall numpy              # uninstall from active environment
     ///   zen uninstall -n myenv requests  # uninstall from a specific environment
+    ///   zen uninstall -n myenv --group dev  # uninstall a whole install group
+    ///   zen uninstall -n myenv 'nvidia-*' --yes  # every installed match
     Uninstall {
-        /// Packages to uninstall
+        /// Packages to uninstall (globs like 'nvidia-*' match installed packages)
+        #[arg(required_unless_present = "group")]
         packages: Vec<String>,
         /// Environment name (uses active environment if omitted)
         #[arg(short = 'n', long = "name")]
         env: Option<String>,
+        /// Uninstall every package of an install group and forget the group
+        #[arg(short, long, conflicts_with = "packages")]
+        group: Option<String>,
+        /// Skip the confirmation for glob matches
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print an environment's packages as requirements.txt or pyproject.toml
+    ///
+    /// Examples:
+    ///   zen freeze myenv                          # name==version lines
+    ///   zen freeze myenv --format pyproject -o pyproject.toml
+    Freeze {
+        /// Environment name (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value = "requirements")]
+        format: FreezeFormatArg,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the path to an environment's interpreter (or another binary)
+    ///
+    /// Examples:
+    ///   zen which ml_env            # .../envs/ml_env/bin/python
+    ///   zen which ml_env pytest     # .../envs/ml_env/bin/pytest
+    Which {
+        /// Environment name (inferred from $VIRTUAL_ENV if omitted)
+        name: Option<String>,
+        /// Binary in the env's bin/ directory (default: python)
+        bin: Option<String>,
+    },
+    /// Print an environment's root directory (for scripts: cd "$(zen path myenv)")
+    Path {
+        /// Environment name (defaults to $VIRTUAL_ENV when omitted)
+        name: Option<String>,
     },
     /// Managed templates
     Template {
@@ -199,11 +377,22 @@ enum Commands {
     /// Show details of an environment
     #[command(visible_alias = "show")]
     Info {
-        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        /// Name of the environment or a glob like '*torch*' (inferred from $VIRTUAL_ENV if omitted)
         name: Option<String>,
+        /// Also list the projects linked to the environment
+        #[arg(long, visible_alias = "links")]
+        link: bool,
+        /// Report packages added, removed or changed relative to a template
+        /// (defaults to the templates the env was created from)
+        #[arg(long, value_name = "NAME:VERSION", num_args = 0..=1)]
+        compare_template: Option<Option<String>>,
     },
     /// Show system status and active environment
-    Status,
+    Status {
+        /// Print machine-readable JSON instead of the dashboard
+        #[arg(long)]
+        json: bool,
+    },
     /// Manage project-environment links
     #[command(visible_alias = "init")]
     Link {
@@ -216,6 +405,12 @@ enum Commands {
         /// File to export to
         #[arg(short, long, default_value = "zen_registry.json")]
         file: PathBuf,
+        /// Export only templates (portable, no local env paths)
+        #[arg(long, conflicts_with = "envs_only")]
+        templates_only: bool,
+        /// Export only the environment registry
+        #[arg(long)]
+        envs_only: bool,
     },
     /// Generate shell completion scripts
     #[command(hide = true)]
@@ -243,6 +438,15 @@ enum Commands {
         #[arg(default_value = "zsh")]
         shell: String,
     },
+    /// Print completion candidates from the registry (internal use for shell hooks)
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// What to complete
+        #[arg(value_enum)]
+        kind: CompleteKind,
+        /// Only print candidates starting with this prefix
+        prefix: Option<String>,
+    },
     /// Clone an existing environment (fast copy) - temporarily disabled
     #[command(hide = true)]
     Clone {
@@ -256,24 +460,69 @@ enum Commands {
     Import {
         /// The JSON file to import from
         file: PathBuf,
+        /// Keep existing environments and templates, report conflicts (default)
+        #[arg(long, conflicts_with = "overwrite")]
+        merge: bool,
+        /// Replace existing environments and templates with the imported ones
+        #[arg(long)]
+        overwrite: bool,
     },
     /// Interactive setup wizards for Zen
     Setup {
         #[command(subcommand)]
         subcommand: SetupCommands,
     },
-    /// Get or set configuration values (stack_info, env_home, etc.)
+    /// Get or set configuration values (stack_info, env_home, theme, etc.)
     Config {
         /// Configuration key to read or write (omit to list all)
         key: Option<String>,
         /// New value to set (requires key)
         value: Option<String>,
+        /// Delete a configuration key
+        #[arg(long, value_name = "KEY", conflicts_with_all = ["key", "value"])]
+        unset: Option<String>,
+        /// Allow setting a key Zen does not recognize
+        #[arg(long)]
+        force: bool,
+        /// Edit all values at once as TOML in $VISUAL/$EDITOR
+        #[arg(long, conflicts_with_all = ["key", "value", "unset"])]
+        edit: bool,
     },
     /// Reset database and config to fresh state (preserves environments on disk)
     Reset {
         /// Skip confirmation prompt
         #[arg(long)]
         yes: bool,
+        /// Reset the registry (envs, links, templates, notes) but keep config values
+        #[arg(long, conflicts_with = "config_only")]
+        db_only: bool,
+        /// Clear config values only, keep the registry
+        #[arg(long)]
+        config_only: bool,
+    },
+    /// Database maintenance: reclaim space or check for corruption
+    #[command(hide = true)]
+    Db {
+        #[command(subcommand)]
+        subcommand: DbCommands,
+    },
+    /// Find (and optionally clean up) broken or orphaned environments
+    ///
+    /// Examples:
+    ///   zen gc                  # report only, nothing is changed
+    ///   zen gc --yes            # remove dangling entries and stale links
+    ///   zen gc --discover --yes # also register unregistered venvs under Zen Home
+    ///   zen gc --stale 90       # also list envs unused for 90+ days (never removed)
+    Gc {
+        /// Apply changes (default is a dry report)
+        #[arg(short, long)]
+        yes: bool,
+        /// Also register venvs under Zen Home that are not in the database
+        #[arg(long)]
+        discover: bool,
+        /// Also list environments unused for at least this many days as removal candidates
+        #[arg(long, value_name = "DAYS")]
+        stale: Option<i64>,
     },
     /// Add, list, or remove notes on an environment
     Note {
@@ -286,11 +535,47 @@ enum Commands {
         #[command(subcommand)]
         subcommand: NoteCommands,
     },
+    /// Star an environment (shown with ★ and listed first)
+    #[command(visible_alias = "fav")]
+    Favorite {
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        name: Option<String>,
+    },
+    /// Remove the star from an environment
+    #[command(visible_alias = "unfav")]
+    Unfavorite {
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        name: Option<String>,
+    },
     /// Manage environment labels (add, rm, list)
     Label {
         #[command(subcommand)]
         subcommand: LabelCommands,
     },
+    /// Short names for environments, accepted wherever an env name is (add, rm, list)
+    Alias {
+        #[command(subcommand)]
+        subcommand: AliasCommands,
+    },
+    /// Save and restore an environment's exact package set (create, list, restore)
+    ///
+    /// Examples:
+    ///   zen snapshot create ml before-torch-upgrade
+    ///   zen snapshot restore ml before-torch-upgrade
+    Snapshot {
+        #[command(subcommand)]
+        subcommand: SnapshotCommands,
+    },
+    /// Per-environment variables, exported on activation and by `zen run`
+    ///
+    /// Examples:
+    ///   zen env set HF_HOME /data/hf myenv
+    ///   zen env set CUDA_VISIBLE_DEVICES 0     # in the active environment
+    ///   zen env list myenv
+    Env {
+        #[command(subcommand)]
+        subcommand: EnvVarCommands,
+    },
     /// Find a package across all environments (substring match by default)
     Find {
         /// Package name or pattern to search for
@@ -298,6 +583,17 @@ enum Commands {
         /// Exact name match only (default is substring/contains)
         #[arg(long, short)]
         exact: bool,
+        /// Scan site-packages on disk instead of using the package index
+        #[arg(long)]
+        live: bool,
+        /// Only print how many environments have a match
+        #[arg(long, conflicts_with_all = ["by_env", "output"])]
+        count: bool,
+        /// Group matches under each environment
+        #[arg(long, conflicts_with = "output")]
+        by_env: bool,
+        #[command(flatten)]
+        output: OutputArgs,
     },
     /// Inspect a specific package in an environment (like pip show)
     Inspect {
@@ -311,21 +607,62 @@ enum Commands {
         /// Long format: one package per line with version
         #[arg(short = 'l')]
         long: bool,
+        /// Show the dependency tree, rooted at packages nothing else requires
+        #[arg(long, conflicts_with = "package")]
+        tree: bool,
+        /// Explain which packages pull PACKAGE into the environment
+        #[arg(long, value_name = "PACKAGE", conflicts_with_all = ["package", "tree"])]
+        why: Option<String>,
+        /// List the files PACKAGE installed, from its .dist-info/RECORD
+        #[arg(long, requires = "package", conflicts_with = "output")]
+        files: bool,
+        #[command(flatten)]
+        output: OutputArgs,
     },
-    /// Compare packages between two environments
+    /// Compare packages between environments
+    ///
+    /// Examples:
+    ///   zen diff dev prod                # side by side
+    ///   zen diff py311 py312 py313 -d    # matrix, only packages that differ
     Diff {
-        /// First environment
-        env1: String,
-        /// Second environment
-        env2: String,
+        /// Environments to compare (three or more render a matrix)
+        #[arg(required = true, num_args = 2..)]
+        envs: Vec<String>,
         /// Only show differences (default shows all)
         #[arg(short = 'd', long)]
         only_diff: bool,
+        #[command(flatten)]
+        output: OutputArgs,
     },
     /// Check environment health: Python binary, CUDA consistency, dependency conflicts
+    ///
+    /// Exit codes: 0 for OK or MINOR, 0 for DRIFT (1 with --strict), 1 for BROKEN
+    /// or when the check itself fails.
     Health {
+        /// Name of the environment or a glob like '*torch*' (inferred from $VIRTUAL_ENV if omitted)
+        name: Option<String>,
+        /// Print the report as JSON (levels: pass, info, warn, fail)
+        #[arg(long)]
+        json: bool,
+        /// Also exit 1 on DRIFT (warn-level issues)
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Check installed files against each package's RECORD
+    ///
+    /// Reports files that are missing, and with --hashes files whose sha256
+    /// no longer matches. Exits 1 when any package has problems.
+    Verify {
         /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
         name: Option<String>,
+        /// Also hash every file and compare it with RECORD (slower)
+        #[arg(long)]
+        hashes: bool,
+    },
+    /// Show the CUDA versions PyTorch publishes wheels for
+    Cuda {
+        #[command(subcommand)]
+        subcommand: CudaCommands,
     },
     /// View the activity log (recent operations)
     #[command(alias = "logs")]
@@ -338,12 +675,30 @@ enum Commands {
         /// Clear the entire log
         #[arg(long)]
         clear: bool,
+        /// Rotate the log now (zen.log → zen.log.1), regardless of its size
+        #[arg(long, conflicts_with = "clear")]
+        rotate: bool,
+        /// Print entries as JSON lines (timestamp, source, action, detail)
+        #[arg(long)]
+        json: bool,
+        /// Only entries from this origin (your commands vs the MCP agent)
+        #[arg(long, value_enum)]
+        source: Option<LogSourceArg>,
+        /// Only this action, including sub-actions (install matches install:retry)
+        #[arg(long)]
+        action: Option<String>,
     },
     /// Start the Model Context Protocol (MCP) server
     #[command(hide = true)]
     Mcp,
 }
 
+#[derive(Subcommand, Clone, Debug)]
+enum CudaCommands {
+    /// List CUDA versions, their `torch-cuXXX` aliases and PyTorch index URLs
+    List,
+}
+
 #[derive(Subcommand, Clone, Debug)]
 enum SetupCommands {
     /// Import existing environments from a directory
@@ -354,6 +709,12 @@ enum SetupCommands {
         /// Automatic yes to prompts
         #[arg(short, long)]
         yes: bool,
+        /// Walk deeper than the default 3 levels (8 unless --depth is given)
+        #[arg(short, long)]
+        recursive: bool,
+        /// How many directory levels to walk
+        #[arg(long, value_name = "N")]
+        depth: Option<usize>,
     },
     /// Configure tracked packages for stack view
     StackInfo,
@@ -388,6 +749,10 @@ enum LinkCommands {
     List {
         /// Project directory to list links for (default: current directory)
         path: Option<String>,
+        /// Tab-separated output for scripts:
+        /// env, path, tag, is_default, link_type, count, last_at
+        #[arg(long)]
+        porcelain: bool,
     },
     /// Remove stale links (deleted envs or missing project dirs)
     Prune,
@@ -420,14 +785,14 @@ enum LabelCommands {
     Add {
         /// Label to add (e.g., dev, testing, ml, debug)
         label: String,
-        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        /// Name of the environment or a glob like '*torch*' (inferred from $VIRTUAL_ENV if omitted)
         env: Option<String>,
     },
     /// Remove a label from an environment
     Rm {
         /// Label to remove
         label: String,
-        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        /// Name of the environment or a glob like '*torch*' (inferred from $VIRTUAL_ENV if omitted)
         env: Option<String>,
     },
     /// List labels for an environment (or all with --all)
@@ -440,6 +805,102 @@ enum LabelCommands {
     },
 }
 
+#[derive(Subcommand, Clone, Debug)]
+enum AliasCommands {
+    /// Point an alias at an environment
+    Add {
+        /// Short name (e.g., px)
+        alias: String,
+        /// Environment the alias stands for
+        env: String,
+    },
+    /// Remove an alias (the environment is untouched)
+    Rm {
+        /// Alias to remove
+        alias: String,
+    },
+    /// List aliases and their environments
+    List,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum DbCommands {
+    /// Rebuild the database file to reclaim free space
+    Vacuum,
+    /// Run SQLite's integrity check and list any problems
+    Check,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum SnapshotCommands {
+    /// Record the environment's installed packages
+    Create {
+        /// Environment name
+        env: String,
+        /// Snapshot label (default: a timestamp like 20260101-120000)
+        label: Option<String>,
+    },
+    /// List an environment's snapshots
+    List {
+        /// Environment name
+        env: String,
+    },
+    /// Install and uninstall packages until the environment matches a snapshot
+    Restore {
+        /// Environment name
+        env: String,
+        /// Snapshot label
+        label: String,
+        /// Skip the confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Show the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand, Clone, Debug)]
+enum EnvVarCommands {
+    /// Set a variable for an environment
+    Set {
+        /// Variable name (e.g., HF_HOME, CUDA_VISIBLE_DEVICES)
+        key: String,
+        /// Value to export
+        value: String,
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+    },
+    /// Print a variable's value
+    Get {
+        /// Variable name
+        key: String,
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+    },
+    /// Remove a variable from an environment
+    Unset {
+        /// Variable name
+        key: String,
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+    },
+    /// List variables for an environment
+    List {
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+    },
+    /// Print shell code exporting the variables (used by the shell hook)
+    #[command(hide = true)]
+    Export {
+        /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
+        env: Option<String>,
+        /// Shell syntax to emit (bash, zsh, fish)
+        #[arg(long, default_value = "bash")]
+        shell: String,
+    },
+}
+
 #[derive(Subcommand, Clone, Debug)]
 enum NoteCommands {
     /// Add a note to an environment
@@ -448,6 +909,9 @@ enum NoteCommands {
         message: String,
         /// Name of the environment (inferred from $VIRTUAL_ENV if omitted)
         env: Option<String>,
+        /// Tag the note (e.g. todo, bug, idea)
+        #[arg(long)]
+        tag: Option<String>,
     },
     /// List notes for an environment (or all with --all)
     List {
@@ -456,6 +920,14 @@ enum NoteCommands {
         /// Show all notes across all environments
         #[arg(short, long)]
         all: bool,
+        /// Only show notes with this tag
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Search note text across all environments (case-insensitive)
+    Search {
+        /// Text to look for
+        term: String,
     },
     /// Remove a note by its UUID (or prefix)
     Rm {
@@ -467,12 +939,22 @@ enum NoteCommands {
 #[derive(Subcommand, Clone, Debug)]
 enum TemplateCommands {
     /// Start recording a new template session
+    ///
+    /// Examples:
+    ///   zen template create torch:2.10                         # interactive session
+    ///   zen template create torch:2.10 --from-requirements reqs.txt
     Create {
         /// Name of the template (e.g., torch:2.10)
         name: String,
         /// Python version
         #[arg(long)]
         python: Option<String>,
+        /// Build the template from a requirements file instead of a session
+        #[arg(long, value_name = "FILE")]
+        from_requirements: Option<PathBuf>,
+        /// Replace an existing template (with --from-requirements)
+        #[arg(long, requires = "from_requirements")]
+        force: bool,
     },
     /// Save the current recording session
     Save,
@@ -525,6 +1007,22 @@ enum TemplateCommands {
         /// Package name or step number to remove
         target: String,
     },
+    /// Create a template from an existing environment's packages
+    ///
+    /// Packages installed through zen are pinned; dependencies stay unpinned.
+    /// Git and editable installs keep their source.
+    ///
+    /// Examples:
+    ///   zen template from-env myproject ml-base:v1
+    FromEnv {
+        /// Environment to snapshot
+        env: String,
+        /// Template name (e.g., ml-base or ml-base:v1)
+        name: String,
+        /// Overwrite an existing template with the same name:version
+        #[arg(long)]
+        force: bool,
+    },
     /// Export a template to a portable TOML file
     ///
     /// Examples:
@@ -542,21 +1040,87 @@ enum TemplateCommands {
     ///
     /// Examples:
     ///   zen template import ml-base.toml
+    ///   zen template import ml-base.toml --force   # replace existing name:version
     #[clap(name = "import")]
     ImportTpl {
         /// Path to TOML file
         file: String,
+        /// Overwrite an existing template with the same name:version
+        #[arg(long)]
+        force: bool,
     },
 }
 
+/// Landing screen command groups, ordered by usage frequency.
+const LANDING_GROUPS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Getting Started",
+        &[
+            ("setup init", "Import existing environments"),
+            ("setup stack-info", "Configure tracked packages"),
+        ],
+    ),
+    (
+        "Core Commands",
+        &[
+            ("activate", "Activate an environment"),
+            ("deactivate", "Deactivate current environment"),
+            ("list", "List all environments"),
+            ("create", "Create a new environment"),
+            ("status", "Show system status"),
+        ],
+    ),
+    (
+        "Environment Tools",
+        &[
+            ("info", "Show environment details"),
+            ("install", "Add packages to an environment"),
+            ("find", "Find a package across environments"),
+            ("inspect", "Inspect a package in an environment"),
+            ("diff", "Compare two environments"),
+            ("health", "Check environment health"),
+        ],
+    ),
+    (
+        "Project & Organization",
+        &[
+            ("link add", "Link environment to project"),
+            ("label add", "Tag environments with labels"),
+            ("note", "Add notes to environments"),
+            ("favorite", "Star environments in the list"),
+        ],
+    ),
+    (
+        "Configuration",
+        &[
+            ("config", "Get or set configuration"),
+            ("template", "Manage environment templates"),
+        ],
+    ),
+];
+
+/// Shortens `text` to at most `width` characters, ending in "…" when cut.
+fn fit_width(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(width.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
 /// Displays the branded landing screen when `zen` is invoked without a subcommand.
 ///
-/// Shows the 禅 icon, version, live status (environment count, active environment,
-/// terminal width), and commands organized into five groups by usage frequency.
+/// Shows the 禅 icon, version, live status (environment count, active environment),
+/// and commands organized into five groups by usage frequency. Descriptions line up
+/// in one column after the longest command and are shortened to the terminal width.
 fn print_landing_screen(db: &Database, _home: &Path) {
     use terminal_size::{Width, terminal_size};
 
     let full_version = env!("ZEN_VERSION");
+    let term_cols = terminal_size()
+        .map(|(Width(w), _)| w as usize)
+        .unwrap_or(80);
 
     // Header with kanji icon and version
     eprintln!();
@@ -568,129 +1132,44 @@ fn print_landing_screen(db: &Database, _home: &Path) {
     eprintln!("  {}", "Peace of mind for Python environments".dimmed());
     eprintln!();
 
-    // Live status: environment count, active virtualenv, and detected list format
-    let env_count = db.list_envs().map(|e| e.len()).unwrap_or(0);
+    // Live status: environment count and active virtualenv
+    let env_count = db.count_envs().unwrap_or(0);
     let active_env = std::env::var("VIRTUAL_ENV").ok().map(|p| {
         std::path::Path::new(&p)
             .file_name()
             .map(|f| f.to_string_lossy().to_string())
             .unwrap_or(p)
     });
-    let _term_cols = terminal_size().map(|(Width(w), _)| w).unwrap_or(80);
 
     eprintln!("  {} {} environments managed", "●".green(), env_count);
     if let Some(ref env_name) = active_env {
-        eprintln!("  {} Active: {}", "●".cyan(), env_name.bold());
+        // "  ● Active: " takes 12 columns
+        let name = fit_width(env_name, term_cols.saturating_sub(12).max(8));
+        eprintln!("  {} Active: {}", "●".cyan(), name.bold());
     } else {
         eprintln!("  {} No active environment", "○".dimmed());
     }
     eprintln!();
 
-    // Command groups ordered by usage frequency
-    eprintln!("  {}", "Getting Started".bold().underline());
-    eprintln!(
-        "    {}       {}",
-        "setup init".cyan(),
-        "Import existing environments".dimmed()
-    );
-    eprintln!(
-        "    {}  {}",
-        "setup stack-info".cyan(),
-        "Configure tracked packages".dimmed()
-    );
-    eprintln!();
-
-    eprintln!("  {}", "Core Commands".bold().underline());
-    eprintln!(
-        "    {}        {}",
-        "activate".cyan(),
-        "Activate an environment".dimmed()
-    );
-    eprintln!(
-        "    {}        {}",
-        "deactivate".cyan(),
-        "Deactivate current environment".dimmed()
-    );
-    eprintln!(
-        "    {}            {}",
-        "list".cyan(),
-        "List all environments".dimmed()
-    );
-    eprintln!(
-        "    {}          {}",
-        "create".cyan(),
-        "Create a new environment".dimmed()
-    );
-    eprintln!(
-        "    {}          {}",
-        "status".cyan(),
-        "Show system status".dimmed()
-    );
-    eprintln!();
-
-    eprintln!("  {}", "Environment Tools".bold().underline());
-    eprintln!(
-        "    {}            {}",
-        "info".cyan(),
-        "Show environment details".dimmed()
-    );
-    eprintln!(
-        "    {}         {}",
-        "install".cyan(),
-        "Add packages to an environment".dimmed()
-    );
-    eprintln!(
-        "    {}            {}",
-        "find".cyan(),
-        "Find a package across environments".dimmed()
-    );
-    eprintln!(
-        "    {}         {}",
-        "inspect".cyan(),
-        "Inspect a package in an environment".dimmed()
-    );
-    eprintln!(
-        "    {}            {}",
-        "diff".cyan(),
-        "Compare two environments".dimmed()
-    );
-    eprintln!(
-        "    {}          {}",
-        "health".cyan(),
-        "Check environment health".dimmed()
-    );
-    eprintln!();
-
-    eprintln!("  {}", "Project & Organization".bold().underline());
-    eprintln!(
-        "    {}        {}",
-        "link add".cyan(),
-        "Link environment to project".dimmed()
-    );
-    eprintln!(
-        "    {}       {}",
-        "label add".cyan(),
-        "Tag environments with labels".dimmed()
-    );
-    eprintln!(
-        "    {}         {}",
-        "note".cyan(),
-        "Add notes to environments".dimmed()
-    );
-    eprintln!();
-
-    eprintln!("  {}", "Configuration".bold().underline());
-    eprintln!(
-        "    {}          {}",
-        "config".cyan(),
-        "Get or set configuration".dimmed()
-    );
-    eprintln!(
-        "    {}        {}",
-        "template".cyan(),
-        "Manage environment templates".dimmed()
-    );
-    eprintln!();
+    // Descriptions start two columns after the longest command
+    let cmd_width = LANDING_GROUPS
+        .iter()
+        .flat_map(|(_, cmds)| cmds.iter().map(|(cmd, _)| cmd.chars().count()))
+        .max()
+        .unwrap_or(0);
+    let desc_width = term_cols.saturating_sub(4 + cmd_width + 2).max(12);
+    for (group, cmds) in LANDING_GROUPS {
+        eprintln!("  {}", group.bold().underline());
+        for (cmd, desc) in *cmds {
+            eprintln!(
+                "    {}{}  {}",
+                cmd.cyan(),
+                " ".repeat(cmd_width - cmd.chars().count()),
+                fit_width(desc, desc_width).dimmed()
+            );
+        }
+        eprintln!();
+    }
 
     eprintln!(
         "  {} {} for detailed usage",
@@ -741,39 +1220,528 @@ fn print_link_entry(
     );
 }
 
-/// Resolves an environment name from an optional argument or `$VIRTUAL_ENV`.
-///
-/// Used by commands that support auto-detection: info, inspect, health,
-/// label add/rm/list, link add/rm.
-fn resolve_env_name(
-    name: Option<String>,
-    db: &Database,
-) -> Result<String, Box<dyn std::error::Error>> {
-    if let Some(n) = name {
-        return Ok(n);
+/// Prints the projects linked to an environment for `zen info --link`.
+fn print_env_projects(db: &Database, env_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(env_id) = db.get_env_id(env_name)? else {
+        return Ok(());
+    };
+    let projects = db.get_projects_for_env(env_id)?;
+    if projects.is_empty() {
+        println!("{}      {}", "Links:".bold(), "none".dimmed());
+        return Ok(());
     }
-    // Try $VIRTUAL_ENV
-    if let Ok(venv) = std::env::var("VIRTUAL_ENV") {
-        let venv_path = std::path::Path::new(&venv);
-        let envs = db.list_envs()?;
-        // Match by path
-        if let Some((name, ..)) = envs
-            .iter()
-            .find(|(_, p, ..)| std::path::Path::new(p) == venv_path)
-        {
-            return Ok(name.clone());
-        }
-        // Fall back to directory basename
-        if let Some(basename) = venv_path.file_name() {
-            return Ok(basename.to_string_lossy().to_string());
-        }
+    println!("{}", "Linked projects:".bold());
+    for (project_path, tag, is_default, link_type, count, last_at) in projects {
+        let tag_str = tag.map(|t| format!(" ({})", t)).unwrap_or_default();
+        let default_marker = if is_default {
+            " [default]".green().to_string()
+        } else {
+            String::new()
+        };
+        let type_icon = if link_type == "user" { " ★" } else { "" };
+        let stats = if count > 0 {
+            let last_str = last_at
+                .map(|t| format!(", last: {}", &t[..10]))
+                .unwrap_or_default();
+            format!(" [{}x{}]", count, last_str)
+        } else {
+            String::new()
+        };
+        let missing = if Path::new(&project_path).exists() {
+            String::new()
+        } else {
+            " (missing)".tone(Tone::Warn).to_string()
+        };
+        println!(
+            "  • {}{}{}{}{}{}",
+            project_path,
+            type_icon,
+            tag_str,
+            default_marker,
+            missing,
+            stats.dimmed()
+        );
     }
-    Err(
+    Ok(())
+}
+
+/// Prints how an environment has drifted from a template (`zen info --compare-template`).
+fn print_template_drift(diff: &crate::types::EnvDiff) {
+    if diff.is_empty() {
+        println!(
+            "{}   {} {}",
+            "Template:".bold(),
+            diff.first,
+            "✓ matches".tone(Tone::Accent)
+        );
+        return;
+    }
+    println!(
+        "{}   {} {}",
+        "Template:".bold(),
+        diff.first,
+        "drifted".tone(Tone::Warn)
+    );
+    if diff.python.0 != diff.python.1 {
+        println!(
+            "  ~ python {} → {}",
+            diff.python.0,
+            diff.python.1.tone(Tone::Warn)
+        );
+    }
+    for (name, version) in &diff.only_in_second {
+        println!(
+            "  {} {} {} {}",
+            "+".green(),
+            name,
+            version.dimmed(),
+            "(not in template)".dimmed()
+        );
+    }
+    for (name, version) in &diff.only_in_first {
+        println!(
+            "  {} {} {} {}",
+            "-".red(),
+            name,
+            version.dimmed(),
+            "(missing)".tone(Tone::Warn)
+        );
+    }
+    for (name, tpl, env) in &diff.changed {
+        println!(
+            "  {} {} {} → {}",
+            "~".tone(Tone::Warn),
+            name,
+            tpl,
+            env.tone(Tone::Warn)
+        );
+    }
+}
+
+/// Prints notes as a table: UUID, optional Env and Tag columns, note, timestamp.
+///
+/// The Tag column appears only when at least one note has a tag.
+fn print_notes_table(
+    comments: Vec<(
+        String,
+        String,
+        Option<String>,
+        String,
+        Option<String>,
+        String,
+    )>,
+    show_env_col: bool,
+) {
+    use comfy_table::{Cell, Color};
+    let show_tag_col = comments.iter().any(|c| c.4.is_some());
+    let mut table = crate::table::new_table();
+    let mut header = vec!["UUID"];
+    if show_env_col {
+        header.push("Env");
+    }
+    if show_tag_col {
+        header.push("Tag");
+    }
+    header.extend(["Note", "Timestamp"]);
+    table.set_header(header);
+    for (uuid, _pp, env_name, msg, note_tag, ts) in comments {
+        let short_uuid = if uuid.len() > 8 {
+            format!("{}…", &uuid[..8])
+        } else {
+            uuid.clone()
+        };
+        let mut row = vec![Cell::new(short_uuid).fg(Color::DarkGrey)];
+        if show_env_col {
+            row.push(Cell::new(env_name.unwrap_or_else(|| "-".into())).fg(Color::Cyan));
+        }
+        if show_tag_col {
+            row.push(Cell::new(note_tag.unwrap_or_default()).fg(Color::Yellow));
+        }
+        row.push(Cell::new(msg));
+        row.push(Cell::new(ts).fg(Color::DarkGrey));
+        table.add_row(row);
+    }
+    println!("{}", table);
+}
+
+/// Renders `zen diff` for three or more environments.
+///
+/// One row per package and one column per environment. Cells that differ
+/// from the row's most common version (a missing package counts as a value)
+/// are highlighted.
+fn print_diff_matrix(names: &[String], paths: &[String], only_diff: bool) {
+    use comfy_table::{Cell, Color};
+    use std::collections::{BTreeSet, HashMap};
+
+    let pkgs: Vec<HashMap<String, String>> = paths
+        .iter()
+        .map(|path| {
+            utils::get_packages(path)
+                .into_iter()
+                .map(|p| (p.name, p.version.unwrap_or_else(|| "?".to_string())))
+                .collect()
+        })
+        .collect();
+    let all: BTreeSet<&String> = pkgs.iter().flat_map(|m| m.keys()).collect();
+
+    let mut table = crate::table::new_table();
+    let mut header = vec!["Package".to_string()];
+    header.extend(names.iter().cloned());
+    table.set_header(header);
+
+    let mut differing = 0;
+    for pkg in &all {
+        let cells: Vec<Option<&String>> = pkgs.iter().map(|m| m.get(*pkg)).collect();
+        // Most common value; ties go to the leftmost environment
+        let modal = cells
+            .iter()
+            .max_by_key(|c| {
+                let count = cells.iter().filter(|o| o == c).count();
+                let first = cells.iter().position(|o| o == *c).unwrap_or(0);
+                (count, std::cmp::Reverse(first))
+            })
+            .copied()
+            .flatten();
+        let uniform = cells.iter().all(|c| *c == modal);
+        if !uniform {
+            differing += 1;
+        } else if only_diff {
+            continue;
+        }
+
+        let mut row = vec![Cell::new(pkg)];
+        for cell in &cells {
+            row.push(match (cell, *cell == modal) {
+                (Some(v), true) => Cell::new(v),
+                (Some(v), false) => Cell::new(v).fg(Color::Yellow),
+                (None, true) => Cell::new("--").fg(Color::DarkGrey),
+                (None, false) => Cell::new("--").fg(Color::Red),
+            });
+        }
+        table.add_row(row);
+    }
+    println!("{}", table);
+    println!(
+        "{} of {} package(s) differ across {} environments",
+        differing,
+        all.len(),
+        names.len()
+    );
+}
+
+/// Resolves an environment name from an optional argument or `$VIRTUAL_ENV`.
+///
+/// Used by commands that support auto-detection: info, inspect, health,
+/// label add/rm/list, link add/rm.
+fn resolve_env_name(
+    name: Option<String>,
+    db: &Database,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if let Some(n) = name {
+        if let Some(target) = db.resolve_alias(&n)? {
+            return Ok(target);
+        }
+        // Tolerate case and separator slips ("ML-Env" for "ml_env") when unambiguous
+        let names: Vec<String> = db.list_envs()?.into_iter().map(|e| e.0).collect();
+        if !names.contains(&n)
+            && let Some(matched) = utils::loose_match(&n, &names)
+        {
+            eprintln!("Using '{}' for '{}'.", matched, n);
+            return Ok(matched);
+        }
+        return Ok(n);
+    }
+    // Try $VIRTUAL_ENV
+    if let Ok(venv) = std::env::var("VIRTUAL_ENV") {
+        let venv_path = std::path::Path::new(&venv);
+        let envs = db.list_envs()?;
+        // Match by path
+        if let Some((name, ..)) = envs
+            .iter()
+            .find(|(_, p, ..)| std::path::Path::new(p) == venv_path)
+        {
+            return Ok(name.clone());
+        }
+        // Fall back to directory basename
+        if let Some(basename) = venv_path.file_name() {
+            return Ok(basename.to_string_lossy().to_string());
+        }
+    }
+    Err(
         "No environment specified. Activate one with 'za <env>' or pass its name as an argument."
             .into(),
     )
 }
 
+/// Maps an alias to the environment it stands for; other names pass through.
+///
+/// For commands that take an exact environment name rather than going
+/// through `resolve_env_name`.
+fn expand_alias(name: String, db: &Database) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(db.resolve_alias(&name)?.unwrap_or(name))
+}
+
+/// Like `resolve_env_name`, but a glob (`*`, `?`) expands to every matching
+/// registered environment. Errors when a glob matches nothing.
+fn resolve_env_names(
+    name: Option<String>,
+    db: &Database,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    match name {
+        Some(pattern) if utils::is_glob(&pattern) => {
+            let names: Vec<String> = db.list_envs()?.into_iter().map(|e| e.0).collect();
+            let matched = utils::match_envs(&pattern, &names);
+            if matched.is_empty() {
+                return Err(format!("No environments match '{}'", pattern).into());
+            }
+            Ok(matched)
+        }
+        name => Ok(vec![resolve_env_name(name, db)?]),
+    }
+}
+
+/// Previews, confirms and removes (or with `cached`, untracks) the given envs.
+///
+/// Shared by the single-name and glob forms of `zen rm`. Returns `false` when
+/// any removal failed, so the caller can exit non-zero.
+fn remove_envs(
+    db: &Database,
+    ops: &crate::ops::ZenOps,
+    home: &Path,
+    names: &[String],
+    yes: bool,
+    cached: bool,
+    dry_run: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let envs = db.list_envs()?;
+    let env_path = |name: &str| {
+        envs.iter()
+            .find(|(n, ..)| n == name)
+            .map(|(_, p, ..)| p.clone())
+    };
+    if dry_run {
+        for name in names {
+            let path =
+                env_path(name).unwrap_or_else(|| home.join(name).to_string_lossy().to_string());
+            let disk = if cached {
+                "kept on disk".dimmed().to_string()
+            } else if Path::new(&path).exists() {
+                "will be deleted".tone(Tone::Warn).to_string()
+            } else {
+                "already missing".dimmed().to_string()
+            };
+            println!("Would remove environment '{}':", name.tone(Tone::Accent));
+            println!("  {:<8} {} ({})", "Path:", path, disk);
+            if let Some(env_id) = db.get_env_id(name)? {
+                println!(
+                    "  {:<8} {} project(s)",
+                    "Links:",
+                    db.count_env_links(env_id)?
+                );
+                println!("  {:<8} {}", "Labels:", db.count_env_labels(env_id)?);
+                println!("  {:<8} {}", "Notes:", db.count_env_comments(env_id)?);
+            } else {
+                println!("  {:<8} not in registry (orphaned directory)", "Registry:");
+            }
+        }
+        println!("{}", "Dry run — nothing was removed.".dimmed());
+        return Ok(true);
+    }
+    if !yes {
+        use dialoguer::{Confirm, theme::ColorfulTheme};
+        let prompt_msg = match (names, cached) {
+            ([name], true) => format!(
+                "Untrack environment '{}' from registry? (files kept on disk)",
+                name
+            ),
+            ([name], false) => format!("Are you sure you want to remove environment '{}'?", name),
+            (_, true) => format!(
+                "Untrack these {} environments from registry? (files kept on disk)",
+                names.len()
+            ),
+            (_, false) => format!("Remove these {} environments?", names.len()),
+        };
+        let confirmed = match Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt(prompt_msg)
+            .default(false)
+            .interact()
+        {
+            Ok(v) => v,
+            Err(_) => {
+                // Ctrl+C — exit silently
+                println!();
+                return Ok(true);
+            }
+        };
+        if !confirmed {
+            println!("Abort.");
+            return Ok(true);
+        }
+    }
+    let mut all_ok = true;
+    for name in names {
+        let env_name = types::EnvName::new(name).map_err(|e| e.to_string())?;
+        let result = if cached {
+            // DB-only removal — keep files on disk
+            activity_log::log_activity("cli", "rm:cached", name);
+            ops.untrack_env(&env_name)
+        } else {
+            println!("{} {}...", "Removing".magenta().bold(), name);
+            activity_log::log_activity("cli", "rm", name);
+            ops.remove_env(&env_name)
+        };
+        match result {
+            Ok(resp) => {
+                println!("{}", resp);
+                // Warn if under ZEN_HOME (auto-discovery will re-add it)
+                if cached
+                    && let Some(ep) = env_path(name)
+                    && ep.starts_with(home.to_string_lossy().as_ref())
+                {
+                    eprintln!(
+                        "{} This env is under Zen Home and will be re-discovered on next list. Use 'zen rm' to delete it from disk.",
+                        "⚠ Note:".tone(Tone::Warn)
+                    );
+                }
+            }
+            Err(e) => {
+                activity_log::log_activity("cli", "rm:error", &format!("{} - {}", name, e));
+                eprintln!("{} {}", "Error:".red(), e);
+                all_ok = false;
+            }
+        }
+    }
+    Ok(all_ok)
+}
+
+/// Parses an edited config file into `(key, value)` pairs.
+///
+/// Values may be strings, numbers or booleans; each must pass the same checks
+/// as `zen config <key> <value>`.
+fn parse_config_edit(content: &str, force: bool) -> Result<Vec<(String, String)>, String> {
+    let table: toml::Table = toml::from_str(content).map_err(|e| e.message().to_string())?;
+    let mut values = Vec::new();
+    for (key, value) in table {
+        let value = match value {
+            toml::Value::String(s) => s,
+            toml::Value::Integer(i) => i.to_string(),
+            toml::Value::Boolean(b) => b.to_string(),
+            other => {
+                return Err(format!(
+                    "{}: expected a string, number or boolean, got {}",
+                    key,
+                    other.type_str()
+                ));
+            }
+        };
+        if key == "schema_version" {
+            return Err("schema_version is managed by Zen and can't be edited".to_string());
+        }
+        if !db::KNOWN_CONFIG_KEYS.contains(&key.as_str()) && !force {
+            return Err(format!(
+                "Unknown config key '{}' (use --force to keep it)",
+                key
+            ));
+        }
+        crate::validation::validate_config_value(&key, &value)?;
+        values.push((key, value));
+    }
+    Ok(values)
+}
+
+/// `zen config --edit`: round-trips the config through a TOML file in the editor.
+///
+/// A file that fails to parse or validate is reopened with the error on top,
+/// so edits are never lost; saving it unchanged aborts without applying anything.
+fn edit_config(db: &Database, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let current: Vec<(String, String)> = db
+        .list_all_config()?
+        .into_iter()
+        .filter(|(k, _)| k != "schema_version")
+        .collect();
+    let mut content = format!(
+        "# Zen configuration. Save to apply; delete a line to unset that key.\n\
+         # Known keys: {}\n",
+        db::KNOWN_CONFIG_KEYS
+            .iter()
+            .filter(|k| **k != "schema_version")
+            .copied()
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for (k, v) in &current {
+        content.push_str(&format!("{} = {}\n", k, toml::Value::String(v.clone())));
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut editor_cmd = editor.split_whitespace();
+    let program = editor_cmd.next().unwrap_or("vi");
+    let editor_args: Vec<&str> = editor_cmd.collect();
+    let path = std::env::temp_dir().join(format!("zen-config-{}.toml", std::process::id()));
+
+    let edited = loop {
+        std::fs::write(&path, &content)?;
+        let status = std::process::Command::new(program)
+            .args(&editor_args)
+            .arg(&path)
+            .status();
+        let edited = std::fs::read_to_string(&path);
+        if !status.as_ref().is_ok_and(|s| s.success()) {
+            let _ = std::fs::remove_file(&path);
+            return Err(
+                format!("Editor '{}' did not exit cleanly; config unchanged", editor).into(),
+            );
+        }
+        let edited = edited?;
+        if edited == content {
+            let _ = std::fs::remove_file(&path);
+            println!("No changes; config unchanged.");
+            return Ok(());
+        }
+        match parse_config_edit(&edited, force) {
+            Ok(values) => break values,
+            Err(e) => {
+                // Keep the user's text, replacing any earlier error banner
+                let body: String = edited
+                    .lines()
+                    .filter(|l| !l.starts_with("# ERROR:"))
+                    .map(|l| format!("{}\n", l))
+                    .collect();
+                content = format!(
+                    "# ERROR: {}\n# ERROR: fix it and save, or quit without saving to abort.\n{}",
+                    e, body
+                );
+            }
+        }
+    };
+    let _ = std::fs::remove_file(&path);
+
+    let mut changes = 0;
+    for (k, v) in &edited {
+        if current.iter().any(|(ck, cv)| ck == k && cv == v) {
+            continue;
+        }
+        db.set_config(k, v)?;
+        activity_log::log_activity("cli", "config", &format!("{} = {}", k, v));
+        println!("{} Config updated: {} = {}", "✓".green(), k, v);
+        changes += 1;
+    }
+    for (k, _) in &current {
+        if !edited.iter().any(|(ek, _)| ek == k) {
+            db.unset_config(k)?;
+            activity_log::log_activity("cli", "config:unset", k);
+            println!("{} Config removed: {}", "✓".green(), k);
+            changes += 1;
+        }
+    }
+    if changes == 0 {
+        println!("No changes; config unchanged.");
+    }
+    Ok(())
+}
+
 ///
 /// Interactive REPL for template create/edit.
 ///
@@ -787,6 +1755,7 @@ fn template_repl(
     template_version: &str,
     env_path: &str,
     is_new: bool,
+    use_uv: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     use rustyline::error::ReadlineError;
 
@@ -798,7 +1767,6 @@ fn template_repl(
     };
 
     let prompt = format!("{}:{}> ", template_name, template_version);
-    let use_uv = which::which("uv").is_ok();
 
     // History file alongside the zen DB
     let history_path = std::env::var("HOME")
@@ -927,7 +1895,12 @@ fn template_repl(
 /// appropriate command handler. Displays the branded landing screen when no
 /// subcommand is provided.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut cli = Cli::parse();
+    use clap::{CommandFactory, FromArgMatches, parser::ValueSource};
+
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // Only the built-in default may be replaced by the `env_home` config
+    let home_is_default = matches.value_source("home") == Some(ValueSource::DefaultValue);
 
     // Restore terminal cursor on Ctrl+C.
     // dialoguer hides the cursor during prompts; SIGINT without cleanup
@@ -939,14 +1912,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     })
     .ok();
 
+    // Scan-heavy read commands don't need write access; this keeps them clear of
+    // writers' locks and working when the DB directory is read-only
+    let read_only = matches!(
+        cli.command,
+        Some(Commands::List { .. } | Commands::Find { .. } | Commands::Inspect { .. })
+    );
+    let db = if read_only {
+        Database::open_readonly(cli.db_path.as_deref())
+            .or_else(|_| Database::open(cli.db_path.as_deref()))?
+    } else {
+        Database::open(cli.db_path.as_deref())?
+    };
+
+    // Precedence: --home flag > ZEN_HOME env > `env_home` config > default
+    let mut home_from_config = false;
+    if home_is_default && let Some(env_home) = db.get_config("env_home")? {
+        cli.home = PathBuf::from(env_home);
+        home_from_config = true;
+    }
+
     // Expand ~ to $HOME since PathBuf doesn't handle tilde
     if cli.home.starts_with("~")
         && let Ok(home) = std::env::var("HOME")
     {
         cli.home = PathBuf::from(cli.home.to_string_lossy().replacen('~', &home, 1));
     }
-
-    let db = Database::open(cli.db_path.as_deref())?;
+    let theme = db.get_config("theme").ok().flatten();
+    printer::init_theme(theme.as_deref(), cli.no_color);
+    if let Some(cap) = db
+        .get_config("log_max_size")
+        .ok()
+        .flatten()
+        .and_then(|v| activity_log::parse_size(&v))
+    {
+        activity_log::set_max_bytes(cap);
+    }
+    activity_log::set_json_format(
+        db.get_config("log_format").ok().flatten().as_deref() == Some("json"),
+    );
 
     let command = match cli.command {
         Some(cmd) => cmd,
@@ -956,17 +1960,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
-        let ops = crate::ops::ZenOps::new(&db, cli.home.clone());
+    let result = (move || -> Result<(), Box<dyn std::error::Error>> {
+        let mut ops = crate::ops::ZenOps::new(&db, cli.home.clone());
+        if cli.no_uv {
+            ops = ops.without_uv();
+        }
         match command {
             Commands::Create {
                 name,
                 python: user_python,
                 template,
+                copy_from,
                 strict,
                 ml,
                 cuda,
+                force_python,
                 rm,
+                no_bootstrap,
+                system_site_packages,
                 rest,
             } => {
                 // Typo detection: catch reversed command order
@@ -987,9 +1998,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
                 if let Some(ref cuda_ver) = cuda {
                     crate::validation::validate_cuda_version(cuda_ver)?;
+                    utils::get_torch_index_url(cuda_ver)?;
+                }
+                if let Some(target) = db.resolve_alias(&name)? {
+                    eprintln!(
+                        "{} '{}' is an alias for '{}'. Remove it first: {}",
+                        "Error:".red(),
+                        name,
+                        target,
+                        format!("zen alias rm {}", name).bold()
+                    );
+                    std::process::exit(1);
                 }
 
-                let mut python = user_python.clone().unwrap_or_else(|| "3.12".to_string());
+                // --copy-from: the source's packages, python and PyTorch variant
+                let copy_source = match copy_from {
+                    Some(source) => {
+                        let source = expand_alias(source, &db)?;
+                        if source == name {
+                            eprintln!("{} Can't copy '{}' onto itself.", "Error:".red(), name);
+                            std::process::exit(1);
+                        }
+                        let Some((_, path, py, ..)) =
+                            db.list_envs()?.into_iter().find(|(n, ..)| n == &source)
+                        else {
+                            eprintln!("{} {}", "Error:".red(), ops.env_not_found(&source));
+                            std::process::exit(1);
+                        };
+                        let python = utils::read_python_version(&path).unwrap_or(py);
+                        let variant = db.get_cuda_variant(&source)?;
+                        Some((source, python, utils::get_packages(&path), variant))
+                    }
+                    None => None,
+                };
+
+                // Precedence: --python > first template's (or --copy-from env's) python
+                // > default_python config > 3.12
+                let mut python = user_python
+                    .clone()
+                    .or_else(|| copy_source.as_ref().map(|(_, py, ..)| py.clone()))
+                    .unwrap_or_else(|| ops.default_python());
                 let env_path = cli.home.join(&name);
 
                 // Guard: check if environment already exists
@@ -1027,7 +2075,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                 // Validate templates before starting creation
                 let mut templates_to_apply = Vec::new();
-                let mut first_tpl_python: Option<String> = None;
+                let mut first_tpl_python: Option<(String, String)> = None;
+                let mut python_conflicts: Vec<(String, String)> = Vec::new();
                 if let Some(t_str) = template {
                     let parts = utils::parse_template_string(&t_str);
                     for part in parts {
@@ -1039,19 +2088,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     .iter()
                                     .find(|t| t.0 == part.name && t.1 == part.version)
                             {
-                                if first_tpl_python.is_none() {
-                                    python = t_info.2.clone();
-                                    first_tpl_python = Some(t_info.2.clone());
-                                } else if first_tpl_python.as_deref() != Some(&t_info.2) {
-                                    eprintln!(
-                                        "  {} Template '{}:{}' uses Python {} but first template uses Python {} — using {}",
-                                        "⚠".yellow(),
-                                        part.name,
-                                        part.version,
-                                        t_info.2,
-                                        first_tpl_python.as_deref().unwrap_or("?"),
-                                        first_tpl_python.as_deref().unwrap_or("?")
-                                    );
+                                let label = format!("{}:{}", part.name, part.version);
+                                match &first_tpl_python {
+                                    None => {
+                                        python = t_info.2.clone();
+                                        first_tpl_python = Some((label, t_info.2.clone()));
+                                    }
+                                    Some((_, first_py)) if first_py != &t_info.2 => {
+                                        python_conflicts.push((label, t_info.2.clone()));
+                                    }
+                                    _ => {}
                                 }
                             }
                             templates_to_apply.push((t_id, part.name, part.version));
@@ -1068,6 +2114,42 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
 
+                // Mixed Python versions can produce an env that can't satisfy
+                // the later templates, so require an explicit choice
+                if let Some((first_label, first_py)) = &first_tpl_python
+                    && !python_conflicts.is_empty()
+                {
+                    if !force_python {
+                        eprintln!(
+                            "{} Templates declare different Python versions:",
+                            "Error:".red()
+                        );
+                        eprintln!("  {} → Python {}", first_label, first_py);
+                        for (label, py) in &python_conflicts {
+                            eprintln!("  {} → Python {}", label, py);
+                        }
+                        eprintln!(
+                            "  Pass {} to choose one, or {} to use Python {} from '{}'.",
+                            "--python <ver>".bold(),
+                            "--force-python".bold(),
+                            first_py,
+                            first_label
+                        );
+                        std::process::exit(1);
+                    }
+                    let overridden: Vec<String> = python_conflicts
+                        .iter()
+                        .map(|(label, py)| format!("{} (Python {})", label, py))
+                        .collect();
+                    eprintln!(
+                        "  {} Using Python {} from template '{}' (--force-python); overriding {}",
+                        "⚠".yellow(),
+                        first_py,
+                        first_label,
+                        overridden.join(", ")
+                    );
+                }
+
                 // Deduplicate: --from a,a should not apply 'a' twice
                 templates_to_apply.dedup_by(|a, b| a.1 == b.1 && a.2 == b.2);
 
@@ -1093,58 +2175,66 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
 
-                // Try to use uv if available, otherwise fallback to venv
-                let status = if let Ok(uv_path) = which::which("uv") {
-                    std::process::Command::new(uv_path)
-                        .arg("venv")
-                        .arg(&env_path)
-                        .arg("--python")
-                        .arg(&python)
-                        .arg("--clear")
-                        .stdout(std::process::Stdio::null())
-                        .stderr(std::process::Stdio::null())
-                        .status()?
+                // Try to use uv if available, otherwise fallback to venv.
+                // stderr is captured so a failure can say why.
+                let mut venv_cmd = if let Some(uv_path) = ops.uv_path() {
+                    let mut cmd = std::process::Command::new(uv_path);
+                    cmd.arg("venv").arg(&env_path).arg("--python").arg(&python);
+                    cmd
                 } else {
-                    std::process::Command::new("python3")
-                        .arg("-m")
-                        .arg("venv")
-                        .arg(&env_path)
-                        .arg("--clear")
-                        .stdout(std::process::Stdio::null())
-                        .stderr(std::process::Stdio::null())
-                        .status()?
+                    let mut cmd = std::process::Command::new("python3");
+                    cmd.arg("-m").arg("venv").arg(&env_path);
+                    cmd
                 };
+                if system_site_packages {
+                    venv_cmd.arg("--system-site-packages");
+                }
+                let venv_out = venv_cmd
+                    .arg("--clear")
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::piped())
+                    .output()?;
 
-                if status.success() {
+                if venv_out.status.success() {
                     let env_str = env_path.to_str().unwrap();
 
-                    // Silent bootstrap — no need to show pip/uv/setuptools install
-                    if let Ok(_uv_path) = which::which("uv") {
-                        utils::run_in_env_silent(
-                            env_str,
-                            "uv",
-                            &["pip", "install", "uv", "setuptools"],
-                        );
-                    } else {
-                        utils::run_in_env_silent(
-                            env_str,
-                            "pip",
-                            &["install", "--upgrade", "pip", "setuptools"],
-                        );
+                    // Silent bootstrap — no need to show pip/uv/setuptools install.
+                    // A failure (e.g. no network) only warns: the venv itself is usable.
+                    if !no_bootstrap {
+                        let bootstrapped = if ops.uv_path().is_some() {
+                            utils::run_in_env_silent(
+                                env_str,
+                                "uv",
+                                &["pip", "install", "uv", "setuptools"],
+                            )
+                        } else {
+                            utils::run_in_env_silent(
+                                env_str,
+                                "pip",
+                                &["install", "--upgrade", "pip", "setuptools"],
+                            )
+                        };
+                        if !bootstrapped {
+                            eprintln!(
+                                "  {} Bootstrap of pip/uv/setuptools failed (offline?). Continuing with the bare venv; use {} to skip it.",
+                                "⚠".tone(Tone::Warn),
+                                "--no-bootstrap".bold()
+                            );
+                        }
                     }
 
                     // Save template info for logging before ownership is consumed
-                    let tpl_log_info: String = if !templates_to_apply.is_empty() {
-                        format!(
-                            " --template {}",
-                            templates_to_apply
-                                .iter()
-                                .map(|(_, n, v)| format!("{}:{}", n, v))
-                                .collect::<Vec<_>>()
-                                .join(",")
-                        )
-                    } else {
-                        String::new()
+                    let source_template = (!templates_to_apply.is_empty()).then(|| {
+                        templates_to_apply
+                            .iter()
+                            .map(|(_, n, v)| format!("{}:{}", n, v))
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    });
+                    let tpl_log_info = match (&source_template, &copy_source) {
+                        (Some(s), _) => format!(" --template {}", s),
+                        (None, Some((source, ..))) => format!(" --copy-from {}", source),
+                        (None, None) => String::new(),
                     };
 
                     // Apply templates — track installed packages for conflict detection
@@ -1158,7 +2248,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         let packages = db.get_template_packages(t_id)?;
 
                         // Detect conflicts with previously applied templates
-                        for (p_name, p_ver, _, _, pkg_install_args, _step) in &packages {
+                        for (p_name, p_ver, _, _, pkg_install_args, ..) in &packages {
                             let pkg_lower = p_name.to_lowercase();
                             if let Some((prev_ver, prev_tpl, prev_args)) =
                                 installed_pkgs.get(&pkg_lower)
@@ -1192,7 +2282,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         let mut pkg_groups: std::collections::HashMap<Option<String>, Vec<String>> =
                             std::collections::HashMap::new();
 
-                        for (p_name, p_ver, is_pinned, itype, pkg_install_args, _step) in packages {
+                        for (
+                            p_name,
+                            p_ver,
+                            is_pinned,
+                            itype,
+                            pkg_install_args,
+                            _step,
+                            extras,
+                            version_spec,
+                        ) in packages
+                        {
                             // Wheel path validation: if install_type is "wheel",
                             // the install_args contains the wheel path — verify it exists.
                             if itype == "wheel"
@@ -1221,6 +2321,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 continue; // Skip this package
                             }
 
+                            // git / editable packages install from their recorded source
+                            let requirement = utils::with_extras(&p_name, extras.as_deref());
+                            let source_spec = utils::template_source_spec(
+                                &requirement,
+                                &itype,
+                                pkg_install_args.as_deref(),
+                            );
                             let pkg_spec = if itype == "wheel" {
                                 // For wheels, use the wheel path directly
                                 pkg_install_args.clone().unwrap_or_else(|| {
@@ -1230,10 +2337,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         p_name.clone()
                                     }
                                 })
-                            } else if strict || is_pinned {
-                                format!("{}=={}", p_name, p_ver)
+                            } else if let Some(spec) = source_spec.clone() {
+                                spec
+                            } else if strict {
+                                // --strict reproduces the snapshot, ignoring ranges
+                                utils::template_requirement(&requirement, &p_ver, true, None)
                             } else {
-                                p_name.clone()
+                                utils::template_requirement(
+                                    &requirement,
+                                    &p_ver,
+                                    is_pinned,
+                                    version_spec.as_deref(),
+                                )
                             };
                             // Track for conflict detection in subsequent templates
                             installed_pkgs.insert(
@@ -1244,8 +2359,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     pkg_install_args.clone(),
                                 ),
                             );
-                            // Wheels use their own path, don't inherit group install_args
-                            let group_key = if itype == "wheel" {
+                            // Wheels and sources carry their own location, don't inherit group install_args
+                            let group_key = if itype == "wheel" || source_spec.is_some() {
                                 None
                             } else {
                                 pkg_install_args
@@ -1271,7 +2386,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 cmd_args.push(pkg);
                             }
 
-                            if which::which("uv").is_ok() {
+                            if ops.uv_path().is_some() {
                                 utils::run_in_env(env_str, "uv", &cmd_args);
                             } else {
                                 utils::run_in_env(env_str, "pip", &cmd_args[1..]);
@@ -1279,10 +2394,58 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
 
+                    if let Some((source, _, packages, variant)) = &copy_source {
+                        let (requirements, editables) =
+                            crate::ops::copy_requirements(packages, strict);
+                        println!(
+                            "Copying {} package(s) from '{}'...",
+                            requirements.len() + editables.len(),
+                            source
+                        );
+                        let mut cmd_args = vec!["pip", "install"];
+                        // PyTorch builds come from the index the source was built against
+                        let variant_index = variant.as_deref().map(utils::torch_index_for_variant);
+                        if let Some(ref url) = variant_index
+                            && requirements
+                                .iter()
+                                .any(|r| utils::is_torch_variant_package(r))
+                        {
+                            cmd_args.push("--extra-index-url");
+                            cmd_args.push(url);
+                        }
+                        for req in &requirements {
+                            cmd_args.push(req);
+                        }
+                        for path in &editables {
+                            cmd_args.push("-e");
+                            cmd_args.push(path);
+                        }
+                        let copied = cmd_args.len() == 2
+                            || if ops.uv_path().is_some() {
+                                utils::run_in_env(env_str, "uv", &cmd_args)
+                            } else {
+                                utils::run_in_env(env_str, "pip", &cmd_args[1..])
+                            };
+                        if !copied {
+                            eprintln!(
+                                "  {} Some packages from '{}' failed to install. Compare with {}.",
+                                "⚠".tone(Tone::Warn),
+                                source,
+                                format!("zen diff {} {}", source, name).bold()
+                            );
+                        }
+                    }
+
                     let py_ver =
                         utils::read_python_version(env_path.to_str().unwrap()).unwrap_or(python);
 
                     let _env_id = db.register_env(&name, env_path.to_str().unwrap(), &py_ver)?;
+                    if let Some(ref source) = source_template {
+                        db.set_source_template(&name, source)?;
+                    }
+                    if let Some((_, _, _, Some(variant))) = &copy_source {
+                        db.set_cuda_variant(&name, variant)?;
+                    }
 
                     // Package versions are now tracked dynamically via `zen list --refresh`
 
@@ -1310,10 +2473,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             "\n{}",
                             "Installing ML stack (PyTorch + CUDA)...".bold().cyan()
                         );
-                        let index_url = format!(
-                            "https://download.pytorch.org/whl/cu{}",
-                            cuda_ver.replace('.', "")
-                        );
+                        let index_url = utils::get_torch_index_url(&cuda_ver)?.to_string();
                         println!("  Using CUDA {} index: {}", cuda_ver, index_url);
 
                         let pip_path = env_path.join("bin").join("pip");
@@ -1331,6 +2491,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         match result {
                             Ok(status) if status.success() => {
                                 println!("{} ML stack installed successfully.", "✓".green());
+                                if let Some(variant) = utils::torch_variant_from_index(&index_url) {
+                                    db.set_cuda_variant(&name, &variant)?;
+                                }
                             }
                             _ => {
                                 eprintln!("{} ML stack installation failed.", "✗".red());
@@ -1338,7 +2501,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
                 } else {
-                    eprintln!("Failed to create environment.");
+                    let stderr = String::from_utf8_lossy(&venv_out.stderr);
+                    eprintln!("{} Failed to create environment '{}'.", "✗".red(), name);
+                    for line in stderr.lines().filter(|l| !l.trim().is_empty()) {
+                        eprintln!("  {}", line.dimmed());
+                    }
+                    if let Some(hint) =
+                        utils::missing_python_hint(&stderr, &python, ops.uv_path().is_some())
+                    {
+                        eprintln!("{} {}", "Hint:".yellow().bold(), hint);
+                    }
+                    activity_log::log_activity(
+                        "cli",
+                        "create:error",
+                        &format!("{} - {}", name, stderr.lines().last().unwrap_or("").trim()),
+                    );
+                    std::process::exit(1);
                 }
             }
             Commands::Add { path, name } => {
@@ -1453,6 +2631,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("  {}", path_str.dimmed());
             }
             Commands::Rename { old, new } => {
+                let old = expand_alias(old, &db)?;
                 let old_name = types::EnvName::new(&old).map_err(|e| e.to_string())?;
                 let new_name = types::EnvName::new(&new).map_err(|e| e.to_string())?;
 
@@ -1471,6 +2650,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     );
                     return Ok(());
                 }
+                if let Some(target) = db.resolve_alias(&new)? {
+                    eprintln!(
+                        "{} '{}' is an alias for '{}'. Remove it first: {}",
+                        "Error:".red(),
+                        new,
+                        target,
+                        format!("zen alias rm {}", new).bold()
+                    );
+                    return Ok(());
+                }
 
                 if db.rename_environment(&old, &new)? {
                     activity_log::log_activity("cli", "rename", &format!("{} -> {}", old, new));
@@ -1489,33 +2678,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 sort,
                 label,
                 format,
+                columns,
                 oneline,
                 long_format,
+                favorites,
+                broken,
+                drift,
+                stale,
+                output: OutputArgs { output },
             } => {
                 // Auto-discover new environments (silent, fast)
-                let home_path = &cli.home;
-                if home_path.exists()
-                    && let Ok(entries) = std::fs::read_dir(home_path)
-                {
-                    for entry in entries.flatten() {
-                        let path = entry.path();
-                        let python_bin = path.join("bin/python");
-                        let python3_bin = path.join("bin/python3");
-                        if path.is_dir() && (python_bin.exists() || python3_bin.exists()) {
-                            let name = entry.file_name().to_string_lossy().to_string();
-                            if db.get_env_id(&name)?.is_none() {
-                                let path_str = path.to_string_lossy().to_string();
-                                let py_ver = utils::read_python_version(&path_str)
-                                    .unwrap_or_else(|| "unknown".to_string());
-                                db.register_env(&name, &path_str, &py_ver)?;
-                            }
-                        }
-                    }
-                }
+                ops.register_unregistered_envs()?;
 
                 let sort_str = match sort {
                     ListSort::Name => "name",
                     ListSort::Date => "date",
+                    ListSort::Recent => "recent",
                 };
 
                 // Get envs, optionally filtered by label
@@ -1528,10 +2706,39 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 } else {
                     ops.list_envs_with_status(pattern.as_deref(), Some(sort_str), None)?
                 };
+                let last_used = ops.env_last_used()?;
+                let now = chrono::Utc::now();
+                let idle_days = |name: &String| last_used.get(name).map(|t| (now - *t).num_days());
+                let envs: Vec<_> = envs
+                    .into_iter()
+                    .filter(|(.., is_fav)| !favorites || *is_fav)
+                    .filter(|(name, ..)| match stale {
+                        Some(days) => idle_days(name).is_some_and(|idle| idle >= days),
+                        None => true,
+                    })
+                    .collect();
+
+                // --broken / --drift keep only envs at those health levels (either, if both)
+                let health_filter = broken || drift;
+                let health_wanted = |health: crate::types::HealthLevel| match health {
+                    crate::types::HealthLevel::Fail => broken,
+                    crate::types::HealthLevel::Warn => drift,
+                    _ => false,
+                };
+                let quick_health = |path: &str, exists: bool| {
+                    if exists {
+                        crate::ops::check_health_quick(std::path::Path::new(path))
+                    } else {
+                        crate::types::HealthLevel::Fail
+                    }
+                };
 
                 // Handle -1 (oneline) — names only, then exit
                 if oneline {
-                    for (name, ..) in &envs {
+                    for (name, path, _, exists, ..) in &envs {
+                        if health_filter && !health_wanted(quick_health(path, *exists)) {
+                            continue;
+                        }
                         println!("{}", name);
                     }
                     return Ok(());
@@ -1541,15 +2748,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .get_config("stack_info")?
                     .unwrap_or_else(|| "torch numpy transformers diffusers".to_string());
                 let tracked_keys: Vec<&str> = stack_info_config.split_whitespace().collect();
+                let columns = match columns
+                    .map(|spec| crate::table::parse_list_columns(&spec, &tracked_keys))
+                    .transpose()
+                {
+                    Ok(columns) => columns,
+                    Err(e) => {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(2);
+                    }
+                };
 
                 // Determine format based on terminal width or explicit flag
-                #[derive(Debug, PartialEq)]
-                enum ListFormat {
-                    Minimal,
-                    Compact,
-                    Wide,
-                }
-
+                use crate::table::ListFormat;
                 let list_format = match format {
                     ListFormatArg::Minimal => ListFormat::Minimal,
                     ListFormatArg::Compact => ListFormat::Compact,
@@ -1557,28 +2768,32 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     ListFormatArg::Auto => {
                         // Auto-detect based on terminal width
                         use terminal_size::{Width, terminal_size};
-                        match terminal_size() {
-                            Some((Width(w), _)) if w < 60 => ListFormat::Minimal,
-                            Some((Width(w), _)) if w < 200 => ListFormat::Minimal,
-                            Some(_) => ListFormat::Compact,
-                            None => ListFormat::Minimal, // Safe default for pipes/non-TTY
-                        }
+                        ListFormat::for_width(terminal_size().map(|(Width(w), _)| w))
                     }
                 };
 
                 // Pre-scan all environments for package versions + health
+                let spinner = crate::printer::Spinner::start(&format!(
+                    "Scanning {} environments...",
+                    envs.len()
+                ));
+                // Opened on first stale entry, since `list` runs on a read-only handle
+                let index_db = std::cell::OnceCell::new();
                 let env_data: Vec<_> = envs
                     .iter()
                     .map(|(name, path, py_ver, exists, _updated, is_fav)| {
                         let packages = crate::utils::get_packages(path);
+                        // Keep the package index warm for `zen find` (best effort)
+                        let mtime = utils::site_packages_mtime(path);
+                        if db.get_package_index_mtime(name).ok().flatten() != mtime
+                            && let Some(index_db) = index_db.get_or_init(|| db.writable().ok())
+                        {
+                            index_db.index_packages(name, mtime, &packages).ok();
+                        }
                         let versions: std::collections::HashMap<String, Option<String>> =
                             packages.into_iter().map(|p| (p.name, p.version)).collect();
                         // Real health check (native, no subprocess)
-                        let health = if *exists {
-                            crate::ops::check_health_quick(std::path::Path::new(path))
-                        } else {
-                            crate::types::HealthLevel::Fail
-                        };
+                        let health = quick_health(path, *exists);
                         (
                             name.clone(),
                             path.clone(),
@@ -1589,14 +2804,110 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             health,
                         )
                     })
+                    .filter(|(.., health)| !health_filter || health_wanted(*health))
+                    .collect();
+                // Sizes walk every file, so they're only computed for a Size column
+                let sizes: std::collections::HashMap<&str, u64> = match &columns {
+                    Some(columns) if columns.contains(&crate::table::ListColumn::Size) => env_data
+                        .iter()
+                        .filter(|(_, _, _, exists, ..)| *exists)
+                        .map(|(name, path, ..)| (name.as_str(), utils::dir_size(path)))
+                        .collect(),
+                    _ => std::collections::HashMap::new(),
+                };
+                spinner.finish();
+
+                // --output json/csv: one row per env, a column per tracked package
+                let mut headers = vec![
+                    "name",
+                    "path",
+                    "python",
+                    "exists",
+                    "favorite",
+                    "health",
+                    "last_used",
+                ];
+                headers.extend(tracked_keys.iter().copied());
+                let rows: Vec<Vec<serde_json::Value>> = env_data
+                    .iter()
+                    .map(|(name, path, py_ver, exists, is_fav, versions, health)| {
+                        let mut row = vec![
+                            name.clone().into(),
+                            path.clone().into(),
+                            py_ver.clone().into(),
+                            (*exists).into(),
+                            (*is_fav).into(),
+                            serde_json::to_value(health).unwrap_or_default(),
+                            last_used
+                                .get(name)
+                                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                                .into(),
+                        ];
+                        for key in &tracked_keys {
+                            row.push(versions.get(*key).cloned().flatten().into());
+                        }
+                        row
+                    })
                     .collect();
+                if let Some(out) = crate::printer::render_records(output, &headers, &rows) {
+                    print!("{}", out);
+                    return Ok(());
+                }
 
-                match list_format {
-                    ListFormat::Minimal => {
-                        // Pre-calculate all column widths
-                        let max_name = env_data
-                            .iter()
-                            .map(|(name, _, _, _, is_fav, _, _)| {
+                match (columns, list_format) {
+                    // --columns: the table is built from exactly the columns asked for
+                    (Some(columns), _) => {
+                        use crate::table::ListColumn;
+                        use comfy_table::{Cell, Color};
+
+                        let mut table = crate::table::new_table();
+                        table.set_header(columns.iter().map(|c| {
+                            Cell::new(c.header()).add_attribute(comfy_table::Attribute::Bold)
+                        }));
+                        for (name, path, py_ver, _exists, is_fav, versions, health) in &env_data {
+                            let row: Vec<Cell> = columns
+                                .iter()
+                                .map(|column| match column {
+                                    ListColumn::Name if *is_fav => {
+                                        Cell::new(format!("★ {}", name)).fg(Color::Yellow)
+                                    }
+                                    ListColumn::Name => Cell::new(name),
+                                    ListColumn::Python => Cell::new(py_ver),
+                                    ListColumn::Health => crate::table::health_cell(*health),
+                                    ListColumn::LastUsed => Cell::new(
+                                        last_used
+                                            .get(name)
+                                            .map(|t| utils::format_ago(now - *t))
+                                            .unwrap_or_else(|| "--".to_string()),
+                                    ),
+                                    ListColumn::Path => Cell::new(path).fg(Color::DarkGrey),
+                                    ListColumn::Size => match sizes.get(name.as_str()) {
+                                        Some(size) => Cell::new(utils::format_size(*size)),
+                                        None => Cell::new("--"),
+                                    },
+                                    ListColumn::Package(key) => {
+                                        match versions.get(key).cloned().flatten() {
+                                            Some(v) if key == "torch" && v.contains("+cu") => {
+                                                Cell::new(v).fg(Color::Green)
+                                            }
+                                            Some(v) if key == "numpy" && v.starts_with('2') => {
+                                                Cell::new(v).fg(Color::Cyan)
+                                            }
+                                            Some(v) => Cell::new(v),
+                                            None => Cell::new("--"),
+                                        }
+                                    }
+                                })
+                                .collect();
+                            table.add_row(row);
+                        }
+                        println!("{}", table);
+                    }
+                    (None, ListFormat::Minimal) => {
+                        // Pre-calculate all column widths
+                        let max_name = env_data
+                            .iter()
+                            .map(|(name, _, _, _, is_fav, _, _)| {
                                 let icon_w = if *is_fav { 2 } else { 0 };
                                 name.len() + icon_w
                             })
@@ -1635,13 +2946,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             // Health status — zen aesthetics
                             let status_str = match health {
                                 crate::types::HealthLevel::Pass => {
-                                    format!(" {}", "✓".truecolor(100, 200, 255))
+                                    format!(" {}", "✓".tone(Tone::Accent))
                                 }
                                 crate::types::HealthLevel::Info => {
-                                    format!(" {}", "△".truecolor(255, 182, 193))
+                                    format!(" {}", "△".tone(Tone::Minor))
                                 }
                                 crate::types::HealthLevel::Warn => {
-                                    format!(" {}", "!".truecolor(255, 140, 0))
+                                    format!(" {}", "!".tone(Tone::Warn))
                                 }
                                 crate::types::HealthLevel::Fail => {
                                     format!(" {}", "✗".red())
@@ -1656,9 +2967,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         v.green().to_string()
                                     } else if *key == "numpy" {
                                         if v.starts_with('2') || v.starts_with('3') {
-                                            v.truecolor(100, 200, 255).to_string()
+                                            v.tone(Tone::Accent).to_string()
                                         } else {
-                                            v.truecolor(255, 140, 0).to_string()
+                                            v.tone(Tone::Warn).to_string()
                                         }
                                     } else {
                                         v.to_string()
@@ -1678,11 +2989,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 }
                             }
 
-                            let path_str = if long_format {
+                            let mut path_str = if long_format {
                                 format!("  {}", path.dimmed())
                             } else {
                                 String::new()
                             };
+                            if stale.is_some()
+                                && let Some(idle) = idle_days(name)
+                            {
+                                path_str
+                                    .push_str(&format!("  {}", format!("idle {}d", idle).dimmed()));
+                            }
                             println!(
                                 "{:<name_w$} {:<py_w$}{}{}{}",
                                 name_display,
@@ -1695,7 +3012,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             );
                         }
                     }
-                    ListFormat::Compact => {
+                    (None, ListFormat::Compact) => {
                         // Medium format: no path, key packages inline
                         use comfy_table::modifiers::UTF8_ROUND_CORNERS;
                         use comfy_table::presets::UTF8_FULL;
@@ -1714,6 +3031,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             Cell::new("Health").add_attribute(header_style),
                         ];
 
+                        if stale.is_some() {
+                            header_row.push(Cell::new("Last used").add_attribute(header_style));
+                        }
+
                         // Only show first 2 tracked packages in compact mode
                         for key in tracked_keys.iter().take(2) {
                             header_row.push(
@@ -1731,21 +3052,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 name.clone()
                             };
 
-                            let health_cell = match health {
-                                crate::types::HealthLevel::Pass => Cell::new("✓").fg(Color::Rgb {
-                                    r: 100,
-                                    g: 200,
-                                    b: 255,
-                                }),
-                                crate::types::HealthLevel::Info => Cell::new("△").fg(Color::Rgb {
-                                    r: 255,
-                                    g: 182,
-                                    b: 193,
-                                }),
-                                crate::types::HealthLevel::Warn => Cell::new("!").fg(Color::Red),
-                                crate::types::HealthLevel::Fail => Cell::new("✗").fg(Color::Red),
-                            };
-
                             let mut row = vec![
                                 if *is_fav {
                                     Cell::new(&name_display).fg(Color::Yellow)
@@ -1753,8 +3059,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     Cell::new(&name_display)
                                 },
                                 Cell::new(py_ver),
-                                health_cell,
+                                crate::table::health_cell(*health),
                             ];
+                            if stale.is_some() {
+                                let used = last_used
+                                    .get(name)
+                                    .map(|t| utils::format_ago(now - *t))
+                                    .unwrap_or_else(|| "--".to_string());
+                                row.push(Cell::new(used));
+                            }
 
                             for key in tracked_keys.iter().take(2) {
                                 let version = versions.get(*key).and_then(|v| v.clone());
@@ -1776,7 +3089,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                         println!("{}", table);
                     }
-                    ListFormat::Wide => {
+                    (None, ListFormat::Wide) => {
                         // Full table with paths and all tracked packages
                         use comfy_table::modifiers::UTF8_ROUND_CORNERS;
                         use comfy_table::presets::UTF8_FULL;
@@ -1793,6 +3106,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             Cell::new("Name").add_attribute(header_style),
                             Cell::new("Python").add_attribute(header_style),
                             Cell::new("Health").add_attribute(header_style),
+                            Cell::new("Last used").add_attribute(header_style),
                         ];
                         header_row.push(Cell::new("Path").add_attribute(header_style));
 
@@ -1812,21 +3126,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 name.clone()
                             };
 
-                            let health_cell = match health {
-                                crate::types::HealthLevel::Pass => Cell::new("✓").fg(Color::Rgb {
-                                    r: 100,
-                                    g: 200,
-                                    b: 255,
-                                }),
-                                crate::types::HealthLevel::Info => Cell::new("△").fg(Color::Rgb {
-                                    r: 255,
-                                    g: 182,
-                                    b: 193,
-                                }),
-                                crate::types::HealthLevel::Warn => Cell::new("!").fg(Color::Red),
-                                crate::types::HealthLevel::Fail => Cell::new("✗").fg(Color::Red),
-                            };
-
                             let mut row = vec![
                                 if *is_fav {
                                     Cell::new(&name_display).fg(Color::Yellow)
@@ -1834,8 +3133,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     Cell::new(&name_display)
                                 },
                                 Cell::new(py_ver),
-                                health_cell,
+                                crate::table::health_cell(*health),
                             ];
+                            let used = last_used
+                                .get(name)
+                                .map(|t| utils::format_ago(now - *t))
+                                .unwrap_or_else(|| "--".to_string());
+                            row.push(Cell::new(used));
                             row.push(Cell::new(path).fg(Color::DarkGrey));
 
                             for key in &tracked_keys {
@@ -1891,21 +3195,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 if n_pass > 0 {
                     print!(
                         "  {} {}",
-                        "✓".truecolor(100, 200, 255),
+                        "✓".tone(Tone::Accent),
                         format!("{} ok", n_pass).dimmed()
                     );
                 }
                 if n_info > 0 {
                     print!(
                         "  {} {}",
-                        "△".truecolor(255, 182, 193),
+                        "△".tone(Tone::Minor),
                         format!("{} minor", n_info).dimmed()
                     );
                 }
                 if n_warn > 0 {
                     print!(
                         "  {} {}",
-                        "!".truecolor(255, 140, 0),
+                        "!".tone(Tone::Warn),
                         format!("{} drift", n_warn).dimmed()
                     );
                 }
@@ -1915,109 +3219,95 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 if n_fav > 0 {
                     print!(
                         "  {} {}",
-                        "★".truecolor(255, 215, 0),
+                        "★".tone(Tone::Fav),
                         format!("{} fav", n_fav).dimmed()
                     );
                 }
                 println!();
             }
-            Commands::Rm { name, yes, cached } => {
-                let env_name = types::EnvName::new(&name).map_err(|e| e.to_string())?;
-                // Check existence before prompting
-                let envs = db.list_envs()?;
-                let in_db = envs.iter().any(|(n, ..)| n == &name);
-                let on_disk = cli.home.join(&name).exists();
-                if !in_db && !on_disk {
-                    activity_log::log_activity("cli", "rm:error", &format!("{} - not found", name));
-                    eprintln!("{} Environment '{}' not found.", "Error:".red(), name);
-                    return Ok(());
-                }
-                if !yes {
-                    use dialoguer::{Confirm, theme::ColorfulTheme};
-                    let prompt_msg = if cached {
-                        format!(
-                            "Untrack environment '{}' from registry? (files kept on disk)",
-                            name
-                        )
-                    } else {
-                        format!("Are you sure you want to remove environment '{}'?", name)
-                    };
-                    let confirmed = match Confirm::with_theme(&ColorfulTheme::default())
-                        .with_prompt(prompt_msg)
-                        .default(false)
-                        .interact()
-                    {
-                        Ok(v) => v,
-                        Err(_) => {
-                            // Ctrl+C — exit silently
-                            println!();
-                            return Ok(());
-                        }
-                    };
-                    if !confirmed {
-                        println!("Abort.");
-                        return Ok(());
+            Commands::Rm {
+                name,
+                yes,
+                cached,
+                dry_run,
+            } => {
+                // A glob removes every matching env after one confirmation for the whole set
+                let names = if utils::is_glob(&name) {
+                    let names: Vec<String> = db.list_envs()?.into_iter().map(|e| e.0).collect();
+                    let matched = utils::match_envs(&name, &names);
+                    if matched.is_empty() {
+                        eprintln!("{} No environments match '{}'.", "Error:".red(), name);
+                        std::process::exit(1);
                     }
-                }
-                if cached {
-                    // DB-only removal — keep files on disk
-                    activity_log::log_activity("cli", "rm:cached", &name);
-                    match ops.untrack_env(&env_name) {
-                        Ok(resp) => {
-                            println!("{}", resp);
-                            // Warn if under ZEN_HOME (auto-discovery will re-add it)
-                            let env_path = envs
-                                .iter()
-                                .find(|(n, ..)| n == &name)
-                                .map(|(_, p, ..)| p.clone());
-                            if let Some(ep) = env_path {
-                                let home_str = cli.home.to_string_lossy();
-                                if ep.starts_with(home_str.as_ref()) {
-                                    eprintln!(
-                                        "{} This env is under Zen Home and will be re-discovered on next list. Use 'zen rm' to delete it from disk.",
-                                        "⚠ Note:".truecolor(255, 140, 0)
-                                    );
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            activity_log::log_activity(
-                                "cli",
-                                "rm:error",
-                                &format!("{} - {}", name, e),
-                            );
-                            eprintln!("{} {}", "Error:".red(), e);
-                            return Ok(());
+                    println!("{} environment(s) match '{}':", matched.len(), name);
+                    if !dry_run {
+                        for m in &matched {
+                            println!("  • {}", m.tone(Tone::Accent));
                         }
                     }
+                    matched
                 } else {
-                    println!("{} {}...", "Removing".magenta().bold(), name);
-                    activity_log::log_activity("cli", "rm", &name);
-                    match ops.remove_env(&env_name) {
-                        Ok(resp) => println!("{}", resp),
-                        Err(e) => {
-                            activity_log::log_activity(
-                                "cli",
-                                "rm:error",
-                                &format!("{} - {}", name, e),
-                            );
-                            eprintln!("{} {}", "Error:".red(), e);
-                            return Ok(());
-                        }
+                    let name = expand_alias(name, &db)?;
+                    // Check existence before prompting
+                    let in_db = db.get_env_id(&name)?.is_some();
+                    if !in_db && !cli.home.join(&name).exists() {
+                        activity_log::log_activity(
+                            "cli",
+                            "rm:error",
+                            &format!("{} - not found", name),
+                        );
+                        eprintln!("{} {}", "Error:".red(), ops.env_not_found(&name));
+                        return Ok(());
                     }
+                    vec![name]
+                };
+                if !remove_envs(&db, &ops, &cli.home, &names, yes, cached, dry_run)? {
+                    std::process::exit(1);
                 }
             }
-            Commands::Config { key, value } => match (key, value) {
-                (Some(k), Some(v)) => {
+            Commands::Config {
+                key,
+                value,
+                unset,
+                force,
+                edit,
+            } => match (key, value, unset) {
+                _ if edit => edit_config(&db, force)?,
+                (_, _, Some(k)) => {
+                    if db.unset_config(&k)? {
+                        activity_log::log_activity("cli", "config:unset", &k);
+                        println!("{} Config removed: {}", "✓".green(), k);
+                    } else {
+                        println!("{} is not set.", k);
+                    }
+                }
+                (Some(k), Some(v), None) => {
+                    if !db::KNOWN_CONFIG_KEYS.contains(&k.as_str()) && !force {
+                        eprintln!("{} Unknown config key '{}'.", "Warning:".yellow(), k);
+                        // Common typo: dashes instead of underscores
+                        let suggestion = k.replace('-', "_").to_lowercase();
+                        if db::KNOWN_CONFIG_KEYS.contains(&suggestion.as_str()) {
+                            eprintln!("  Did you mean {}?", suggestion.cyan());
+                        }
+                        eprintln!(
+                            "  Known keys: {}. Use --force to set it anyway.",
+                            db::KNOWN_CONFIG_KEYS.join(", ")
+                        );
+                        std::process::exit(1);
+                    }
+                    if let Err(e) = crate::validation::validate_config_value(&k, &v) {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
                     db.set_config(&k, &v)?;
                     activity_log::log_activity("cli", "config", &format!("{} = {}", k, v));
                     println!("{} Config updated: {} = {}", "✓".green(), k, v);
                 }
-                (Some(k), None) => {
+                (Some(k), None, None) => {
                     let v = db.get_config(&k)?.unwrap_or_else(|| "not set".to_string());
                     println!("{} = {}", k, v);
                 }
-                (None, _) => {
+                (None, _, None) => {
                     let configs = db.list_all_config()?;
                     if configs.is_empty() {
                         println!("No configuration values set.");
@@ -2029,13 +3319,141 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             },
-            Commands::Reset { yes } => {
+            Commands::Gc {
+                yes,
+                discover,
+                stale,
+            } => {
+                let missing = ops.find_missing_envs()?;
+                let unregistered = if discover {
+                    ops.find_unregistered_envs()?
+                } else {
+                    Vec::new()
+                };
+                // Missing envs are already reported above; stale ones are only suggested
+                let stale_envs: Vec<_> = match stale {
+                    Some(days) => ops
+                        .find_stale_envs(days)?
+                        .into_iter()
+                        .filter(|(name, ..)| !missing.iter().any(|(m, _)| m == name))
+                        .collect(),
+                    None => Vec::new(),
+                };
+                // Links must be pruned before their env rows go (ON DELETE CASCADE
+                // would otherwise drop them silently and skew the counts).
+                let links = if yes {
+                    db.prune_stale_links()?
+                } else {
+                    db.find_stale_links()?
+                };
+
+                if missing.is_empty()
+                    && links.is_empty()
+                    && unregistered.is_empty()
+                    && stale_envs.is_empty()
+                {
+                    println!("{} Nothing to clean up.", "✓".green());
+                    return Ok(());
+                }
+
+                for (name, path) in &missing {
+                    println!(
+                        "  {} '{}' at {} (directory missing)",
+                        "✗".red(),
+                        name,
+                        path.dimmed()
+                    );
+                    if yes {
+                        db.delete_env(name)?;
+                    }
+                }
+                for (project_path, env_name, reason) in &links {
+                    println!(
+                        "  {} link '{}' at {} ({})",
+                        "✗".red(),
+                        env_name,
+                        project_path.dimmed(),
+                        reason.dimmed()
+                    );
+                }
+                for (name, path) in &unregistered {
+                    println!(
+                        "  {} '{}' at {} (unregistered)",
+                        "+".tone(Tone::Accent),
+                        name,
+                        path.dimmed()
+                    );
+                    if yes {
+                        let py_ver = utils::read_python_version(path)
+                            .unwrap_or_else(|| "unknown".to_string());
+                        db.register_env(name, path, &py_ver)?;
+                    }
+                }
+                for (name, path, idle) in &stale_envs {
+                    println!(
+                        "  {} '{}' at {} (unused for {}d)",
+                        "~".tone(Tone::Warn),
+                        name,
+                        path.dimmed(),
+                        idle
+                    );
+                }
+
+                println!();
+                if yes {
+                    println!(
+                        "{} Removed {} env row(s), pruned {} link(s), registered {} env(s).",
+                        "✓".green(),
+                        missing.len(),
+                        links.len(),
+                        unregistered.len()
+                    );
+                    activity_log::log_activity(
+                        "cli",
+                        "gc",
+                        &format!(
+                            "rows={} links={} discovered={}",
+                            missing.len(),
+                            links.len(),
+                            unregistered.len()
+                        ),
+                    );
+                } else {
+                    println!(
+                        "Found {} dangling env row(s), {} stale link(s), {} unregistered env(s).",
+                        missing.len(),
+                        links.len(),
+                        unregistered.len()
+                    );
+                    println!("Run {} to apply.", "zen gc --yes".bold());
+                }
+                if !stale_envs.is_empty() {
+                    println!(
+                        "{} stale env(s) are kept; remove them with {}.",
+                        stale_envs.len(),
+                        "zen rm <name>".bold()
+                    );
+                }
+            }
+            Commands::Reset {
+                yes,
+                db_only,
+                config_only,
+            } => {
                 use std::io::Write;
 
                 if !yes {
+                    let what = if db_only {
+                        "the zen database (config values are kept)"
+                    } else if config_only {
+                        "all zen config values"
+                    } else {
+                        "the zen database and config"
+                    };
                     print!(
-                        "{} This will delete the zen database and config. Environments on disk will NOT be affected.\nContinue? [y/N] ",
-                        "⚠".yellow()
+                        "{} This will delete {}. Environments on disk will NOT be affected.\nContinue? [y/N] ",
+                        "⚠".yellow(),
+                        what
                     );
                     std::io::stdout().flush()?;
                     let mut input = String::new();
@@ -2046,30 +3464,116 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
 
-                let home = home::home_dir().ok_or("Could not find home directory")?;
-                let db_path = home.join(".zen").join("zen.db");
-                let config_path = home.join(".zen").join("config.toml");
+                if config_only {
+                    let count = db.clear_config()?;
+                    activity_log::log_activity("cli", "reset:config", &count.to_string());
+                    println!("{} Cleared {} config value(s).", "✓".green(), count);
+                    return Ok(());
+                }
+
+                // Config lives in the same database file — keep a copy for --db-only
+                let saved_config: Vec<(String, String)> = if db_only {
+                    db.list_all_config()?
+                        .into_iter()
+                        .filter(|(k, _)| k != "schema_version")
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+
+                let db_path = cli.db_path.clone().unwrap_or_else(db::default_db_path);
+                // Close our connection before deleting the files it has open
+                drop(ops);
+                drop(db);
 
                 if db_path.exists() {
                     std::fs::remove_file(&db_path)?;
+                    // WAL sidecar files belong to the old database
+                    for suffix in ["-wal", "-shm"] {
+                        let mut sidecar = db_path.clone().into_os_string();
+                        sidecar.push(suffix);
+                        std::fs::remove_file(&sidecar).ok();
+                    }
                     println!("{} Removed {}", "✓".green(), db_path.display());
                 }
-                if config_path.exists() {
-                    std::fs::remove_file(&config_path)?;
-                    println!("{} Removed {}", "✓".green(), config_path.display());
+
+                if db_only && !saved_config.is_empty() {
+                    let fresh = Database::open(Some(&db_path))?;
+                    for (k, v) in &saved_config {
+                        fresh.set_config(k, v)?;
+                    }
+                    println!(
+                        "{} Kept {} config value(s).",
+                        "✓".green(),
+                        saved_config.len()
+                    );
                 }
 
+                activity_log::log_activity("cli", "reset", if db_only { "db-only" } else { "all" });
                 println!(
                     "\n{} Database reset. Run {} to rediscover environments.",
                     "✓".green(),
-                    "zen scan".cyan()
+                    "zen list".cyan()
                 );
             }
+            Commands::Db { subcommand } => {
+                let db_path = cli.db_path.clone().unwrap_or_else(db::default_db_path);
+                // The WAL holds pages not yet folded into the main file
+                let on_disk = || {
+                    ["", "-wal"]
+                        .iter()
+                        .map(|suffix| {
+                            let mut file = db_path.clone().into_os_string();
+                            file.push(suffix);
+                            std::fs::metadata(file).map(|m| m.len()).unwrap_or(0)
+                        })
+                        .sum::<u64>()
+                };
+                match subcommand {
+                    DbCommands::Vacuum => {
+                        let before = on_disk();
+                        db.vacuum()?;
+                        let after = on_disk();
+                        activity_log::log_activity("cli", "db:vacuum", &after.to_string());
+                        println!(
+                            "{} Vacuumed {}: {} → {}",
+                            "✓".green(),
+                            db_path.display(),
+                            utils::format_size(before),
+                            utils::format_size(after)
+                        );
+                    }
+                    DbCommands::Check => {
+                        let problems = db.integrity_check()?;
+                        if problems.is_empty() {
+                            println!("{} {}: integrity ok", "✓".green(), db_path.display());
+                        } else {
+                            eprintln!(
+                                "{} {}: {} problem(s) found",
+                                "✗".red(),
+                                db_path.display(),
+                                problems.len()
+                            );
+                            for problem in &problems {
+                                eprintln!("  • {}", problem);
+                            }
+                            eprintln!(
+                                "  Restore a backup ({}) or start over with {}.",
+                                "zen.db.bak-v*".dimmed(),
+                                "zen reset".cyan()
+                            );
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            }
             Commands::Template { subcommand } => {
                 match subcommand {
                     TemplateCommands::Create {
                         name,
                         python: user_python,
+                        from_requirements,
+                        force,
                     } => {
                         // Validate inputs
                         crate::validation::validate_name(
@@ -2080,7 +3584,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             crate::validation::validate_python_version(py)?;
                         }
 
-                        let python = user_python.unwrap_or_else(|| "3.12".to_string());
+                        let python = user_python.unwrap_or_else(|| ops.default_python());
+
+                        if let Some(file) = from_requirements {
+                            let content = std::fs::read_to_string(&file)
+                                .map_err(|e| format!("Cannot read '{}': {}", file.display(), e))?;
+                            let (t_name, t_ver) = name.split_once(':').unwrap_or((&name, "latest"));
+                            match ops
+                                .template_from_requirements(t_name, t_ver, &python, &content, force)
+                            {
+                                Ok((msg, skipped)) => {
+                                    println!("{}", msg);
+                                    for line in &skipped {
+                                        eprintln!(
+                                            "  {} Skipped unsupported line: {}",
+                                            "⚠".yellow(),
+                                            line
+                                        );
+                                    }
+                                    activity_log::log_activity(
+                                        "cli",
+                                        "template:from-requirements",
+                                        &format!("{} → {}:{}", file.display(), t_name, t_ver),
+                                    );
+                                }
+                                Err(e) => {
+                                    eprintln!("{} {}", "✗".red(), e);
+                                    std::process::exit(1);
+                                }
+                            }
+                            return Ok(());
+                        }
                         if !db.clear_stale_session()? {
                             eprintln!(
                                 "A recording session is already active. Please save or exit first."
@@ -2109,7 +3643,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             }
                         );
 
-                        let status = if let Ok(uv_path) = which::which("uv") {
+                        let status = if let Some(uv_path) = ops.uv_path() {
                             std::process::Command::new(uv_path)
                                 .arg("venv")
                                 .arg(&tmp_env)
@@ -2132,7 +3666,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                         if status.success() {
                             let env_str = tmp_env.to_str().unwrap();
-                            if which::which("uv").is_ok() {
+                            if ops.uv_path().is_some() {
                                 utils::run_in_env_silent(
                                     env_str,
                                     "uv",
@@ -2148,7 +3682,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             db.start_session(temp_id, env_str)?;
 
                             // Enter interactive REPL
-                            template_repl(&db, temp_id, t_name, t_ver, env_str, is_new)?;
+                            template_repl(
+                                &db,
+                                temp_id,
+                                t_name,
+                                t_ver,
+                                env_str,
+                                is_new,
+                                ops.uv_path().is_some(),
+                            )?;
                         } else {
                             eprintln!("{} Failed to create template environment.", "✗".red());
                         }
@@ -2265,7 +3807,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 // Group by step
                                 let mut steps: std::collections::BTreeMap<
                                     i64,
-                                    Vec<&(String, String, bool, String, Option<String>, i64)>,
+                                    Vec<&crate::db::TemplatePackageRow>,
                                 > = std::collections::BTreeMap::new();
                                 for pkg in &packages {
                                     steps.entry(pkg.5).or_default().push(pkg);
@@ -2306,8 +3848,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     }
 
                                     for pkg in step_pkgs {
-                                        let name_col = format!("    {:<24}", pkg.0);
-                                        let ver_col = format!("{:<20}", pkg.1);
+                                        let name_col = format!(
+                                            "    {:<24}",
+                                            utils::with_extras(&pkg.0, pkg.6.as_deref())
+                                        );
+                                        // Ranges show instead of the frozen version they override
+                                        let shown_ver = match pkg.7.as_deref() {
+                                            Some(spec) if spec != format!("=={}", pkg.1) => spec,
+                                            _ => pkg.1.as_str(),
+                                        };
+                                        let ver_col = format!("{:<20}", shown_ver);
                                         let type_col = &pkg.3;
 
                                         if type_col == "wheel" {
@@ -2446,18 +3996,24 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 };
 
                                 for pkg_name in &args {
+                                    let extras = utils::requirement_extras(pkg_name);
                                     let (name, ver, itype, iargs) = if let Some(ref whl) = wheel {
                                         let whl_name = utils::normalize_wheel_name(whl)
                                             .unwrap_or_else(|| pkg_name.clone());
                                         (whl_name, "0.0.0".to_string(), "wheel", Some(whl.clone()))
                                     } else {
                                         (
-                                            pkg_name.clone(),
+                                            utils::requirement_name(pkg_name),
                                             "latest".to_string(),
                                             "pypi",
                                             install_args.clone(),
                                         )
                                     };
+                                    let version_spec = if wheel.is_some() {
+                                        None
+                                    } else {
+                                        utils::requirement_specifier(pkg_name)
+                                    };
                                     db.add_template_package(
                                         t_id,
                                         &name,
@@ -2466,6 +4022,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         itype,
                                         iargs.as_deref(),
                                         target_step,
+                                        extras.as_deref(),
+                                        version_spec.as_deref(),
                                     )?;
                                     println!(
                                         "{} Added '{}' to '{}:{}' step {}.",
@@ -2505,7 +4063,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 // Always start from scratch (Docker-like rebuild)
                                 println!("Rebuilding environment for '{}:{}'...", t_name, t_ver);
 
-                                let status = if let Ok(uv_path) = which::which("uv") {
+                                let status = if let Some(uv_path) = ops.uv_path() {
                                     std::process::Command::new(uv_path)
                                         .arg("venv")
                                         .arg(&tmp_env)
@@ -2532,7 +4090,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 }
 
                                 let env_str = tmp_env.to_str().unwrap();
-                                let use_uv = which::which("uv").is_ok();
+                                let use_uv = ops.uv_path().is_some();
 
                                 // Bootstrap (silent)
                                 if use_uv {
@@ -2557,11 +4115,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         i64,
                                         (Option<String>, Vec<(String, String)>),
                                     > = std::collections::BTreeMap::new();
-                                    for (p_name, _p_ver, _pinned, itype, iargs, step) in &packages {
+                                    for (
+                                        p_name,
+                                        p_ver,
+                                        _pinned,
+                                        itype,
+                                        iargs,
+                                        step,
+                                        extras,
+                                        spec,
+                                    ) in &packages
+                                    {
                                         let entry = steps
                                             .entry(*step)
                                             .or_insert_with(|| (iargs.clone(), Vec::new()));
-                                        entry.1.push((p_name.clone(), itype.clone()));
+                                        let requirement =
+                                            utils::with_extras(p_name, extras.as_deref());
+                                        entry.1.push((
+                                            utils::template_requirement(
+                                                &requirement,
+                                                p_ver,
+                                                false,
+                                                spec.as_deref(),
+                                            ),
+                                            itype.clone(),
+                                        ));
                                     }
 
                                     for (step_num, (install_args, pkgs)) in &steps {
@@ -2617,7 +4195,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 db.start_session(t_id, env_str)?;
 
                                 // Enter interactive REPL
-                                template_repl(&db, t_id, t_name, t_ver, env_str, false)?;
+                                template_repl(
+                                    &db,
+                                    t_id,
+                                    t_name,
+                                    t_ver,
+                                    env_str,
+                                    false,
+                                    ops.uv_path().is_some(),
+                                )?;
                             }
                         }
                     }
@@ -2688,13 +4274,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             i64,
                             (Option<String>, Vec<toml::Value>),
                         > = std::collections::BTreeMap::new();
-                        for (p_name, p_ver, _pinned, itype, iargs, step) in &packages {
+                        for (p_name, p_ver, _pinned, itype, iargs, step, extras, version_spec) in
+                            &packages
+                        {
                             let entry = steps
                                 .entry(*step)
                                 .or_insert_with(|| (iargs.clone(), Vec::new()));
                             let mut pkg = toml::map::Map::new();
                             pkg.insert("name".to_string(), toml::Value::String(p_name.clone()));
                             pkg.insert("version".to_string(), toml::Value::String(p_ver.clone()));
+                            if let Some(spec) = version_spec {
+                                pkg.insert(
+                                    "version_spec".to_string(),
+                                    toml::Value::String(spec.clone()),
+                                );
+                            }
+                            if let Some(extras) = extras {
+                                let list = extras
+                                    .split(',')
+                                    .map(|e| toml::Value::String(e.to_string()))
+                                    .collect();
+                                pkg.insert("extras".to_string(), toml::Value::Array(list));
+                            }
                             if itype != "pypi" {
                                 pkg.insert("type".to_string(), toml::Value::String(itype.clone()));
                             }
@@ -2747,6 +4348,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         );
                                     }
                                 }
+                                // Verbatim copy so flags beyond the index URLs survive a round trip
+                                step_table.insert(
+                                    "install_args".to_string(),
+                                    toml::Value::String(args.clone()),
+                                );
                             }
                             step_table
                                 .insert("packages".to_string(), toml::Value::Array(pkgs.clone()));
@@ -2766,7 +4372,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             out_path.cyan()
                         );
                     }
-                    TemplateCommands::ImportTpl { file } => {
+                    TemplateCommands::FromEnv { env, name, force } => {
+                        let env = expand_alias(env, &db)?;
+                        let env_name = types::EnvName::new(&env).map_err(|e| e.to_string())?;
+                        let mut parts = name.splitn(2, ':');
+                        let t_name = parts.next().unwrap();
+                        let t_ver = parts.next().unwrap_or("latest");
+                        crate::validation::validate_name(t_name, "Template")?;
+                        match ops.template_from_env(&env_name, t_name, t_ver, force) {
+                            Ok(msg) => {
+                                println!("{}", msg);
+                                activity_log::log_activity(
+                                    "cli",
+                                    "template:from-env",
+                                    &format!("{} → {}:{}", env, t_name, t_ver),
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "✗".red(), e);
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    TemplateCommands::ImportTpl { file, force } => {
                         let content = match std::fs::read_to_string(&file) {
                             Ok(c) => c,
                             Err(e) => {
@@ -2805,13 +4433,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .and_then(|v: &toml::Value| v.as_str())
                             .unwrap_or("3.12");
 
-                        // Delete existing template with same name:version if present
-                        if let Some(existing_id) = db.get_template_id(t_name, t_ver)? {
-                            db.delete_template_by_id(existing_id)?;
-                        }
-
-                        let (t_id, _) = db.create_template(t_name, t_ver, py_ver)?;
-
                         let steps = match doc.get("step").and_then(|v: &toml::Value| v.as_array()) {
                             Some(s) => s,
                             None => {
@@ -2820,7 +4441,19 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             }
                         };
 
-                        let mut total_pkgs = 0usize;
+                        // Replace an existing name:version only when asked to
+                        if !force && db.get_template_id(t_name, t_ver)?.is_some() {
+                            eprintln!(
+                                "{} Template '{}:{}' already exists. Use {} to overwrite it.",
+                                "✗".red(),
+                                t_name,
+                                t_ver,
+                                "--force".bold()
+                            );
+                            std::process::exit(1);
+                        }
+
+                        let mut rows: Vec<crate::db::TemplatePackageRow> = Vec::new();
                         for (step_num, step_val) in steps.iter().enumerate() {
                             let step_tbl = step_val.as_table();
                             // Build install_args from index_url / extra_index_url
@@ -2835,7 +4468,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     install_parts.push(format!("--extra-index-url {}", url));
                                 }
                             }
-                            let install_args = if install_parts.is_empty() {
+                            let verbatim = step_tbl
+                                .and_then(|t| t.get("install_args"))
+                                .and_then(|v| v.as_str())
+                                .map(str::to_string);
+                            let install_args = if verbatim.is_some() {
+                                verbatim
+                            } else if install_parts.is_empty() {
                                 None
                             } else {
                                 Some(install_parts.join(" "))
@@ -2857,6 +4496,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                         .get("version")
                                         .and_then(|v| v.as_str())
                                         .unwrap_or("latest");
+                                    // Hand-written ranges may sit in `version` itself
+                                    let (version, version_spec) = match pkg_tbl
+                                        .get("version_spec")
+                                        .and_then(|v| v.as_str())
+                                    {
+                                        Some(spec) => (version, Some(spec.to_string())),
+                                        None if version.starts_with(['>', '<', '=', '!', '~']) => {
+                                            ("latest", Some(version.replace(' ', "")))
+                                        }
+                                        None => (version, None),
+                                    };
                                     let itype = pkg_tbl
                                         .get("type")
                                         .and_then(|v| v.as_str())
@@ -2869,20 +4519,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     } else {
                                         install_args.clone()
                                     };
-
-                                    db.add_template_package(
-                                        t_id,
-                                        name,
-                                        version,
+                                    let extras = pkg_tbl
+                                        .get("extras")
+                                        .and_then(|v| v.as_array())
+                                        .map(|list| {
+                                            list.iter()
+                                                .filter_map(|e| e.as_str())
+                                                .collect::<Vec<_>>()
+                                                .join(",")
+                                        })
+                                        .filter(|e| !e.is_empty());
+
+                                    rows.push((
+                                        name.to_string(),
+                                        version.to_string(),
                                         true,
-                                        itype,
-                                        iargs.as_deref(),
+                                        itype.to_string(),
+                                        iargs,
                                         step_num as i64,
-                                    )?;
-                                    total_pkgs += 1;
+                                        extras,
+                                        version_spec,
+                                    ));
                                 }
                             }
                         }
+                        // One transaction: a failed write keeps the existing template
+                        db.replace_template(t_name, t_ver, py_ver, &rows)?;
+                        let total_pkgs = rows.len();
 
                         println!(
                             "{} Imported '{}:{}' from {} ({} package(s), {} step(s)).",
@@ -2906,16 +4569,64 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 pre,
                 upgrade,
                 dry_run,
+                quiet,
+                group,
+                constraint,
+                retries,
+                only_if_missing,
+                no_check,
             } => {
+                if let Some(ref g) = group {
+                    crate::validation::validate_name(g, "Group")?;
+                }
+                // Absolute, so pip resolves it regardless of its working directory
+                let constraint = constraint
+                    .map(|c| {
+                        c.canonicalize()
+                            .map_err(|e| format!("Constraint file '{}': {}", c.display(), e))
+                    })
+                    .transpose()?
+                    .map(|c| c.to_string_lossy().to_string());
+                // `zen install -e` alone installs the project in the current directory
+                let packages = if editable && packages.is_empty() {
+                    if !utils::is_python_project(&std::env::current_dir()?) {
+                        return Err(
+                            "Nothing to install: the current directory has no pyproject.toml, setup.py or setup.cfg"
+                                .into(),
+                        );
+                    }
+                    vec![".".to_string()]
+                } else {
+                    packages
+                };
+                // Local editable paths become absolute, so pip resolves them wherever it
+                // runs, and each one can be matched to the package it installed
+                let mut editable_dirs: std::collections::HashMap<String, PathBuf> =
+                    std::collections::HashMap::new();
+                let packages: Vec<String> = if editable {
+                    packages
+                        .into_iter()
+                        .map(|p| match utils::resolve_editable_spec(&p) {
+                            Some((dir, spec)) => {
+                                editable_dirs.insert(spec.clone(), dir);
+                                spec
+                            }
+                            None => p,
+                        })
+                        .collect()
+                } else {
+                    packages
+                };
                 let (target_id, target_path, is_session) =
                     if let Some(session) = db.get_active_session()? {
                         (Some(session.0), session.1, true)
                     } else if let Some(env_name) = env {
+                        let env_name = expand_alias(env_name, &db)?;
                         let envs = db.list_envs()?;
                         let e = envs
                             .iter()
                             .find(|(n, ..)| n == &env_name)
-                            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+                            .ok_or_else(|| ops.env_not_found(&env_name))?;
                         let id = db.get_env_id(&env_name)?.ok_or_else(|| {
                             format!("Environment '{}' not found in database", env_name)
                         })?;
@@ -2929,41 +4640,116 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         let e = envs
                             .iter()
                             .find(|(n, ..)| n == &resolved)
-                            .ok_or_else(|| format!("Environment '{}' not found", resolved))?;
+                            .ok_or_else(|| ops.env_not_found(&resolved))?;
                         let id = db.get_env_id(&resolved)?.ok_or_else(|| {
                             format!("Environment '{}' not found in database", resolved)
                         })?;
                         (Some(id), e.1.clone(), false)
                     };
 
-                println!("Installing packages in {}...", target_path);
+                let log_env = std::path::Path::new(&target_path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| target_path.clone());
+                let packages = if only_if_missing && !editable {
+                    let installed = utils::get_packages(&target_path);
+                    let (present, missing): (Vec<_>, Vec<_>) = packages
+                        .into_iter()
+                        .map(|p| (utils::satisfied_version(&p, &installed), p))
+                        .partition(|(v, _)| v.is_some());
+                    if !present.is_empty() {
+                        let skipped: Vec<String> = present
+                            .iter()
+                            .map(|(v, p)| format!("{} ({})", p, v.as_deref().unwrap_or("")))
+                            .collect();
+                        println!(
+                            "{} Already installed, skipping: {}",
+                            "✓".green(),
+                            skipped.join(", ")
+                        );
+                    }
+                    if missing.is_empty() {
+                        println!("Nothing to install.");
+                        let names: Vec<&str> = present.iter().map(|(_, p)| p.as_str()).collect();
+                        activity_log::log_activity(
+                            "cli",
+                            "install:skipped",
+                            &format!("{} {} (already installed)", log_env, names.join(" ")),
+                        );
+                        return Ok(());
+                    }
+                    missing.into_iter().map(|(_, p)| p).collect()
+                } else {
+                    packages
+                };
+
+                if !quiet {
+                    println!("Installing packages in {}...", target_path);
+                }
 
                 let mut final_args = Vec::new();
                 let mut index_url = cli_index_url.clone();
 
                 for pkg in &packages {
-                    if pkg.starts_with("torch-cu") {
-                        let cuda_ver = pkg.trim_start_matches("torch-cu");
-                        // Map common aliases (e.g. 130 -> 13.0)
-                        let normalized_cuda = if cuda_ver.len() == 3 {
-                            format!("{}.{}", &cuda_ver[0..2], &cuda_ver[2..])
-                        } else {
-                            cuda_ver.to_string()
-                        };
-
-                        if let Some(url) = utils::get_torch_index_url(&normalized_cuda) {
-                            index_url = Some(url.to_string());
-                            final_args.push("torch".to_string());
-                            final_args.push("torchvision".to_string());
-                            final_args.push("torchaudio".to_string());
-                        } else {
-                            final_args.push(pkg.clone());
-                        }
+                    // torch-cu130 → torch/torchvision/torchaudio from the CUDA 13.0 index,
+                    // torch-cpu → the same from the CPU-only index
+                    let alias_ver = if pkg == "torch-cpu" {
+                        Some("cpu")
+                    } else {
+                        pkg.strip_prefix("torch-cu").filter(|v| {
+                            !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+                        })
+                    };
+                    if let Some(cuda_ver) = alias_ver {
+                        match utils::get_torch_index_url(cuda_ver) {
+                            Ok(url) => {
+                                index_url = Some(url.to_string());
+                                final_args.push("torch".to_string());
+                                final_args.push("torchvision".to_string());
+                                final_args.push("torchaudio".to_string());
+                            }
+                            Err(e) => {
+                                eprintln!("{} {}", "Error:".red(), e);
+                                eprintln!(
+                                    "  See {} for the available aliases.",
+                                    "zen cuda list".bold()
+                                );
+                                std::process::exit(1);
+                            }
+                        }
                     } else {
                         final_args.push(pkg.clone());
                     }
                 }
 
+                // Torch packages follow the env's recorded variant unless an index was given
+                let env_name = match target_id {
+                    Some(id) if !is_session => db.get_env_name_by_id(id)?,
+                    _ => None,
+                };
+                let mut variant_index = None;
+                if index_url.is_none()
+                    && let Some(ref env_name) = env_name
+                    && let Some(variant) = db.get_cuda_variant(env_name)?
+                    && final_args
+                        .iter()
+                        .any(|p| utils::is_torch_variant_package(p))
+                {
+                    let url = utils::torch_index_for_variant(&variant);
+                    if !quiet {
+                        println!("  Using {} index for PyTorch packages: {}", variant, url);
+                    }
+                    // Other packages still resolve from the default index
+                    if final_args
+                        .iter()
+                        .all(|p| utils::is_torch_variant_package(p))
+                    {
+                        index_url = Some(url);
+                    } else {
+                        variant_index = Some(url);
+                    }
+                }
+
                 let mut cmd_args = vec!["pip", "install"];
 
                 // Add pip-compatible flags
@@ -2987,16 +4773,77 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     cmd_args.push("--extra-index-url");
                     cmd_args.push(url);
                 }
+                if let Some(ref url) = variant_index {
+                    cmd_args.push("--extra-index-url");
+                    cmd_args.push(url);
+                }
+                if let Some(ref file) = constraint {
+                    cmd_args.push("--constraint");
+                    cmd_args.push(file);
+                }
 
                 for pkg in &final_args {
                     cmd_args.push(pkg);
                 }
 
-                let success = if which::which("uv").is_ok() {
-                    utils::run_in_env(&target_path, "uv", &cmd_args)
+                // Stream pip/uv output live (progress bars) unless --quiet. Retries need
+                // stderr to tell network errors apart, so it is teed through a pipe.
+                // Conflicts that predate the install aren't this install's doing
+                let check_conflicts = !no_check && !dry_run;
+                let conflicts_before: std::collections::HashSet<String> = if check_conflicts {
+                    use crate::types::Diagnostic;
+                    utils::check_dependencies(&target_path)
+                        .iter()
+                        .map(|i| i.message())
+                        .collect()
+                } else {
+                    Default::default()
+                };
+
+                // Tee costs uv its progress bars, so a live install only pipes stderr
+                // when retries were asked for; quiet installs capture it anyway
+                let explicit_retries =
+                    retries.is_some() || db.get_config("install_retries")?.is_some();
+                let retries =
+                    retries.unwrap_or_else(|| crate::ops::configured_install_retries(&db));
+                let mode = if quiet {
+                    utils::StreamMode::Quiet
+                } else if explicit_retries && retries > 0 {
+                    utils::StreamMode::Tee
                 } else {
-                    utils::run_in_env(&target_path, "pip", &cmd_args[1..])
+                    utils::StreamMode::Live
                 };
+                let outcome = utils::run_with_retries(
+                    retries,
+                    || {
+                        if ops.uv_path().is_some() {
+                            utils::run_in_env_streamed(&target_path, "uv", &cmd_args, mode)
+                        } else {
+                            utils::run_in_env_streamed(&target_path, "pip", &cmd_args[1..], mode)
+                        }
+                    },
+                    |attempt, delay| {
+                        eprintln!(
+                            "  {} Network error, retrying in {}s (attempt {}/{})...",
+                            "⚠".yellow(),
+                            delay.as_secs(),
+                            attempt,
+                            retries
+                        );
+                        activity_log::log_activity(
+                            "cli",
+                            "install:retry",
+                            &format!(
+                                "{} {} (attempt {}/{})",
+                                log_env,
+                                packages.join(" "),
+                                attempt,
+                                retries
+                            ),
+                        );
+                    },
+                );
+                let success = outcome.success;
 
                 // Record packages to session or audit log.
                 // BUG FIX: Always scan even on partial failure — some packages
@@ -3025,8 +4872,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     let mut recorded = 0usize;
                     for pkg_name in &packages {
                         // Resolve the pip name for matching
-                        let (base_name, is_wheel, wheel_path) = if pkg_name.starts_with("torch-cu")
+                        let (base_name, is_wheel, wheel_path) = if let Some(pkg) = editable_dirs
+                            .get(pkg_name)
+                            .and_then(|dir| utils::find_editable(&installed, dir))
                         {
+                            (pkg.name.clone(), false, None)
+                        } else if pkg_name.starts_with("torch-cu") || pkg_name == "torch-cpu" {
                             ("torch".to_string(), false, None)
                         } else if pkg_name.ends_with(".whl") || pkg_name.contains(".whl") {
                             // Wheel file — extract distribution name from PEP 427 filename
@@ -3035,7 +4886,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 None => (pkg_name.clone(), false, None),
                             }
                         } else {
-                            (pkg_name.clone(), false, None)
+                            (utils::requirement_name(pkg_name), false, None)
+                        };
+                        // Keep requested extras ("pkg[cuda]") so template apply reinstalls them
+                        let extras = if is_wheel {
+                            None
+                        } else {
+                            utils::requirement_extras(pkg_name)
                         };
 
                         // Match against installed packages (normalize both sides)
@@ -3052,13 +4909,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             } else {
                                 ("pypi", install_args_str.as_deref())
                             };
+                            let version_spec = format!("=={}", ver);
                             db.add_template_package(
-                                t_id, &pkg.name, ver, true, itype, iargs, step,
+                                t_id,
+                                &pkg.name,
+                                ver,
+                                true,
+                                itype,
+                                iargs,
+                                step,
+                                extras.as_deref(),
+                                (!is_wheel).then_some(version_spec.as_str()),
                             )?;
                             recorded += 1;
                         }
                     }
 
+                    if group.is_some() {
+                        eprintln!(
+                            "  {} --group is ignored in a template session.",
+                            "⚠".yellow()
+                        );
+                    }
                     if !success && recorded > 0 {
                         eprintln!(
                             "  {} Some packages failed, but {} successfully-installed package(s) were recorded.",
@@ -3066,18 +4938,43 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             recorded
                         );
                     }
-                } else if success {
+                } else if success && !dry_run {
                     let e_id = target_id.ok_or("Missing environment ID")?;
                     let installed = utils::get_packages(&target_path);
+                    let mut grouped = Vec::new();
                     for pkg_name in &packages {
-                        let base_name = if pkg_name.starts_with("torch-cu") {
-                            "torch".to_string()
-                        } else if pkg_name.ends_with(".whl") || pkg_name.contains(".whl") {
-                            utils::normalize_wheel_name(pkg_name)
-                                .unwrap_or_else(|| pkg_name.clone())
-                        } else {
-                            pkg_name.clone()
-                        };
+                        if let Some(pkg) = editable_dirs
+                            .get(pkg_name)
+                            .and_then(|dir| utils::find_editable(&installed, dir))
+                        {
+                            let ver = pkg.version.as_deref().unwrap_or("unknown");
+                            db.log_package(e_id, &pkg.name, ver, "edit")?;
+                            grouped.push(pkg.name.clone());
+                            if !quiet {
+                                let import = if pkg.import_names.is_empty() {
+                                    String::new()
+                                } else {
+                                    format!(" (import {})", pkg.import_names.join(", "))
+                                };
+                                println!(
+                                    "  Editable: {} {}{} ← {}",
+                                    pkg.name,
+                                    ver,
+                                    import,
+                                    editable_dirs[pkg_name].display()
+                                );
+                            }
+                            continue;
+                        }
+                        let base_name =
+                            if pkg_name.starts_with("torch-cu") || pkg_name == "torch-cpu" {
+                                "torch".to_string()
+                            } else if pkg_name.ends_with(".whl") || pkg_name.contains(".whl") {
+                                utils::normalize_wheel_name(pkg_name)
+                                    .unwrap_or_else(|| pkg_name.clone())
+                            } else {
+                                utils::requirement_name(pkg_name)
+                            };
                         let norm_base = utils::normalize_package_name(&base_name);
                         if let Some(pkg) = installed
                             .iter()
@@ -3085,31 +4982,89 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         {
                             let ver = pkg.version.as_deref().unwrap_or("unknown");
                             db.log_package(e_id, &pkg.name, ver, "pypi")?;
+                            grouped.push(pkg.name.clone());
+                        }
+                    }
+                    if let Some(env_name) = db.get_env_name_by_id(e_id)? {
+                        db.touch_env(&env_name)?;
+                        // A PyTorch index install marks the env's variant for later installs
+                        if success
+                            && !dry_run
+                            && let Some(variant) = index_url
+                                .as_deref()
+                                .and_then(utils::torch_variant_from_index)
+                            && db.get_cuda_variant(&env_name)?.as_deref() != Some(variant.as_str())
+                        {
+                            db.set_cuda_variant(&env_name, &variant)?;
+                            if !quiet {
+                                println!(
+                                    "Recorded PyTorch variant '{}' for '{}'.",
+                                    variant, env_name
+                                );
+                            }
+                        }
+                        if let Some(ref g) = group {
+                            db.add_group_packages(&env_name, g, &grouped)?;
+                            if !quiet {
+                                println!("Recorded {} package(s) in group '{}'.", grouped.len(), g);
+                            }
                         }
                     }
                 }
 
                 if success {
-                    println!("Installation complete.");
-                    let log_env = std::path::Path::new(&target_path)
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| target_path.clone());
+                    if !quiet {
+                        println!("Installation complete.");
+                    }
+                    if check_conflicts {
+                        use crate::types::{Diagnostic, HealthLevel};
+                        let introduced: Vec<String> = utils::check_dependencies(&target_path)
+                            .iter()
+                            .filter(|i| i.level() >= HealthLevel::Warn)
+                            .map(|i| i.message())
+                            .filter(|m| !conflicts_before.contains(m))
+                            .collect();
+                        for issue in &introduced {
+                            eprintln!(
+                                "{} Installing {} introduced: {}",
+                                "⚠".yellow(),
+                                packages.join(" "),
+                                issue
+                            );
+                        }
+                        if !introduced.is_empty()
+                            && let Some(ref env_name) = env_name
+                        {
+                            eprintln!(
+                                "  Run {} for the full report.",
+                                format!("zen health {}", env_name).bold()
+                            );
+                        }
+                    }
                     activity_log::log_activity(
                         "cli",
                         "install",
-                        &format!("{} {}", log_env, packages.join(" ")),
+                        &format!(
+                            "{} {} ({:.1}s)",
+                            log_env,
+                            packages.join(" "),
+                            outcome.elapsed.as_secs_f64()
+                        ),
                     );
                 } else {
-                    let log_env = std::path::Path::new(&target_path)
-                        .file_name()
-                        .map(|n| n.to_string_lossy().to_string())
-                        .unwrap_or_else(|| target_path.clone());
+                    let exit = outcome
+                        .code
+                        .map(|c| format!("exit {}", c))
+                        .unwrap_or_else(|| "did not run".to_string());
                     activity_log::log_activity(
                         "cli",
                         "install:error",
-                        &format!("{} {}", log_env, packages.join(" ")),
+                        &format!("{} {} ({})", log_env, packages.join(" "), exit),
                     );
+                    // Quiet mode swallowed the stream — show what went wrong
+                    if quiet && !outcome.stderr.is_empty() {
+                        eprint!("{}", outcome.stderr);
+                    }
                     eprintln!(
                         "{} Package installation failed. Check the error message above.",
                         "Error:".red()
@@ -3117,8 +5072,78 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     std::process::exit(1);
                 }
             }
-            Commands::Run { name, command } => {
+            Commands::Run {
+                name,
+                command,
+                all: true,
+                label,
+                continue_on_error,
+            } => {
+                // With --all there is no env name: the positional is the command
+                let command: Vec<String> = std::iter::once(name).chain(command).collect();
+                let mut envs: Vec<(String, String)> = db
+                    .list_envs()?
+                    .into_iter()
+                    .map(|(n, p, ..)| (n, p))
+                    .collect();
+                if let Some(ref label) = label {
+                    let labeled = db.get_envs_by_label(label)?;
+                    envs.retain(|(n, _)| labeled.contains(n));
+                }
+                envs.sort_by(|a, b| a.0.cmp(&b.0));
+
+                let (mut passed, mut failed, mut skipped) = (0, 0, 0);
+                for (env, path) in &envs {
+                    if !std::path::Path::new(path).exists() {
+                        println!(
+                            "{} {} {}",
+                            "──".dimmed(),
+                            env.bold(),
+                            "(missing, skipped)".dimmed()
+                        );
+                        skipped += 1;
+                        continue;
+                    }
+                    let env_name = types::EnvName::new(env)?;
+                    db.touch_env(env)?;
+                    let (code, output) = ops.run_in_env(&env_name, command.clone())?;
+                    let status = if code == 0 {
+                        "exit 0".tone(Tone::Accent).to_string()
+                    } else {
+                        format!("exit {}", code).red().to_string()
+                    };
+                    println!("{} {} ({})", "──".dimmed(), env.bold(), status);
+                    if !output.trim().is_empty() {
+                        println!("{}", output.trim_end());
+                    }
+                    if code == 0 {
+                        passed += 1;
+                    } else {
+                        failed += 1;
+                        if !continue_on_error {
+                            break;
+                        }
+                    }
+                }
+
+                let mut summary = format!("{} passed, {} failed", passed, failed);
+                if skipped > 0 {
+                    summary.push_str(&format!(", {} skipped", skipped));
+                }
+                let not_run = envs.len() - passed - failed - skipped;
+                if not_run > 0 {
+                    summary.push_str(&format!(", {} not run (use --continue-on-error)", not_run));
+                }
+                println!("\n{} {}", "Summary:".bold(), summary);
+                activity_log::log_activity("cli", "run:all", &command.join(" "));
+                if failed > 0 {
+                    std::process::exit(1);
+                }
+            }
+            Commands::Run { name, command, .. } => {
+                let name = expand_alias(name, &db)?;
                 let env_name = types::EnvName::new(&name)?;
+                db.touch_env(&name)?;
                 match ops.run_in_env(&env_name, command) {
                     Ok((code, output)) => {
                         print!("{}", output);
@@ -3129,7 +5154,162 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     Err(e) => return Err(e),
                 }
             }
-            Commands::Uninstall { packages, env } => {
+            Commands::Time { env, module, top } => {
+                let valid = !module.is_empty()
+                    && module.split('.').all(|part| {
+                        part.chars().next().is_some_and(|c| !c.is_ascii_digit())
+                            && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+                    });
+                if !valid {
+                    eprintln!("{} '{}' is not a module name.", "Error:".red(), module);
+                    std::process::exit(2);
+                }
+                let name = expand_alias(env, &db)?;
+                let env_name = types::EnvName::new(&name)?;
+                db.touch_env(&name)?;
+                let importtime = |code: String| {
+                    ops.run_in_env(
+                        &env_name,
+                        vec![
+                            "python".into(),
+                            "-X".into(),
+                            "importtime".into(),
+                            "-c".into(),
+                            code,
+                        ],
+                    )
+                };
+                // Modules loaded at interpreter startup are already cached when
+                // the import runs, so only what the import itself adds counts.
+                let (_, baseline) = importtime("pass".into())?;
+                let startup: std::collections::HashSet<String> = utils::parse_importtime(&baseline)
+                    .into_iter()
+                    .map(|t| t.module)
+                    .collect();
+                let (code, output) = importtime(format!("import {}", module))?;
+                if code != 0 {
+                    for line in output.lines().filter(|l| !l.starts_with("import time:")) {
+                        eprintln!("{}", line);
+                    }
+                    std::process::exit(code);
+                }
+                let mut times: Vec<_> = utils::parse_importtime(&output)
+                    .into_iter()
+                    .filter(|t| !startup.contains(&t.module))
+                    .collect();
+                let total_us: u64 = times.iter().map(|t| t.self_us).sum();
+                times.sort_by_key(|t| std::cmp::Reverse(t.self_us));
+                let ms = |us: u64| format!("{:.1} ms", us as f64 / 1000.0);
+                println!(
+                    "{} {} in '{}': {} across {} module(s)",
+                    "import".dimmed(),
+                    module.tone(Tone::Accent),
+                    name,
+                    ms(total_us).bold(),
+                    times.len()
+                );
+                if !times.is_empty() {
+                    println!();
+                    println!("{:>10}  {:>10}  module", "self", "cumulative");
+                }
+                for t in times.iter().take(top) {
+                    println!(
+                        "{:>10}  {:>10}  {}",
+                        ms(t.self_us),
+                        ms(t.cumulative_us).dimmed(),
+                        t.module
+                    );
+                }
+                if times.len() > top {
+                    println!(
+                        "{}",
+                        format!("(showing the slowest {} of {})", top, times.len()).dimmed()
+                    );
+                }
+            }
+            Commands::Shell { name } => {
+                // A subshell inside a subshell stacks activations confusingly
+                if let Ok(current) = std::env::var("ZEN_SHELL") {
+                    eprintln!(
+                        "{} Already in a zen shell for '{}'. Exit it first.",
+                        "Error:".red(),
+                        current
+                    );
+                    std::process::exit(1);
+                }
+                let name = expand_alias(name, &db)?;
+                let envs = db.list_envs()?;
+                let Some((_, env_path, ..)) = envs.iter().find(|(n, ..)| n == &name) else {
+                    eprintln!("{} {}", "Error:".red(), ops.env_not_found(&name));
+                    std::process::exit(1);
+                };
+                let env_path = Path::new(env_path);
+                let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+                let kind = Path::new(&shell)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let user_dir = std::env::var_os("ZDOTDIR")
+                    .filter(|_| kind == "zsh")
+                    .map(PathBuf::from)
+                    .or_else(home::home_dir)
+                    .unwrap_or_default();
+
+                let mut cmd = std::process::Command::new(&shell);
+                cmd.envs(db.list_env_vars(&name)?)
+                    .env("ZEN_SHELL", &name)
+                    .env_remove("PYTHONHOME");
+                let rc_dir = std::env::temp_dir().join(format!("zen-shell-{}", std::process::id()));
+                let files = hooks::subshell_files(&kind, &user_dir, env_path);
+                if files.is_empty() {
+                    // No rc file to hook: activate through the environment instead
+                    let bin = env_path.join("bin");
+                    let path = std::env::var("PATH").unwrap_or_default();
+                    cmd.env("PATH", format!("{}:{}", bin.display(), path))
+                        .env("VIRTUAL_ENV", env_path)
+                        .env("PS1", format!("[zen] ({}) $ ", name));
+                } else {
+                    std::fs::create_dir_all(&rc_dir)?;
+                    for (file, contents) in &files {
+                        std::fs::write(rc_dir.join(file), contents)?;
+                    }
+                    if kind == "bash" {
+                        cmd.arg("--rcfile").arg(rc_dir.join("bashrc")).arg("-i");
+                    } else {
+                        cmd.env("ZDOTDIR", &rc_dir).arg("-i");
+                    }
+                }
+
+                if let Ok(cwd) = std::env::current_dir() {
+                    let cwd_str = cwd
+                        .canonicalize()
+                        .unwrap_or(cwd)
+                        .to_string_lossy()
+                        .to_string();
+                    let _ = db.record_activation(&cwd_str, &name);
+                }
+                db.touch_env(&name)?;
+                activity_log::log_activity("cli", "shell", &name);
+
+                eprintln!(
+                    "Entering zen shell for '{}'. Type {} to leave.",
+                    name.tone(Tone::Accent),
+                    "exit".bold()
+                );
+                let status = cmd.status();
+                let _ = std::fs::remove_dir_all(&rc_dir);
+                let status = status.map_err(|e| format!("Could not start {}: {}", shell, e))?;
+                eprintln!("Left zen shell for '{}'.", name);
+                if let Some(code) = status.code().filter(|c| *c != 0) {
+                    std::process::exit(code);
+                }
+            }
+            Commands::Uninstall {
+                packages,
+                env,
+                group,
+                yes,
+            } => {
                 let env_name = if let Some(name) = env {
                     types::EnvName::new(&name)?
                 } else if let Some(session) = db.get_active_session()? {
@@ -3148,8 +5328,87 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     types::EnvName::new(&resolved)?
                 };
 
+                let packages = match group {
+                    Some(ref g) => {
+                        let members = db.get_group_packages(env_name.as_str(), g)?;
+                        if members.is_empty() {
+                            eprintln!(
+                                "{} No install group '{}' in '{}'.",
+                                "Error:".red(),
+                                g,
+                                env_name
+                            );
+                            std::process::exit(1);
+                        }
+                        members
+                    }
+                    None if packages.iter().any(|p| utils::is_glob(p)) => {
+                        // Globs expand against what is actually installed
+                        let envs = db.list_envs()?;
+                        let (_, env_path, ..) = envs
+                            .iter()
+                            .find(|(n, ..)| n == env_name.as_str())
+                            .ok_or_else(|| ops.env_not_found(&env_name))?;
+                        let installed = utils::get_packages(env_path);
+                        let mut expanded: Vec<String> = Vec::new();
+                        for pkg in &packages {
+                            if !utils::is_glob(pkg) {
+                                expanded.push(pkg.clone());
+                                continue;
+                            }
+                            let matched = utils::match_packages(pkg, &installed);
+                            if matched.is_empty() {
+                                println!("{} No installed packages match '{}'.", "⚠".yellow(), pkg);
+                            }
+                            for m in matched {
+                                if !expanded.contains(&m) {
+                                    expanded.push(m);
+                                }
+                            }
+                        }
+                        if expanded.is_empty() {
+                            println!("Nothing to uninstall.");
+                            return Ok(());
+                        }
+                        println!(
+                            "{} package(s) to uninstall from '{}':",
+                            expanded.len(),
+                            env_name
+                        );
+                        for m in &expanded {
+                            println!("  • {}", m.tone(Tone::Accent));
+                        }
+                        if !yes {
+                            use dialoguer::{Confirm, theme::ColorfulTheme};
+                            let confirmed = match Confirm::with_theme(&ColorfulTheme::default())
+                                .with_prompt(format!(
+                                    "Uninstall these {} packages?",
+                                    expanded.len()
+                                ))
+                                .default(false)
+                                .interact()
+                            {
+                                Ok(v) => v,
+                                Err(_) => {
+                                    println!();
+                                    return Ok(());
+                                }
+                            };
+                            if !confirmed {
+                                println!("Abort.");
+                                return Ok(());
+                            }
+                        }
+                        expanded
+                    }
+                    None => packages,
+                };
+
                 match ops.uninstall_packages(&env_name, packages.clone()) {
                     Ok(msg) => {
+                        if let Some(ref g) = group {
+                            db.delete_group(env_name.as_str(), g)?;
+                        }
                         println!("{}", msg);
                         activity_log::log_activity(
                             "cli",
@@ -3168,124 +5427,312 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                 }
             }
-            Commands::Info { name } => {
+            Commands::Freeze {
+                env,
+                format,
+                output,
+            } => {
+                let env_name = types::EnvName::new(&resolve_env_name(env, &db)?)?;
+                let format = match format {
+                    FreezeFormatArg::Requirements => crate::ops::FreezeFormat::Requirements,
+                    FreezeFormatArg::Pyproject => crate::ops::FreezeFormat::Pyproject,
+                };
+                let frozen = ops.freeze_env(&env_name, format)?;
+                if let Some(path) = output {
+                    std::fs::write(&path, frozen)?;
+                    println!("{} Wrote {}", "✓".green(), path.display());
+                } else {
+                    print!("{}", frozen);
+                }
+            }
+            Commands::Which { name, bin } => {
+                let name = resolve_env_name(name, &db)?;
+                let envs = db.list_envs()?;
+                let (_, env_path, ..) = envs
+                    .iter()
+                    .find(|(n, ..)| n == &name)
+                    .ok_or_else(|| format!("Environment '{}' not found", name))?;
+                let bin = bin.unwrap_or_else(|| "python".to_string());
+                if bin.contains('/') {
+                    return Err(format!("Invalid binary name '{}'", bin).into());
+                }
+                let bin_path = Path::new(env_path).join("bin").join(&bin);
+                if !bin_path.exists() {
+                    return Err(format!("'{}' not found in environment '{}'", bin, name).into());
+                }
+                println!("{}", bin_path.display());
+            }
+            Commands::Path { name } => {
+                if name.is_none()
+                    && let Ok(venv) = std::env::var("VIRTUAL_ENV")
+                    && !venv.is_empty()
+                {
+                    println!("{}", venv);
+                    return Ok(());
+                }
                 let name = resolve_env_name(name, &db)?;
+                let envs = db.list_envs()?;
+                let (_, env_path, ..) = envs
+                    .iter()
+                    .find(|(n, ..)| n == &name)
+                    .ok_or_else(|| format!("Environment '{}' not found", name))?;
+                println!("{}", env_path);
+            }
+            Commands::Info {
+                name,
+                link,
+                compare_template,
+            } => {
+                let names = resolve_env_names(name, &db)?;
                 let envs = ops.list_envs_with_status(None, None, None)?;
-                let env = envs.iter().find(|(n, ..)| n == &name);
-                if let Some((_, path, _, exists, ..)) = env {
-                    if !exists {
-                        println!(
-                            "Environment: {} (MISSING on filesystem)",
-                            name.magenta().bold()
-                        );
-                    } else {
-                        let py_ver = utils::read_python_version(path)
-                            .unwrap_or_else(|| "unknown".to_string());
-                        println!(
-                            "{}  {}",
-                            "Environment:".bold(),
-                            name.truecolor(100, 200, 255)
-                        );
-                        println!("{}       {}", "Path:".bold(), path.dimmed());
-                        println!("{}     {}", "Python:".bold(), py_ver);
-
-                        // Torch version from version.py (accurate CUDA suffix)
-                        let (torch_ver, cuda_ver) = utils::read_torch_version(path)
-                            .map(|(t, c)| (Some(t), c))
-                            .unwrap_or((None, None));
-
-                        // All packages from scan
-                        let packages = utils::get_packages(path);
-                        let get_ver = |name: &str| {
-                            packages
-                                .iter()
-                                .find(|p| p.name == name)
-                                .and_then(|p| p.version.clone())
-                        };
+                for (i, name) in names.into_iter().enumerate() {
+                    if i > 0 {
+                        println!();
+                    }
+                    let env = envs.iter().find(|(n, ..)| n == &name);
+                    if let Some((_, path, _, exists, ..)) = env {
+                        if !exists {
+                            println!(
+                                "Environment: {} (MISSING on filesystem)",
+                                name.magenta().bold()
+                            );
+                        } else {
+                            let mut py_ver = utils::read_python_version(path)
+                                .unwrap_or_else(|| "unknown".to_string());
+                            if utils::is_conda_env(path) {
+                                py_ver.push_str(" (conda)");
+                            }
+                            println!("{}  {}", "Environment:".bold(), name.tone(Tone::Accent));
+                            println!("{}       {}", "Path:".bold(), path.dimmed());
+                            let aliases = db.get_env_aliases(&name)?;
+                            if !aliases.is_empty() {
+                                println!("{}    {}", "Aliases:".bold(), aliases.join(", "));
+                            }
+                            println!("{}     {}", "Python:".bold(), py_ver);
+                            if let Some(source) = db.get_source_template(&name)? {
+                                println!("{} {}", "Created from:".bold(), source);
+                            }
+                            if utils::inherits_system_site_packages(path) {
+                                println!(
+                                    "{}     {}",
+                                    "System:".bold(),
+                                    "inherits system site-packages".tone(Tone::Minor)
+                                );
+                            }
 
-                        // NumPy with version coloring
-                        if let Some(np_ver) = get_ver("numpy") {
-                            let colored = if np_ver.starts_with('2') || np_ver.starts_with('3') {
-                                np_ver.truecolor(100, 200, 255).to_string()
-                            } else {
-                                np_ver.truecolor(255, 140, 0).to_string()
-                            };
-                            println!("{}      {}", "NumPy:".bold(), colored);
-                        }
+                            // Torch version from version.py (accurate CUDA suffix)
+                            let (torch_ver, cuda_ver) = utils::read_torch_version(path)
+                                .map(|(t, c)| (Some(t), c))
+                                .unwrap_or((None, None));
 
-                        // Torch with +cu coloring
-                        if let Some(ref tv) = torch_ver {
-                            let colored = if tv.contains("+cu") {
-                                tv.green().to_string()
-                            } else {
-                                tv.to_string()
+                            // All packages from scan
+                            let packages = utils::get_packages(path);
+                            let get_ver = |name: &str| {
+                                packages
+                                    .iter()
+                                    .find(|p| p.name == name)
+                                    .and_then(|p| p.version.clone())
                             };
-                            println!("{}      {}", "Torch:".bold(), colored);
-                        }
-                        if let Some(ref cv) = cuda_ver {
-                            println!("{}       {}", "CUDA:".bold(), cv);
-                        }
-
-                        // Package count
-                        println!(
-                            "{}   {}",
-                            "Packages:".bold(),
-                            packages.len().to_string().dimmed()
-                        );
 
-                        // Quick health
-                        let health = crate::ops::check_health_quick(std::path::Path::new(path));
-                        let health_str = match health {
-                            crate::types::HealthLevel::Pass => {
-                                format!("{} {}", "✓".truecolor(100, 200, 255), "ok".dimmed())
+                            // NumPy with version coloring
+                            if let Some(np_ver) = get_ver("numpy") {
+                                let colored = if np_ver.starts_with('2') || np_ver.starts_with('3')
+                                {
+                                    np_ver.tone(Tone::Accent).to_string()
+                                } else {
+                                    np_ver.tone(Tone::Warn).to_string()
+                                };
+                                println!("{}      {}", "NumPy:".bold(), colored);
                             }
-                            crate::types::HealthLevel::Info => {
-                                format!("{} {}", "△".truecolor(255, 182, 193), "minor".dimmed())
+
+                            // Torch with +cu coloring
+                            if let Some(ref tv) = torch_ver {
+                                let colored = if tv.contains("+cu") {
+                                    tv.green().to_string()
+                                } else {
+                                    tv.to_string()
+                                };
+                                println!("{}      {}", "Torch:".bold(), colored);
                             }
-                            crate::types::HealthLevel::Warn => {
-                                format!("{} {}", "!".truecolor(255, 140, 0), "drift".dimmed())
+                            if let Some(ref cv) = cuda_ver {
+                                println!("{}       {}", "CUDA:".bold(), cv);
                             }
-                            crate::types::HealthLevel::Fail => {
-                                format!("{} {}", "✗".red(), "broken".dimmed())
+                            if let Some(variant) = db.get_cuda_variant(&name)? {
+                                println!(
+                                    "{}    {} {}",
+                                    "Variant:".bold(),
+                                    variant.tone(Tone::Accent),
+                                    format!(
+                                        "(installs use {})",
+                                        utils::torch_index_for_variant(&variant)
+                                    )
+                                    .dimmed()
+                                );
                             }
-                        };
-                        println!("{}     {}", "Health:".bold(), health_str);
 
-                        // Editable source packages
-                        let source: Vec<_> = packages
-                            .iter()
-                            .filter(|p| p.is_editable)
-                            .map(|p| p.name.clone())
-                            .collect();
-                        if !source.is_empty() {
+                            // Package count
                             println!(
-                                "{}     {}",
-                                "Project:".bold(),
-                                source.join(", ").truecolor(100, 200, 255)
+                                "{}   {}",
+                                "Packages:".bold(),
+                                packages.len().to_string().dimmed()
                             );
+                            if let Some(ages) = utils::package_ages(&packages) {
+                                let now = chrono::Utc::now().timestamp();
+                                let ago =
+                                    |t: i64| utils::format_ago(chrono::Duration::seconds(now - t));
+                                let mut line = format!(
+                                    "newest {} ({}), oldest {} ({}), median {}",
+                                    ages.newest.0,
+                                    ago(ages.newest.1),
+                                    ages.oldest.0,
+                                    ago(ages.oldest.1),
+                                    ago(ages.median)
+                                );
+                                if ages.dated < packages.len() {
+                                    line.push_str(&format!(
+                                        " — {} of {} dated",
+                                        ages.dated,
+                                        packages.len()
+                                    ));
+                                }
+                                println!("{}  {}", "Installed:".bold(), line.dimmed());
+                            }
+
+                            // Quick health
+                            let health = crate::ops::check_health_quick(std::path::Path::new(path));
+                            let health_str = match health {
+                                crate::types::HealthLevel::Pass => {
+                                    format!("{} {}", "✓".tone(Tone::Accent), "ok".dimmed())
+                                }
+                                crate::types::HealthLevel::Info => {
+                                    format!("{} {}", "△".tone(Tone::Minor), "minor".dimmed())
+                                }
+                                crate::types::HealthLevel::Warn => {
+                                    format!("{} {}", "!".tone(Tone::Warn), "drift".dimmed())
+                                }
+                                crate::types::HealthLevel::Fail => {
+                                    format!("{} {}", "✗".red(), "broken".dimmed())
+                                }
+                            };
+                            println!("{}     {}", "Health:".bold(), health_str);
+
+                            // Editable source packages
+                            let source: Vec<_> = packages
+                                .iter()
+                                .filter(|p| p.is_editable)
+                                .map(|p| p.name.clone())
+                                .collect();
+                            if !source.is_empty() {
+                                println!(
+                                    "{}     {}",
+                                    "Project:".bold(),
+                                    source.join(", ").tone(Tone::Accent)
+                                );
+                            }
+
+                            // Install groups (zen install --group)
+                            for (i, (group, members)) in
+                                db.list_groups(&name)?.into_iter().enumerate()
+                            {
+                                println!(
+                                    "{}     {} {}",
+                                    if i == 0 { "Groups:" } else { "       " }.bold(),
+                                    format!("{}:", group).tone(Tone::Minor),
+                                    members.join(", ")
+                                );
+                            }
+                        }
+                        if link {
+                            print_env_projects(&db, &name)?;
+                        }
+                        if let Some(template) = &compare_template {
+                            match template.clone().or(db.get_source_template(&name)?) {
+                                Some(template) => {
+                                    let diff = ops.compare_to_template(
+                                        &crate::types::EnvName::new(&name)?,
+                                        &template,
+                                    )?;
+                                    print_template_drift(&diff);
+                                }
+                                None => println!(
+                                    "{}   {}",
+                                    "Template:".bold(),
+                                    "none recorded (pass --compare-template <name:version>)"
+                                        .dimmed()
+                                ),
+                            }
                         }
+                    } else {
+                        eprintln!("{}", ops.env_not_found(&name));
                     }
-                } else {
-                    eprintln!("Environment '{}' not found.", name);
                 }
             }
-            Commands::Status => {
-                let envs = db.list_envs()?;
+            Commands::Status { json } => {
+                let env_count = db.count_envs()?;
                 let active = ops.infer_current_env()?;
+                // infer_current_env matched the registered path against $VIRTUAL_ENV
+                let active_path = active.as_ref().and(utils::get_current_venv_path());
+                let zen_home_note = if home_from_config {
+                    " (config)"
+                } else if home_is_default {
+                    " (default)"
+                } else {
+                    ""
+                };
+                let db_path = cli.db_path.clone().unwrap_or_else(db::default_db_path);
+                let zen_dojo_default = std::env::var("ZEN_DOJO").is_err();
+                // "recording" while a template session is open, otherwise "normal"
+                let mode = if db.get_active_session()?.is_some() {
+                    "recording"
+                } else {
+                    "normal"
+                };
+                let mcp_configs = home::home_dir()
+                    .map(|h| utils::find_mcp_client_configs(&h))
+                    .unwrap_or_default();
+
+                if json {
+                    #[derive(serde::Serialize)]
+                    struct StatusReport {
+                        version: String,
+                        active_env: Option<String>,
+                        active_path: Option<String>,
+                        managed_envs: usize,
+                        zen_home: String,
+                        db_path: String,
+                        mode: String,
+                        schema_version: i32,
+                        mcp_configs: Vec<String>,
+                    }
+
+                    let report = StatusReport {
+                        version: env!("ZEN_VERSION").to_string(),
+                        active_env: active,
+                        active_path,
+                        managed_envs: env_count as usize,
+                        zen_home: cli.home.display().to_string(),
+                        db_path: db_path.display().to_string(),
+                        mode: mode.to_string(),
+                        schema_version: db.schema_version()?,
+                        mcp_configs: mcp_configs
+                            .iter()
+                            .map(|p| p.display().to_string())
+                            .collect(),
+                    };
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                    return Ok(());
+                }
 
                 println!(
                     "\n{}",
-                    " Zen System Dashboard "
-                        .bold()
-                        .on_truecolor(100, 160, 160)
-                        .white()
+                    " Zen System Dashboard ".bold().on_tone(Tone::Muted).white()
                 );
-                println!("{}", "----------------------".truecolor(100, 160, 160));
+                println!("{}", "----------------------".tone(Tone::Muted));
 
                 if let Some(ref name) = active {
                     println!("  {: <20} {}", "Active Env:".bold(), name.green().bold());
                     // Show path
-                    if let Some((_, path, ..)) = envs.iter().find(|(n, ..)| n == name) {
+                    if let Some(ref path) = active_path {
                         println!("  {: <20} {}", "Active Path:".bold(), path.dimmed());
                     }
                 } else {
@@ -3295,25 +5742,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!(
                     "  {: <20} {}",
                     "Managed Envs:".bold(),
-                    envs.len().to_string().truecolor(100, 160, 160)
+                    env_count.to_string().tone(Tone::Muted)
                 );
-                let zen_home_default = std::env::var("ZEN_HOME").is_err();
+                if mode == "recording" {
+                    println!(
+                        "  {: <20} {}",
+                        "Mode:".bold(),
+                        "recording template".tone(Tone::Warn)
+                    );
+                }
                 println!(
                     "  {: <20} {}{}",
                     "Zen Home:".bold(),
                     cli.home.display().to_string().dimmed(),
-                    if zen_home_default {
-                        " (default)".dimmed().to_string()
-                    } else {
-                        String::new()
-                    }
+                    zen_home_note.dimmed()
                 );
-
-                let db_path = cli.db_path.clone().unwrap_or_else(|| {
-                    let home = home::home_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-                    home.join(".config").join("zen").join("zen.db")
-                });
-                let zen_dojo_default = std::env::var("ZEN_DOJO").is_err();
                 println!(
                     "  {: <20} {}{}",
                     "Zen Dojo:".bold(),
@@ -3324,61 +5767,109 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         String::new()
                     }
                 );
+                // Companion = MCP server registered with at least one AI client
+                if mcp_configs.is_empty() {
+                    println!(
+                        "  {: <20} {}",
+                        "Companion:".bold(),
+                        "not configured (see 'zen mcp')".dimmed()
+                    );
+                } else {
+                    println!(
+                        "  {: <20} {} {}",
+                        "Companion:".bold(),
+                        "configured".tone(Tone::Accent),
+                        format!("({} client config(s))", mcp_configs.len()).dimmed()
+                    );
+                }
                 println!();
             }
 
-            Commands::Export { file } => {
+            Commands::Export {
+                file,
+                templates_only,
+                envs_only,
+            } => {
                 #[derive(serde::Serialize)]
                 struct TemplateExport {
                     name: String,
                     version: String,
                     python_version: String,
-                    packages: Vec<(String, String, bool, String, Option<String>, i64)>, // includes install_args + step
+                    packages: Vec<crate::db::TemplatePackageRow>, // includes install_args, step, extras
                 }
 
+                // A section left out of a scoped export is omitted from the file
                 #[derive(serde::Serialize)]
                 struct FullRegistry {
-                    environments: Vec<(
-                        String, // name
-                        String, // path
-                        String, // python_version
-                        String, // updated_at
-                        bool,   // is_favorite
-                    )>,
-                    templates: Vec<TemplateExport>,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    environments: Option<
+                        Vec<(
+                            String,         // name
+                            String,         // path
+                            String,         // python_version
+                            String,         // updated_at
+                            bool,           // is_favorite
+                            Option<String>, // source_template
+                        )>,
+                    >,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    templates: Option<Vec<TemplateExport>>,
                 }
 
-                let envs = db.list_envs()?;
-                let tpls_data = db.get_all_templates_with_packages()?;
-                let templates_export = tpls_data
-                    .into_iter()
-                    .map(|(name, version, python_version, packages)| TemplateExport {
-                        name,
-                        version,
-                        python_version,
-                        packages,
-                    })
-                    .collect();
+                let environments = if templates_only {
+                    None
+                } else {
+                    let mut rows = Vec::new();
+                    for (name, path, python, updated_at, is_favorite) in db.list_envs()? {
+                        let source = db.get_source_template(&name)?;
+                        rows.push((name, path, python, updated_at, is_favorite, source));
+                    }
+                    Some(rows)
+                };
+                let templates = if envs_only {
+                    None
+                } else {
+                    let tpls_data = db.get_all_templates_with_packages()?;
+                    Some(
+                        tpls_data
+                            .into_iter()
+                            .map(|(name, version, python_version, packages)| TemplateExport {
+                                name,
+                                version,
+                                python_version,
+                                packages,
+                            })
+                            .collect(),
+                    )
+                };
 
                 let registry = FullRegistry {
-                    environments: envs,
-                    templates: templates_export,
+                    environments,
+                    templates,
                 };
 
                 let json = serde_json::to_string_pretty(&registry)?;
                 std::fs::write(file, json)?;
-                println!("Full registry (environments + templates) exported.");
+                let scope = if templates_only {
+                    "Templates"
+                } else if envs_only {
+                    "Environment registry"
+                } else {
+                    "Full registry (environments + templates)"
+                };
+                println!("{} exported.", scope);
             }
-            Commands::Import { file } => {
+            Commands::Import {
+                file,
+                merge: _,
+                overwrite,
+            } => {
+                // Scoped exports (--templates-only / --envs-only) omit a section
                 #[derive(serde::Deserialize)]
                 struct FullRegistry {
-                    environments: Vec<(
-                        String, // name
-                        String, // path
-                        String, // python_version
-                        String, // updated_at
-                        bool,   // is_favorite
-                    )>,
+                    #[serde(default)]
+                    environments: Vec<EnvRow>,
+                    #[serde(default)]
                     templates: Vec<TemplateExport>,
                 }
                 #[derive(serde::Deserialize)]
@@ -3386,39 +5877,143 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     name: String,
                     version: String,
                     python_version: String,
-                    packages: Vec<(String, String, bool, String, Option<String>, i64)>, // includes install_args + step
+                    packages: Vec<PackageRow>,
+                }
+                // Older exports have 5-element env rows (no source_template)
+                #[derive(serde::Deserialize)]
+                #[serde(untagged)]
+                enum EnvRow {
+                    // name, path, python_version, updated_at, is_favorite, source_template
+                    Current((String, String, String, String, bool, Option<String>)),
+                    Legacy((String, String, String, String, bool)),
+                }
+                // Older exports have 6-element rows (no extras) or 7 (no version_spec)
+                #[derive(serde::Deserialize)]
+                #[serde(untagged)]
+                enum PackageRow {
+                    Current(crate::db::TemplatePackageRow),
+                    WithExtras(
+                        (
+                            String,
+                            String,
+                            bool,
+                            String,
+                            Option<String>,
+                            i64,
+                            Option<String>,
+                        ),
+                    ),
+                    Legacy((String, String, bool, String, Option<String>, i64)),
                 }
 
                 let content = std::fs::read_to_string(file)?;
                 let registry: FullRegistry = serde_json::from_str(&content)?;
-
-                for (name, path, python, ..) in registry.environments {
+                // Envs: an existing name is kept unless --overwrite
+                let existing_envs: std::collections::HashMap<String, String> = db
+                    .list_envs()?
+                    .into_iter()
+                    .map(|(name, path, ..)| (name, path))
+                    .collect();
+                let mut env_count = 0;
+                let mut conflicts = Vec::new();
+                for row in registry.environments {
+                    let (name, path, python, source) = match row {
+                        EnvRow::Current((n, p, py, _, _, source)) => (n, p, py, source),
+                        EnvRow::Legacy((n, p, py, ..)) => (n, p, py, None),
+                    };
+                    if let Some(existing) = existing_envs.get(&name)
+                        && !overwrite
+                    {
+                        if existing != &path {
+                            conflicts.push(format!(
+                                "environment '{}': registered at {}, import has {}",
+                                name, existing, path
+                            ));
+                        }
+                        continue;
+                    }
                     db.register_env(&name, &path, &python)?;
+                    if let Some(source) = source {
+                        db.set_source_template(&name, &source)?;
+                    }
+                    env_count += 1;
                 }
 
+                // Templates: an identical name:version is a no-op; a differing
+                // one is kept unless --overwrite, which replaces it wholesale
+                let mut template_count = 0;
                 for t in registry.templates {
-                    let (t_id, _) = db.create_template(&t.name, &t.version, &t.python_version)?;
-                    for (p_name, p_ver, is_pinned, install_type, install_args, step) in t.packages {
-                        db.add_template_package(
-                            t_id,
-                            &p_name,
-                            &p_ver,
-                            is_pinned,
-                            &install_type,
-                            install_args.as_deref(),
-                            step,
-                        )?;
+                    let packages: Vec<crate::db::TemplatePackageRow> = t
+                        .packages
+                        .into_iter()
+                        .map(|row| match row {
+                            PackageRow::Current(row) => row,
+                            PackageRow::WithExtras((n, v, p, t, a, s, e)) => {
+                                (n, v, p, t, a, s, e, None)
+                            }
+                            PackageRow::Legacy((n, v, p, t, a, s)) => {
+                                (n, v, p, t, a, s, None, None)
+                            }
+                        })
+                        .collect();
+                    if let Some(existing_id) = db.get_template_id(&t.name, &t.version)? {
+                        let package_set = |rows: &[crate::db::TemplatePackageRow]| {
+                            let mut set: Vec<(String, String)> = rows
+                                .iter()
+                                .map(|r| (utils::normalize_package_name(&r.0), r.1.clone()))
+                                .collect();
+                            set.sort();
+                            set
+                        };
+                        let current = db.get_template_packages(existing_id)?;
+                        if package_set(&current) == package_set(&packages) {
+                            continue;
+                        }
+                        if !overwrite {
+                            conflicts.push(format!(
+                                "template '{}:{}': packages differ from the existing one",
+                                t.name, t.version
+                            ));
+                            continue;
+                        }
+                    }
+                    db.replace_template(&t.name, &t.version, &t.python_version, &packages)?;
+                    template_count += 1;
+                }
+                println!(
+                    "Imported {} environment(s) and {} template(s).",
+                    env_count, template_count
+                );
+                if !conflicts.is_empty() {
+                    println!(
+                        "{} Kept {} existing entr{} that differ from the import:",
+                        "!".tone(Tone::Warn),
+                        conflicts.len(),
+                        if conflicts.len() == 1 { "y" } else { "ies" }
+                    );
+                    for conflict in &conflicts {
+                        println!("  • {}", conflict);
                     }
+                    println!("  Re-run with {} to replace them.", "--overwrite".bold());
                 }
-                println!("Full registry (environments + templates) imported.");
             }
             Commands::Setup { subcommand } => match subcommand {
-                SetupCommands::Init { path, yes } => {
+                SetupCommands::Init {
+                    path,
+                    yes,
+                    recursive,
+                    depth,
+                } => {
+                    let max_depth = match depth {
+                        Some(n) => n,
+                        None if recursive => crate::utils::RECURSIVE_DISCOVERY_DEPTH,
+                        None => crate::utils::DEFAULT_DISCOVERY_DEPTH,
+                    };
                     println!(
                         "Zen Setup Wizard: Scanning {} for environments...",
                         path.display()
                     );
-                    let found = crate::utils::discover_venvs(&path);
+                    let found = crate::utils::discover_venvs(&path, max_depth);
 
                     if found.is_empty() {
                         println!("No virtual environments found in this directory.");
@@ -3442,7 +6037,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
                         if confirm {
                             println!("Importing... (this will scan packages for each env)");
-                            match ops.bulk_import(found) {
+                            let spinner = crate::printer::Spinner::start(&format!(
+                                "Importing {} environments...",
+                                found.len()
+                            ));
+                            let imported = ops.bulk_import(found);
+                            spinner.finish();
+                            match imported {
                                 Ok(msg) => println!("\n✓ {}", msg),
                                 Err(e) => eprintln!("\nError: {}", e),
                             }
@@ -3465,15 +6066,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             },
             Commands::Note { subcommand } | Commands::Comment { subcommand } => match subcommand {
-                NoteCommands::Add { env, message } => {
+                NoteCommands::Add { env, message, tag } => {
                     let env = resolve_env_name(env, &db)?;
                     let env_name = types::EnvName::new(&env).map_err(|e| e.to_string())?;
-                    match ops.log_comment(Some(&env_name), &message) {
+                    if let Some(ref t) = tag {
+                        crate::validation::validate_name(t, "Tag")?;
+                    }
+                    match ops.log_comment(Some(&env_name), &message, tag.as_deref()) {
                         Ok(resp) => println!("{}", resp),
                         Err(e) => eprintln!("Error: {}", e),
                     }
                 }
-                NoteCommands::List { env, all } => {
+                NoteCommands::List { env, all, tag } => {
                     let (env_filter, show_env_col) = if all {
                         (None, true)
                     } else {
@@ -3483,50 +6087,32 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             false,
                         )
                     };
-                    match ops.list_comments(None, env_filter.as_ref()) {
+                    match ops.list_comments(None, env_filter.as_ref(), tag.as_deref()) {
                         Ok(comments) => {
                             if comments.is_empty() {
-                                if show_env_col {
-                                    println!("No notes found.");
-                                } else {
-                                    println!("No notes for '{}'", env_filter.unwrap());
-                                }
-                            } else {
-                                use comfy_table::{Cell, Color};
-                                let mut table = crate::table::new_table();
-                                if show_env_col {
-                                    table.set_header(vec!["UUID", "Env", "Note", "Timestamp"]);
-                                } else {
-                                    table.set_header(vec!["UUID", "Note", "Timestamp"]);
-                                }
-                                for (uuid, _pp, env_name, msg, _tag, ts) in comments {
-                                    let short_uuid = if uuid.len() > 8 {
-                                        format!("{}…", &uuid[..8])
-                                    } else {
-                                        uuid.clone()
-                                    };
-                                    if show_env_col {
-                                        table.add_row(vec![
-                                            Cell::new(short_uuid).fg(Color::DarkGrey),
-                                            Cell::new(env_name.unwrap_or_else(|| "-".into()))
-                                                .fg(Color::Cyan),
-                                            Cell::new(msg),
-                                            Cell::new(ts).fg(Color::DarkGrey),
-                                        ]);
-                                    } else {
-                                        table.add_row(vec![
-                                            Cell::new(short_uuid).fg(Color::DarkGrey),
-                                            Cell::new(msg),
-                                            Cell::new(ts).fg(Color::DarkGrey),
-                                        ]);
+                                let scope = match (&env_filter, &tag) {
+                                    (Some(env), Some(t)) => {
+                                        format!(" tagged '{}' for '{}'", t, env)
                                     }
-                                }
-                                println!("{}", table);
+                                    (Some(env), None) => format!(" for '{}'", env),
+                                    (None, Some(t)) => format!(" tagged '{}'", t),
+                                    (None, None) => " found".to_string(),
+                                };
+                                println!("No notes{}.", scope);
+                            } else {
+                                print_notes_table(comments, show_env_col);
                             }
                         }
                         Err(e) => eprintln!("Error: {}", e),
                     }
                 }
+                NoteCommands::Search { term } => match ops.search_comments(&term) {
+                    Ok(comments) if comments.is_empty() => {
+                        println!("No notes matching '{}'.", term)
+                    }
+                    Ok(comments) => print_notes_table(comments, true),
+                    Err(e) => eprintln!("Error: {}", e),
+                },
                 NoteCommands::Rm { uuid } => match ops.remove_comment(&uuid) {
                     Ok(0) => eprintln!("{} No note found matching '{}'", "✗".red(), uuid),
                     Ok(1) => println!("{} Note {} removed.", "✓".green(), uuid),
@@ -3535,53 +6121,299 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 },
             },
 
+            Commands::Favorite { name } => {
+                let name = resolve_env_name(name, &db)?;
+                if !db.set_favorite(&name, true)? {
+                    eprintln!("{} Environment '{}' not found.", "✗".red(), name);
+                    std::process::exit(1);
+                }
+                println!("{} Starred '{}'.", "★".tone(Tone::Fav), name);
+            }
+            Commands::Unfavorite { name } => {
+                let name = resolve_env_name(name, &db)?;
+                if !db.set_favorite(&name, false)? {
+                    eprintln!("{} Environment '{}' not found.", "✗".red(), name);
+                    std::process::exit(1);
+                }
+                println!("{} Unstarred '{}'.", "✓".green(), name);
+            }
             Commands::Label { subcommand } => match subcommand {
                 LabelCommands::Add { env, label } => {
+                    for env in resolve_env_names(env, &db)? {
+                        match db.add_label(&env, &label) {
+                            Ok(_) => {
+                                println!("{} Added label '{}' to '{}'", "✓".green(), label, env)
+                            }
+                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                        }
+                    }
+                }
+                LabelCommands::Rm { env, label } => {
+                    for env in resolve_env_names(env, &db)? {
+                        match db.remove_label(&env, &label) {
+                            Ok(_) => {
+                                println!("{} Removed label '{}' from '{}'", "✓".green(), label, env)
+                            }
+                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                        }
+                    }
+                }
+                LabelCommands::List { env, all } => {
+                    if all {
+                        match db.get_all_labels() {
+                            Ok(entries) => {
+                                if entries.is_empty() {
+                                    println!("No labels found.");
+                                } else {
+                                    for (env, labels) in entries {
+                                        println!("{}: {}", env, labels.join(", "));
+                                    }
+                                }
+                            }
+                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                        }
+                    } else {
+                        let env = resolve_env_name(env, &db)?;
+                        match db.get_labels(&env) {
+                            Ok(labels) => {
+                                if labels.is_empty() {
+                                    println!("No labels for '{}'", env);
+                                } else {
+                                    println!("{}: {}", env, labels.join(", "));
+                                }
+                            }
+                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                        }
+                    }
+                }
+            },
+            Commands::Alias { subcommand } => match subcommand {
+                AliasCommands::Add { alias, env } => {
+                    crate::validation::validate_name(&alias, "Alias")?;
+                    let env = expand_alias(env, &db)?;
+                    if db.get_env_id(&env)?.is_none() {
+                        eprintln!("{} {}", "Error:".red(), ops.env_not_found(&env));
+                        std::process::exit(1);
+                    }
+                    if let Err(e) = db.add_alias(&alias, &env) {
+                        eprintln!("{} {}", "Error:".red(), e);
+                        std::process::exit(1);
+                    }
+                    activity_log::log_activity(
+                        "cli",
+                        "alias:add",
+                        &format!("{} -> {}", alias, env),
+                    );
+                    println!("{} Alias '{}' → '{}'", "✓".green(), alias.bold(), env);
+                }
+                AliasCommands::Rm { alias } => {
+                    if db.remove_alias(&alias)? {
+                        activity_log::log_activity("cli", "alias:rm", &alias);
+                        println!("{} Removed alias '{}'", "✓".green(), alias);
+                    } else {
+                        eprintln!("{} No alias named '{}'.", "Error:".red(), alias);
+                        std::process::exit(1);
+                    }
+                }
+                AliasCommands::List => {
+                    let aliases = db.list_aliases()?;
+                    if aliases.is_empty() {
+                        println!("No aliases defined.");
+                    } else {
+                        let width = aliases.iter().map(|(a, _)| a.len()).max().unwrap_or(0);
+                        for (alias, env) in aliases {
+                            println!(
+                                "  {}  {} {}",
+                                format!("{:<width$}", alias).bold(),
+                                "→".dimmed(),
+                                env.tone(Tone::Accent)
+                            );
+                        }
+                    }
+                }
+            },
+            Commands::Snapshot { subcommand } => match subcommand {
+                SnapshotCommands::Create { env, label } => {
+                    let env = types::EnvName::new(&expand_alias(env, &db)?)?;
+                    let label = label.unwrap_or_else(|| {
+                        chrono::Local::now().format("%Y%m%d-%H%M%S").to_string()
+                    });
+                    crate::validation::validate_name(&label, "Snapshot")?;
+                    match ops.create_snapshot(&env, &label) {
+                        Ok(count) => {
+                            activity_log::log_activity(
+                                "cli",
+                                "snapshot:create",
+                                &format!("{} {} ({} packages)", env, label, count),
+                            );
+                            println!(
+                                "{} Snapshot '{}' of '{}' saved ({} package(s)).",
+                                "✓".green(),
+                                label.bold(),
+                                env,
+                                count
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                SnapshotCommands::List { env } => {
+                    let env = expand_alias(env, &db)?;
+                    if db.get_env_id(&env)?.is_none() {
+                        eprintln!("{} {}", "Error:".red(), ops.env_not_found(&env));
+                        std::process::exit(1);
+                    }
+                    let snapshots = db.list_snapshots(&env)?;
+                    if snapshots.is_empty() {
+                        println!("No snapshots for '{}'.", env);
+                    } else {
+                        let width = snapshots.iter().map(|(l, ..)| l.len()).max().unwrap_or(0);
+                        for (label, created_at, count) in snapshots {
+                            println!(
+                                "  {}  {}  {}",
+                                format!("{:<width$}", label).bold(),
+                                created_at.dimmed(),
+                                format!("{} package(s)", count).dimmed()
+                            );
+                        }
+                    }
+                }
+                SnapshotCommands::Restore {
+                    env,
+                    label,
+                    yes,
+                    dry_run,
+                } => {
+                    let env = types::EnvName::new(&expand_alias(env, &db)?)?;
+                    let plan = match ops.plan_snapshot_restore(&env, &label) {
+                        Ok(plan) => plan,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    };
+                    if plan.is_empty() {
+                        println!("'{}' already matches snapshot '{}'.", env, label);
+                        return Ok(());
+                    }
+                    println!("Restoring '{}' to snapshot '{}':", env, label.bold());
+                    for req in &plan.install {
+                        println!("  {} {}", "+".green(), req);
+                    }
+                    for path in &plan.install_editable {
+                        println!("  {} -e {}", "+".green(), path);
+                    }
+                    for name in &plan.remove {
+                        println!("  {} {}", "-".red(), name);
+                    }
+                    if dry_run {
+                        println!("{}", "Dry run — nothing was changed.".dimmed());
+                        return Ok(());
+                    }
+                    if !yes {
+                        use dialoguer::{Confirm, theme::ColorfulTheme};
+                        let confirmed = match Confirm::with_theme(&ColorfulTheme::default())
+                            .with_prompt("Apply these changes?")
+                            .default(false)
+                            .interact()
+                        {
+                            Ok(v) => v,
+                            Err(_) => {
+                                println!();
+                                return Ok(());
+                            }
+                        };
+                        if !confirmed {
+                            println!("Abort.");
+                            return Ok(());
+                        }
+                    }
+                    match ops.restore_snapshot(&env, &plan) {
+                        Ok(()) => {
+                            activity_log::log_activity(
+                                "cli",
+                                "snapshot:restore",
+                                &format!("{} {}", env, label),
+                            );
+                            println!("{} Restored '{}' to '{}'.", "✓".green(), env, label);
+                        }
+                        Err(e) => {
+                            activity_log::log_activity(
+                                "cli",
+                                "snapshot:restore:error",
+                                &format!("{} {} - {}", env, label, e),
+                            );
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+            },
+            Commands::Env { subcommand } => match subcommand {
+                EnvVarCommands::Set { key, value, env } => {
+                    crate::validation::validate_env_var_key(&key)?;
                     let env = resolve_env_name(env, &db)?;
-                    match db.add_label(&env, &label) {
-                        Ok(_) => println!("{} Added label '{}' to '{}'", "✓".green(), label, env),
+                    match db.set_env_var(&env, &key, &value) {
+                        Ok(_) => {
+                            activity_log::log_activity(
+                                "cli",
+                                "env:set",
+                                &format!("{} {}", env, key),
+                            );
+                            println!("{} Set {} for '{}'", "✓".green(), key, env)
+                        }
                         Err(e) => eprintln!("{} {}", "Error:".red(), e),
                     }
                 }
-                LabelCommands::Rm { env, label } => {
+                EnvVarCommands::Get { key, env } => {
                     let env = resolve_env_name(env, &db)?;
-                    match db.remove_label(&env, &label) {
-                        Ok(_) => {
-                            println!("{} Removed label '{}' from '{}'", "✓".green(), label, env)
+                    match db.get_env_var(&env, &key)? {
+                        Some(value) => println!("{}", value),
+                        None => {
+                            eprintln!("{} {} is not set for '{}'", "✗".red(), key, env);
+                            std::process::exit(1);
                         }
-                        Err(e) => eprintln!("{} {}", "Error:".red(), e),
                     }
                 }
-                LabelCommands::List { env, all } => {
-                    if all {
-                        match db.get_all_labels() {
-                            Ok(entries) => {
-                                if entries.is_empty() {
-                                    println!("No labels found.");
-                                } else {
-                                    for (env, labels) in entries {
-                                        println!("{}: {}", env, labels.join(", "));
-                                    }
-                                }
-                            }
-                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
-                        }
+                EnvVarCommands::Unset { key, env } => {
+                    let env = resolve_env_name(env, &db)?;
+                    if db.unset_env_var(&env, &key)? {
+                        activity_log::log_activity("cli", "env:unset", &format!("{} {}", env, key));
+                        println!("{} Unset {} for '{}'", "✓".green(), key, env);
                     } else {
-                        let env = resolve_env_name(env, &db)?;
-                        match db.get_labels(&env) {
-                            Ok(labels) => {
-                                if labels.is_empty() {
-                                    println!("No labels for '{}'", env);
-                                } else {
-                                    println!("{}: {}", env, labels.join(", "));
-                                }
-                            }
-                            Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                        eprintln!("{} {} is not set for '{}'", "✗".red(), key, env);
+                    }
+                }
+                EnvVarCommands::List { env } => {
+                    let env = resolve_env_name(env, &db)?;
+                    let vars = db.list_env_vars(&env)?;
+                    if vars.is_empty() {
+                        println!("No variables for '{}'", env);
+                    } else {
+                        for (key, value) in vars {
+                            println!("{}={}", key.bold(), value);
                         }
                     }
                 }
+                EnvVarCommands::Export { env, shell } => {
+                    let env = resolve_env_name(env, &db)?;
+                    print!(
+                        "{}",
+                        hooks::export_env_vars(&shell, &db.list_env_vars(&env)?)
+                    );
+                }
             },
-            Commands::Find { package, exact } => {
+            Commands::Find {
+                package,
+                exact,
+                live,
+                count,
+                by_env,
+                output: OutputArgs { output },
+            } => {
                 // Split query into name and optional version at "=="
                 let (pkg_query, version_query) = if package.contains("==") {
                     let parts: Vec<&str> = package.split("==").collect();
@@ -3594,49 +6426,108 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 };
 
                 let pattern = pkg_query.replace('*', "");
-                // pip treats hyphens and underscores as equivalent
-                let normalize = |s: &str| s.to_lowercase().replace('-', "_");
 
-                let envs = db.list_envs()?;
-                let mut found = Vec::new();
-
-                for (name, path, ..) in &envs {
-                    let packages = crate::utils::get_packages(path);
-                    for pkg in packages {
-                        let pkg_norm = normalize(&pkg.name);
-                        let pattern_norm = normalize(&pattern);
-
-                        // Default: substring match. --exact: exact name match
-                        let name_match = if exact {
-                            pkg_norm == pattern_norm
-                        } else {
-                            pkg_norm.contains(&pattern_norm)
-                        };
-
-                        // Version match with CUDA-awareness
-                        let version_match = match (&version_query, &pkg.version) {
-                            (Some(q), Some(v)) => {
-                                if q.contains('+') {
-                                    // Query has +cuXXX: exact match
-                                    v == q
-                                } else {
-                                    // Query without +: match base version (before +)
-                                    let base_ver = v.split('+').next().unwrap_or(v);
-                                    base_ver.starts_with(q.as_str())
-                                }
+                // Name matches: indexed query by default, full disk scan with --live
+                let name_matches: Vec<(String, String, Option<String>)> = if live {
+                    // pip treats hyphens and underscores as equivalent
+                    let normalize = |s: &str| s.to_lowercase().replace('-', "_");
+                    let pattern_norm = normalize(&pattern);
+                    let mut matches = Vec::new();
+                    // The scan refreshes the index too, when the DB is writable
+                    let index_db = db.writable().ok();
+                    for (name, path, ..) in &db.list_envs()? {
+                        let packages = crate::utils::get_packages(path);
+                        if let Some(ref index_db) = index_db {
+                            index_db.index_packages(
+                                name,
+                                utils::site_packages_mtime(path),
+                                &packages,
+                            )?;
+                        }
+                        for pkg in packages {
+                            let pkg_norm = normalize(&pkg.name);
+                            // Default: substring match. --exact: exact name match
+                            let name_match = if exact {
+                                pkg_norm == pattern_norm
+                            } else {
+                                pkg_norm.contains(&pattern_norm)
+                            };
+                            if name_match {
+                                matches.push((name.clone(), pkg.name, pkg.version));
                             }
-                            (Some(_), None) => false,
-                            (None, _) => true,
-                        };
-
-                        if name_match && version_match {
-                            found.push((name.clone(), pkg.name.clone(), pkg.version.clone()));
                         }
                     }
-                }
+                    matches
+                } else {
+                    ops.refresh_package_index(false)?;
+                    db.search_packages(&pattern, exact)?
+                };
+
+                // Version match with CUDA-awareness
+                let found: Vec<_> = name_matches
+                    .into_iter()
+                    .filter(|(_, _, version)| match (&version_query, version) {
+                        (Some(q), Some(v)) => {
+                            if q.contains('+') {
+                                // Query has +cuXXX: exact match
+                                v == q
+                            } else {
+                                // Query without +: match base version (before +)
+                                let base_ver = v.split('+').next().unwrap_or(v);
+                                base_ver.starts_with(q.as_str())
+                            }
+                        }
+                        (Some(_), None) => false,
+                        (None, _) => true,
+                    })
+                    .collect();
 
-                if found.is_empty() {
+                let rows: Vec<Vec<serde_json::Value>> = found
+                    .iter()
+                    .map(|(env, pkg_name, version)| {
+                        vec![
+                            env.clone().into(),
+                            pkg_name.clone().into(),
+                            version.clone().into(),
+                        ]
+                    })
+                    .collect();
+                if let Some(out) =
+                    crate::printer::render_records(output, &["env", "package", "version"], &rows)
+                {
+                    print!("{}", out);
+                } else if count {
+                    let mut envs: Vec<&String> = found.iter().map(|(env, ..)| env).collect();
+                    envs.sort();
+                    envs.dedup();
+                    println!(
+                        "{} environment{} contain{} '{}'",
+                        envs.len(),
+                        if envs.len() == 1 { "" } else { "s" },
+                        if envs.len() == 1 { "s" } else { "" },
+                        package
+                    );
+                } else if found.is_empty() {
                     println!("No environments contain package matching '{}'", package);
+                } else if by_env {
+                    let mut groups: Vec<(String, Vec<(String, Option<String>)>)> = Vec::new();
+                    for (env, pkg_name, version) in found {
+                        match groups.iter_mut().find(|(e, _)| *e == env) {
+                            Some((_, pkgs)) => pkgs.push((pkg_name, version)),
+                            None => groups.push((env, vec![(pkg_name, version)])),
+                        }
+                    }
+                    for (env, pkgs) in groups {
+                        println!(
+                            "{} {}",
+                            env.cyan().bold(),
+                            format!("({})", pkgs.len()).dimmed()
+                        );
+                        for (pkg_name, version) in pkgs {
+                            let ver = version.unwrap_or_else(|| "?".to_string());
+                            println!("  {} {} {}", pkg_name, "→".dimmed(), ver.green());
+                        }
+                    }
                 } else {
                     println!("{}", "Package matches:".bold());
                     for (env, pkg_name, version) in found {
@@ -3656,14 +6547,113 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 package,
                 names_only,
                 long,
+                tree,
+                why,
+                files,
+                output: OutputArgs { output },
             } => {
+                if output != OutputFormat::Table && (tree || why.is_some()) {
+                    eprintln!(
+                        "{} --output only applies to package listings, not --tree or --why.",
+                        "Error:".red()
+                    );
+                    std::process::exit(2);
+                }
                 let env = resolve_env_name(env, &db)?;
                 let envs = db.list_envs()?;
                 let env_entry = envs.iter().find(|(n, ..)| n == &env);
-                if let Some((name, path, ..)) = env_entry {
+                if let Some((name, path, ..)) = env_entry
+                    && let Some(target) = why
+                {
+                    const MAX_CHAINS: usize = 20;
+                    let graph = utils::dependency_graph(path);
+                    let Some(node) = graph.get(&utils::normalize_package_name(&target)) else {
+                        eprintln!("Package '{}' not found in environment '{}'", target, name);
+                        std::process::exit(1);
+                    };
+                    let chains = utils::why_chains(&graph, &target, MAX_CHAINS + 1);
+                    if chains.is_empty() {
+                        println!(
+                            "{} {} is not required by any installed package (installed directly).",
+                            node.name.tone(Tone::Accent),
+                            node.version.dimmed()
+                        );
+                    }
+                    for chain in chains.iter().take(MAX_CHAINS) {
+                        let names: Vec<&str> = chain
+                            .iter()
+                            .map(|k| graph.get(k).map(|n| n.name.as_str()).unwrap_or(k))
+                            .collect();
+                        println!("{}", names.join(" ← "));
+                    }
+                    if chains.len() > MAX_CHAINS {
+                        println!(
+                            "{}",
+                            format!("(showing the first {} chains)", MAX_CHAINS).dimmed()
+                        );
+                    }
+                } else if let Some((_, path, ..)) = env_entry
+                    && tree
+                {
+                    for line in utils::dependency_tree_lines(&utils::dependency_graph(path)) {
+                        println!("{}", line);
+                    }
+                } else if let Some((name, path, ..)) = env_entry {
                     let packages = crate::utils::get_packages(path);
 
-                    if let Some(package) = package {
+                    if output != OutputFormat::Table {
+                        let mut selected: Vec<_> = match &package {
+                            Some(package) => packages
+                                .into_iter()
+                                .filter(|p| p.name.eq_ignore_ascii_case(package))
+                                .collect(),
+                            None => packages,
+                        };
+                        if selected.is_empty()
+                            && let Some(package) = &package
+                        {
+                            eprintln!("Package '{}' not found in environment '{}'", package, name);
+                            std::process::exit(1);
+                        }
+                        selected.sort_by_key(|p| p.name.to_lowercase());
+                        let rows: Vec<Vec<serde_json::Value>> = selected
+                            .into_iter()
+                            .map(|p| {
+                                let installed = p.installed_at.and_then(|epoch| {
+                                    use chrono::{Local, TimeZone};
+                                    Local
+                                        .timestamp_opt(epoch, 0)
+                                        .single()
+                                        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+                                });
+                                vec![
+                                    p.name.into(),
+                                    p.version.into(),
+                                    p.installer.into(),
+                                    p.install_source.into(),
+                                    p.is_editable.into(),
+                                    p.source_url.into(),
+                                    p.commit_id.into(),
+                                    p.import_names.into(),
+                                    installed.into(),
+                                ]
+                            })
+                            .collect();
+                        let headers = [
+                            "name",
+                            "version",
+                            "installer",
+                            "source",
+                            "editable",
+                            "url",
+                            "commit",
+                            "import",
+                            "installed",
+                        ];
+                        if let Some(out) = crate::printer::render_records(output, &headers, &rows) {
+                            print!("{}", out);
+                        }
+                    } else if let Some(package) = package {
                         // Single package detail view
                         let pkg_lower = package.to_lowercase();
                         let found = packages
@@ -3683,11 +6673,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             } else {
                                 source_str.cyan().to_string()
                             };
-                            println!(
-                                "{:12}{}",
-                                "Package:".bold(),
-                                pkg.name.truecolor(100, 200, 255)
-                            );
+                            println!("{:12}{}", "Package:".bold(), pkg.name.tone(Tone::Accent));
                             println!("{:12}{}", "Version:".bold(), colored_ver);
                             println!(
                                 "{:12}{}",
@@ -3699,7 +6685,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 "{:12}{}",
                                 "Editable:".bold(),
                                 if pkg.is_editable {
-                                    "yes".truecolor(100, 200, 255).to_string()
+                                    "yes".tone(Tone::Accent).to_string()
                                 } else {
                                     "no".dimmed().to_string()
                                 }
@@ -3710,11 +6696,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             if let Some(commit) = &pkg.commit_id {
                                 println!("{:12}{}", "Commit:".bold(), commit.dimmed());
                             }
-                            if let Some(ref import) = pkg.import_name {
+                            if !pkg.import_names.is_empty() {
                                 println!(
                                     "{:12}{}",
                                     "Import:".bold(),
-                                    import.truecolor(100, 200, 255)
+                                    pkg.import_names.join(", ").tone(Tone::Accent)
                                 );
                             }
                             if let Some(epoch) = pkg.installed_at {
@@ -3727,13 +6713,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     );
                                 }
                             }
+                            if files {
+                                let record =
+                                    utils::find_dist_info(path, &pkg.name).and_then(|dir| {
+                                        std::fs::read_to_string(dir.join("RECORD")).ok()
+                                    });
+                                match record {
+                                    Some(content) => {
+                                        let paths = utils::parse_record_paths(&content);
+                                        println!("{:12}{}", "Files:".bold(), paths.len());
+                                        for file in paths {
+                                            println!("  {}", file);
+                                        }
+                                    }
+                                    None => println!(
+                                        "{:12}{}",
+                                        "Files:".bold(),
+                                        "no RECORD (legacy install)".dimmed()
+                                    ),
+                                }
+                            }
                         } else {
                             eprintln!("Package '{}' not found in environment '{}'", package, name);
                         }
                     } else {
                         // List all packages
                         let mut sorted = packages;
-                        sorted.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+                        sorted.sort_by_key(|a| a.name.to_lowercase());
 
                         if names_only {
                             // -1: one name per line
@@ -3744,8 +6750,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             // -l: long format, aligned name + version + installer + date
                             println!(
                                 "{} {} — {} package(s)",
-                                "●".truecolor(100, 200, 255),
-                                name.truecolor(100, 200, 255).bold(),
+                                "●".tone(Tone::Accent),
+                                name.tone(Tone::Accent).bold(),
                                 sorted.len()
                             );
                             println!();
@@ -3776,7 +6782,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 };
                                 println!(
                                     "  {:<nw$}  {:<vw$}  {:<3}  {}{}",
-                                    pkg.name.truecolor(100, 200, 255),
+                                    pkg.name.tone(Tone::Accent),
                                     colored_ver,
                                     installer.dimmed(),
                                     date_str.dimmed(),
@@ -3789,8 +6795,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             // Default: ls-style column layout
                             println!(
                                 "{} {} — {} package(s)",
-                                "●".truecolor(100, 200, 255),
-                                name.truecolor(100, 200, 255).bold(),
+                                "●".tone(Tone::Accent),
+                                name.tone(Tone::Accent).bold(),
                                 sorted.len()
                             );
                             println!();
@@ -3806,7 +6812,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     let plain = format!("{} ({})", pkg.name, ver);
                                     let colored = format!(
                                         "{} {}{}{}",
-                                        pkg.name.truecolor(100, 200, 255),
+                                        pkg.name.tone(Tone::Accent),
                                         "(".dimmed(),
                                         if ver.contains("+cu") {
                                             ver.green().to_string()
@@ -3848,38 +6854,75 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
             Commands::Diff {
-                env1,
-                env2,
+                envs: names,
                 only_diff,
+                output: OutputArgs { output },
             } => {
-                // Compare packages between two environments
+                let names = names
+                    .into_iter()
+                    .map(|n| expand_alias(n, &db))
+                    .collect::<Result<Vec<_>, _>>()?;
                 let envs = db.list_envs()?;
-                let path1 = envs
-                    .iter()
-                    .find(|(n, ..)| n == &env1)
-                    .map(|(_, p, ..)| p.clone());
-                let path2 = envs
-                    .iter()
-                    .find(|(n, ..)| n == &env2)
-                    .map(|(_, p, ..)| p.clone());
-
-                let (path1, path2) = match (path1, path2) {
-                    (Some(p1), Some(p2)) => (p1, p2),
-                    (None, _) => {
-                        eprintln!("{} Environment '{}' not found", "Error:".red(), env1);
-                        return Ok(());
+                let mut paths = Vec::new();
+                for name in &names {
+                    match envs.iter().find(|(n, ..)| n == name) {
+                        Some((_, p, ..)) => paths.push(p.clone()),
+                        None => {
+                            eprintln!("{} {}", "Error:".red(), ops.env_not_found(name));
+                            return Ok(());
+                        }
                     }
-                    (_, None) => {
-                        eprintln!("{} Environment '{}' not found", "Error:".red(), env2);
-                        return Ok(());
+                }
+                if output != OutputFormat::Table {
+                    // One row per package, a version column per environment
+                    let versions: Vec<std::collections::HashMap<String, Option<String>>> = paths
+                        .iter()
+                        .map(|p| {
+                            crate::utils::get_packages(p)
+                                .into_iter()
+                                .map(|pkg| (pkg.name, pkg.version))
+                                .collect()
+                        })
+                        .collect();
+                    let mut all_pkgs: Vec<&String> =
+                        versions.iter().flat_map(|v| v.keys()).collect();
+                    all_pkgs.sort();
+                    all_pkgs.dedup();
+                    let mut rows = Vec::new();
+                    for pkg in all_pkgs {
+                        let row: Vec<Option<&Option<String>>> =
+                            versions.iter().map(|v| v.get(pkg)).collect();
+                        if only_diff && row.iter().all(|v| *v == row[0]) {
+                            continue;
+                        }
+                        let mut values: Vec<serde_json::Value> = vec![pkg.clone().into()];
+                        values
+                            .extend(row.into_iter().map(|v| {
+                                v.map(|v| v.clone().unwrap_or_else(|| "?".into())).into()
+                            }));
+                        rows.push(values);
                     }
-                };
+                    let mut headers = vec!["package"];
+                    headers.extend(names.iter().map(String::as_str));
+                    if let Some(out) = crate::printer::render_records(output, &headers, &rows) {
+                        print!("{}", out);
+                    }
+                    return Ok(());
+                }
+                if names.len() > 2 {
+                    print_diff_matrix(&names, &paths, only_diff);
+                    return Ok(());
+                }
 
-                let pkgs1: std::collections::HashMap<_, _> = crate::utils::get_packages(&path1)
+                // Two environments: colored side-by-side table
+                let (env1, env2) = (&names[0], &names[1]);
+                let (path1, path2) = (&paths[0], &paths[1]);
+
+                let pkgs1: std::collections::HashMap<_, _> = crate::utils::get_packages(path1)
                     .into_iter()
                     .map(|p| (p.name, p.version))
                     .collect();
-                let pkgs2: std::collections::HashMap<_, _> = crate::utils::get_packages(&path2)
+                let pkgs2: std::collections::HashMap<_, _> = crate::utils::get_packages(path2)
                     .into_iter()
                     .map(|p| (p.name, p.version))
                     .collect();
@@ -3889,93 +6932,222 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 all_pkgs.dedup();
 
                 println!(
-                    "{:^30} {:^15} {:^15}",
+                    "  {:^30} {:^15} {:^15}",
                     "Package".bold(),
                     env1.cyan(),
                     env2.cyan()
                 );
-                println!("{}", "─".repeat(60));
+                println!("{}", "─".repeat(62));
 
+                // Row markers: `~` changed, `-` only in env1, `+` only in env2
+                let (mut changed, mut only1, mut only2, mut same) = (0, 0, 0, 0);
                 for pkg in all_pkgs {
-                    let v1 = pkgs1.get(pkg).and_then(|v| v.clone());
-                    let v2 = pkgs2.get(pkg).and_then(|v| v.clone());
-                    let is_diff = v1 != v2;
+                    let marker = match (pkgs1.get(pkg), pkgs2.get(pkg)) {
+                        (Some(a), Some(b)) if a == b => {
+                            same += 1;
+                            ' '
+                        }
+                        (Some(_), Some(_)) => {
+                            changed += 1;
+                            '~'
+                        }
+                        (Some(_), None) => {
+                            only1 += 1;
+                            '-'
+                        }
+                        _ => {
+                            only2 += 1;
+                            '+'
+                        }
+                    };
+                    let is_diff = marker != ' ';
 
                     if only_diff && !is_diff {
                         continue;
                     }
 
-                    let v1_str = v1.unwrap_or_else(|| "--".to_string());
-                    let v2_str = v2.unwrap_or_else(|| "--".to_string());
+                    let v1_str = pkgs1
+                        .get(pkg)
+                        .map(|v| v.clone().unwrap_or_else(|| "?".to_string()))
+                        .unwrap_or_else(|| "--".to_string());
+                    let v2_str = pkgs2
+                        .get(pkg)
+                        .map(|v| v.clone().unwrap_or_else(|| "?".to_string()))
+                        .unwrap_or_else(|| "--".to_string());
 
                     if is_diff {
                         println!(
-                            "{:30} {:^15} {:^15}",
+                            "{} {:30} {:^15} {:^15}",
+                            marker.to_string().bold(),
                             pkg.yellow(),
                             v1_str.red(),
                             v2_str.green()
                         );
                     } else {
-                        println!("{:30} {:^15} {:^15}", pkg, v1_str, v2_str);
+                        println!("  {:30} {:^15} {:^15}", pkg, v1_str, v2_str);
                     }
                 }
+
+                println!("{}", "─".repeat(62));
+                println!(
+                    "{} changed, {} only in {}, {} only in {}, {} shared identical",
+                    changed, only1, env1, only2, env2, same
+                );
             }
-            Commands::Health { name } => {
-                let name = resolve_env_name(name, &db)?;
-                let env_name = types::EnvName::new(&name).map_err(|e| e.to_string())?;
-                match ops.check_health(&env_name) {
-                    Ok(report) => {
-                        use crate::types::Diagnostic;
-                        println!(
-                            "{}  {}",
-                            "Environment:".bold(),
-                            name.truecolor(100, 200, 255)
-                        );
-                        let label = " Health ";
-                        let total_w: usize = 50;
-                        let pad = total_w.saturating_sub(label.len()) / 2;
-                        println!(
-                            "{}{}{}",
-                            "─".repeat(pad),
-                            label.dimmed(),
-                            "─".repeat(total_w - pad - label.len())
-                        );
-                        for item in &report.items {
-                            let (icon, color_msg) = match item.level() {
-                                crate::types::HealthLevel::Pass => (
-                                    "✓".truecolor(100, 200, 255).to_string(),
-                                    item.message().normal().to_string(),
-                                ),
-                                crate::types::HealthLevel::Info => (
-                                    "△".truecolor(255, 182, 193).to_string(),
-                                    item.message().truecolor(255, 182, 193).to_string(),
-                                ),
-                                crate::types::HealthLevel::Warn => (
-                                    "⚠".truecolor(255, 140, 0).to_string(),
-                                    item.message().truecolor(255, 140, 0).to_string(),
-                                ),
-                                crate::types::HealthLevel::Fail => {
-                                    ("✗".red().to_string(), item.message().red().to_string())
-                                }
-                            };
-                            println!("{} {}", icon, color_msg);
+            Commands::Health { name, json, strict } => {
+                // A glob checks every matching env; the exit code follows the worst one
+                let names = resolve_env_names(name, &db)?;
+                let mut worst = crate::types::HealthLevel::Pass;
+                let mut json_reports = Vec::new();
+                for (i, name) in names.iter().enumerate() {
+                    let env_name = types::EnvName::new(name).map_err(|e| e.to_string())?;
+                    let report = match ops.check_health(&env_name) {
+                        Ok(report) => report,
+                        Err(e) => {
+                            eprintln!("{} {}", "Error:".red(), e);
+                            std::process::exit(1);
                         }
+                    };
+                    worst = worst.max(report.overall());
+                    if json {
+                        json_reports.push(report.to_json(name));
+                        continue;
+                    }
+                    if i > 0 {
                         println!();
-                        let status = match report.overall() {
-                            crate::types::HealthLevel::Pass => {
-                                "OK".truecolor(100, 200, 255).bold().to_string()
-                            }
-                            crate::types::HealthLevel::Info => {
-                                "MINOR".truecolor(255, 182, 193).bold().to_string()
-                            }
-                            crate::types::HealthLevel::Warn => {
-                                "DRIFT".truecolor(255, 140, 0).bold().to_string()
+                    }
+                    use crate::types::Diagnostic;
+                    println!("{}  {}", "Environment:".bold(), name.tone(Tone::Accent));
+                    let label = " Health ";
+                    let total_w: usize = 50;
+                    let pad = total_w.saturating_sub(label.len()) / 2;
+                    println!(
+                        "{}{}{}",
+                        "─".repeat(pad),
+                        label.dimmed(),
+                        "─".repeat(total_w - pad - label.len())
+                    );
+                    for item in &report.items {
+                        let (icon, color_msg) = match item.level() {
+                            crate::types::HealthLevel::Pass => (
+                                "✓".tone(Tone::Accent).to_string(),
+                                item.message().normal().to_string(),
+                            ),
+                            crate::types::HealthLevel::Info => (
+                                "△".tone(Tone::Minor).to_string(),
+                                item.message().tone(Tone::Minor).to_string(),
+                            ),
+                            crate::types::HealthLevel::Warn => (
+                                "⚠".tone(Tone::Warn).to_string(),
+                                item.message().tone(Tone::Warn).to_string(),
+                            ),
+                            crate::types::HealthLevel::Fail => {
+                                ("✗".red().to_string(), item.message().red().to_string())
                             }
-                            crate::types::HealthLevel::Fail => "BROKEN".red().bold().to_string(),
                         };
-                        println!("Overall: {}", status);
+                        println!("{} {}", icon, color_msg);
+                    }
+                    println!();
+                    let status = match report.overall() {
+                        crate::types::HealthLevel::Pass => {
+                            "OK".tone(Tone::Accent).bold().to_string()
+                        }
+                        crate::types::HealthLevel::Info => {
+                            "MINOR".tone(Tone::Minor).bold().to_string()
+                        }
+                        crate::types::HealthLevel::Warn => {
+                            "DRIFT".tone(Tone::Warn).bold().to_string()
+                        }
+                        crate::types::HealthLevel::Fail => "BROKEN".red().bold().to_string(),
+                    };
+                    println!("Overall: {}", status);
+                }
+                if json {
+                    if json_reports.len() == 1 {
+                        println!("{}", json_reports[0]);
+                    } else {
+                        println!("[\n{}\n]", json_reports.join(",\n"));
+                    }
+                }
+                // OK / MINOR exit 0, BROKEN exits 1; DRIFT only with --strict
+                let threshold = if strict {
+                    crate::types::HealthLevel::Warn
+                } else {
+                    crate::types::HealthLevel::Fail
+                };
+                if worst >= threshold {
+                    std::process::exit(1);
+                }
+            }
+            Commands::Verify { name, hashes } => {
+                const MAX_FILES: usize = 10;
+                let name = resolve_env_name(name, &db)?;
+                let envs = db.list_envs()?;
+                let (_, path, ..) = envs
+                    .iter()
+                    .find(|(n, ..)| n == &name)
+                    .ok_or_else(|| ops.env_not_found(&name))?;
+                let results = utils::verify_packages(path, hashes);
+                let problems: Vec<_> = results.iter().filter(|r| !r.is_ok()).collect();
+                for result in &problems {
+                    let mut counts = Vec::new();
+                    if !result.missing.is_empty() {
+                        counts.push(format!("{} missing", result.missing.len()));
+                    }
+                    if !result.modified.is_empty() {
+                        counts.push(format!("{} modified", result.modified.len()));
+                    }
+                    println!(
+                        "{} {} {} — {}",
+                        "✗".red(),
+                        result.name.tone(Tone::Accent),
+                        result.version.dimmed(),
+                        counts.join(", ")
+                    );
+                    let files = result
+                        .missing
+                        .iter()
+                        .map(|f| ("missing ", f))
+                        .chain(result.modified.iter().map(|f| ("modified", f)));
+                    let total = result.missing.len() + result.modified.len();
+                    for (kind, file) in files.take(MAX_FILES) {
+                        println!("    {} {}", kind.dimmed(), file);
+                    }
+                    if total > MAX_FILES {
+                        println!(
+                            "    {}",
+                            format!("… and {} more", total - MAX_FILES).dimmed()
+                        );
                     }
-                    Err(e) => eprintln!("{} {}", "Error:".red(), e),
+                }
+                let no_record = results.iter().filter(|r| r.no_record).count();
+                let checked = if hashes { "files and hashes" } else { "files" };
+                if problems.is_empty() {
+                    println!(
+                        "{} {} package(s) in '{}' match their RECORD ({} checked)",
+                        "✓".tone(Tone::Accent),
+                        results.len() - no_record,
+                        name,
+                        checked
+                    );
+                } else {
+                    println!(
+                        "{} of {} package(s) in '{}' don't match their RECORD ({} checked)",
+                        problems.len(),
+                        results.len() - no_record,
+                        name,
+                        checked
+                    );
+                }
+                if no_record > 0 {
+                    println!(
+                        "{}",
+                        format!("{} package(s) have no RECORD and were skipped", no_record)
+                            .dimmed()
+                    );
+                }
+                if !problems.is_empty() {
+                    std::process::exit(1);
                 }
             }
             Commands::Activate {
@@ -4019,8 +7191,47 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
 
                 // zen activate <name>: explicit environment name
-                if let Some(ref env_name) = name {
+                if let Some(ref requested) = name {
                     let envs = db.list_envs()?;
+                    let names: Vec<String> = envs.iter().map(|e| e.0.clone()).collect();
+                    // Exact, then loose ("mlenv" → "ml_env"), then a confirmed near miss
+                    let resolved = if names.contains(requested) {
+                        Some(requested.clone())
+                    } else if let Some(target) = db.resolve_alias(requested)? {
+                        Some(target)
+                    } else if let Some(matched) = utils::loose_match(requested, &names) {
+                        eprintln!("Using '{}' for '{}'.", matched, requested);
+                        Some(matched)
+                    } else {
+                        match utils::similar_names(requested, &names).as_slice() {
+                            [] => None,
+                            [only] => {
+                                eprint!(
+                                    "Environment '{}' not found. Activate '{}'? [Y/n] ",
+                                    requested,
+                                    only.cyan()
+                                );
+                                // EOF (no terminal) declines rather than guessing
+                                let mut input = String::new();
+                                let read = std::io::stdin().read_line(&mut input)?;
+                                if read > 0 && matches!(input.trim(), "" | "y" | "Y" | "yes") {
+                                    Some(only.clone())
+                                } else {
+                                    eprintln!("Cancelled.");
+                                    std::process::exit(1);
+                                }
+                            }
+                            several => {
+                                eprintln!(
+                                    "Environment '{}' not found. Did you mean: {}?",
+                                    requested,
+                                    several.join(", ")
+                                );
+                                std::process::exit(1);
+                            }
+                        }
+                    };
+                    let env_name = resolved.as_ref().unwrap_or(requested);
                     let env = envs.iter().find(|(n, ..)| n == env_name);
 
                     if let Some((_, path, ..)) = env {
@@ -4049,7 +7260,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             "activate:error",
                             &format!("{} - not found", env_name),
                         );
-                        eprintln!("Environment '{}' not found.", env_name);
+                        eprintln!("{}", ops.env_not_found(env_name));
                         std::process::exit(1);
                     }
                     return Ok(());
@@ -4232,7 +7443,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Commands::Hook { shell } => {
                 print!("{}", crate::hooks::generate_hook(&shell));
             }
+            Commands::Complete { kind, prefix } => {
+                let mut candidates: Vec<String> = match kind {
+                    CompleteKind::Envs => db
+                        .list_envs()?
+                        .into_iter()
+                        .map(|e| e.0)
+                        .chain(db.list_aliases()?.into_iter().map(|(alias, _)| alias))
+                        .collect(),
+                    CompleteKind::Labels => db
+                        .get_all_labels()?
+                        .into_iter()
+                        .flat_map(|(_, labels)| labels)
+                        .collect(),
+                    CompleteKind::Templates => db
+                        .list_templates()?
+                        .into_iter()
+                        .flat_map(|(name, version, _)| {
+                            let versioned = format!("{}:{}", name, version);
+                            [name, versioned]
+                        })
+                        .collect(),
+                };
+                candidates.sort();
+                candidates.dedup();
+                let prefix = prefix.unwrap_or_default();
+                for candidate in candidates.iter().filter(|c| c.starts_with(&prefix)) {
+                    println!("{}", candidate);
+                }
+            }
             Commands::Clone { source, name } => {
+                let source = expand_alias(source, &db)?;
                 let envs = db.list_envs()?;
                 let found = envs.iter().find(|(n, ..)| n == &source);
                 let (_, source_path, source_py, ..) = match found {
@@ -4332,8 +7573,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         println!("Linked '{}' to this project.", name.cyan());
                     } else {
                         eprintln!(
-                            "Environment '{}' not found. Run 'zen list' to see available environments.",
-                            name
+                            "{} Run 'zen list' to see available environments.",
+                            ops.env_not_found(&name)
                         );
                     }
                 }
@@ -4369,7 +7610,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         eprintln!("Environment '{}' not found.", name);
                     }
                 }
-                LinkCommands::List { path } => {
+                LinkCommands::List { path, porcelain } => {
                     let project_path = match path {
                         Some(p) => std::path::Path::new(&p)
                             .canonicalize()
@@ -4385,6 +7626,38 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     // Get linked environments with activation stats
                     let links = db.get_project_links_with_stats(&project_path)?;
 
+                    if porcelain {
+                        // Fall back to the nearest linked parent, like activation does
+                        let links = if links.is_empty() {
+                            let all_projects = db.get_all_project_paths()?;
+                            match all_projects
+                                .iter()
+                                .filter(|p| project_path.starts_with(*p) && *p != &project_path)
+                                .max_by_key(|p| p.len())
+                            {
+                                Some(parent) => db.get_project_links_with_stats(parent)?,
+                                None => links,
+                            }
+                        } else {
+                            links
+                        };
+                        for (env_name, env_path, tag, is_default, link_type, count, last_at) in
+                            links
+                        {
+                            println!(
+                                "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                                env_name,
+                                env_path,
+                                tag.unwrap_or_default(),
+                                if is_default { 1 } else { 0 },
+                                link_type,
+                                count,
+                                last_at.unwrap_or_default()
+                            );
+                        }
+                        return Ok(());
+                    }
+
                     if links.is_empty() {
                         // Check for inherited (parent path prefix match)
                         let all_projects = db.get_all_project_paths()?;
@@ -4524,22 +7797,68 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 filter,
                 lines,
                 clear,
+                rotate,
+                json,
+                source,
+                action,
             } => {
                 if clear {
                     activity_log::clear_log();
                     println!("Log cleared.");
                     return Ok(());
                 }
-                let entries = activity_log::read_log(lines, filter.as_deref());
-                if entries.is_empty() {
+                if rotate {
+                    if activity_log::rotate() {
+                        println!("Log rotated.");
+                    } else {
+                        println!("Log is empty; nothing to rotate.");
+                    }
+                    return Ok(());
+                }
+                let filter = activity_log::LogFilter {
+                    keyword: filter.as_deref(),
+                    source: source.map(|s| match s {
+                        LogSourceArg::Cli => "cli",
+                        LogSourceArg::Mcp => "mcp",
+                    }),
+                    action: action.as_deref(),
+                };
+                let entries = activity_log::read_entries(lines, &filter);
+                if json {
+                    for entry in &entries {
+                        println!("{}", serde_json::to_string(entry)?);
+                    }
+                } else if entries.is_empty() {
                     println!("No log entries found.");
                 } else {
                     for entry in &entries {
-                        println!("{}", entry);
+                        println!("{}", entry.to_text());
                     }
                     println!("{}", format!("({} entries)", entries.len()).dimmed());
                 }
             }
+            Commands::Cuda { subcommand } => match subcommand {
+                CudaCommands::List => {
+                    println!("{}", "PyTorch indexes:".bold());
+                    for (version, url) in utils::TORCH_INDEXES {
+                        let alias = match *version {
+                            "cpu" => "torch-cpu".to_string(),
+                            v => format!("torch-cu{}", v.replace('.', "")),
+                        };
+                        println!(
+                            "  {:<6} {:<14} {}",
+                            version.tone(Tone::Accent),
+                            alias,
+                            url.dimmed()
+                        );
+                    }
+                    println!(
+                        "\n{}",
+                        "Install with e.g. `zen install torch-cu128`, or `zen create <name> --ml --cuda 12.8`."
+                            .dimmed()
+                    );
+                }
+            },
             Commands::Mcp => {
                 let db_clone = db::Database::open(cli.db_path.as_deref())?;
                 tokio::runtime::Runtime::new()?
@@ -30,6 +30,51 @@ fn redact_path(path: &str) -> String {
         .unwrap_or_else(|| "~/…".to_string())
 }
 
+/// Redacts every absolute path in a free-form line (e.g. an activity log entry).
+fn redact_paths_in(line: &str) -> String {
+    line.split(' ')
+        .map(|word| {
+            let start = word.find('/').unwrap_or(word.len());
+            // Only absolute paths, optionally wrapped: "(/home/me/env)" keeps its parens
+            if start < word.len() && word[..start].chars().all(|c| "([\"'".contains(c)) {
+                let end = word
+                    .rfind(|c: char| !")],.:;\"'".contains(c))
+                    .map_or(word.len(), |i| i + 1)
+                    .max(start + 1);
+                format!(
+                    "{}{}{}",
+                    &word[..start],
+                    redact_path(&word[start..end]),
+                    &word[end..]
+                )
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Default cap on result lines for search-style tools.
+const DEFAULT_RESULT_LIMIT: usize = 50;
+
+/// Keeps the first `limit` lines and appends an "…and N more" note.
+///
+/// Bounds tool responses so a large fleet can't flood the agent's context.
+fn truncate_lines(mut lines: Vec<String>, limit: Option<usize>) -> String {
+    let limit = limit.unwrap_or(DEFAULT_RESULT_LIMIT).max(1);
+    let hidden = lines.len().saturating_sub(limit);
+    lines.truncate(limit);
+    let mut out = lines.join("\n");
+    if hidden > 0 {
+        out.push_str(&format!(
+            "\n…and {} more (raise `limit` to see them)",
+            hidden
+        ));
+    }
+    out
+}
+
 /// Input parameter types for MCP tools
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct CreateEnvironmentParams {
@@ -105,6 +150,16 @@ pub struct EnvNameParam {
     pub env_name: EnvName,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct HealthParams {
+    #[schemars(description = "Name of the environment")]
+    pub env_name: EnvName,
+    #[schemars(
+        description = "Return only a JSON summary: overall level (OK, MINOR, DRIFT, BROKEN) and item counts per level"
+    )]
+    pub summary_only: Option<bool>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct ProjectPathParam {
     #[schemars(description = "Absolute path to the project directory")]
@@ -123,12 +178,28 @@ pub struct AssociateProjectParams {
     pub is_default: Option<bool>,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ProjectLinkParams {
+    #[schemars(description = "Absolute path to the project directory")]
+    pub project_path: String,
+    #[schemars(description = "Name of the linked environment")]
+    pub env_name: EnvName,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct CompareEnvironmentsParams {
     #[schemars(description = "List of environment names to compare")]
     pub env_names: Vec<String>,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DiffEnvironmentsParams {
+    #[schemars(description = "First environment (baseline)")]
+    pub first: EnvName,
+    #[schemars(description = "Second environment to compare against the first")]
+    pub second: EnvName,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct AddEnvironmentNoteParams {
     #[schemars(description = "Name of the environment")]
@@ -141,6 +212,8 @@ pub struct AddEnvironmentNoteParams {
 pub struct SearchPackagesParams {
     #[schemars(description = "Package name or partial name to search for")]
     pub query: String,
+    #[schemars(description = "Maximum number of results to return (default 50)")]
+    pub limit: Option<usize>,
 }
 
 /// Parameters for the `find_package` MCP tool.
@@ -155,6 +228,8 @@ pub struct FindPackageParams {
         description = "Package name or pattern. Supports wildcards (*torch*) and version pinning (torch==2.10). CUDA-aware: 'torch==2.10' matches '2.10.0+cu130'"
     )]
     pub query: String,
+    #[schemars(description = "Maximum number of results to return (default 50)")]
+    pub limit: Option<usize>,
 }
 
 /// Parameters for the `get_package_details` MCP tool.
@@ -169,6 +244,16 @@ pub struct PackageDetailsParams {
     pub package: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ActivityLogParams {
+    #[schemars(
+        description = "Only entries containing this keyword (env name, action like 'install', source like 'mcp')"
+    )]
+    pub filter: Option<String>,
+    #[schemars(description = "Number of most recent entries to return (default 25, max 200)")]
+    pub lines: Option<usize>,
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct LabelParams {
     #[schemars(description = "Name of the environment")]
@@ -185,6 +270,28 @@ pub struct RenameParams {
     pub new_name: String,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ApplyTemplateParams {
+    #[schemars(description = "Name of an existing environment")]
+    pub env_name: EnvName,
+    #[schemars(
+        description = "Template(s) as name:version, comma-separated for several (e.g. 'torch:2.10,dev:1')"
+    )]
+    pub template: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct CreateFromTemplateParams {
+    #[schemars(description = "Name of the new environment")]
+    pub name: EnvName,
+    #[schemars(
+        description = "Template(s) as name:version, comma-separated for several (e.g. 'torch:2.10,dev:1')"
+    )]
+    pub template: String,
+    #[schemars(description = "Python version. Defaults to the first template's Python version")]
+    pub python: Option<String>,
+}
+
 /// The Zen MCP Server.
 #[derive(Clone)]
 pub struct ZenMcpServer {
@@ -278,6 +385,7 @@ impl ZenMcpServer {
             upgrade: params.upgrade.unwrap_or(false),
             editable: params.editable.unwrap_or(false),
             dry_run: false,
+            retries: crate::ops::configured_install_retries(&db),
         };
 
         match ops.install_packages(&params.env_name, params.packages.clone(), opts) {
@@ -611,6 +719,52 @@ impl ZenMcpServer {
         }
     }
 
+    #[tool(description = "Remove the link between a project directory and an environment")]
+    fn unlink_project(&self, Parameters(params): Parameters<ProjectLinkParams>) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.unlink_project(&params.project_path, &params.env_name) {
+            Ok(msg) => {
+                crate::activity_log::log_activity(
+                    "mcp",
+                    "link:rm",
+                    &format!(
+                        "{} -> {}",
+                        params.env_name,
+                        redact_path(&params.project_path)
+                    ),
+                );
+                msg
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Make an already-linked environment the project's default (clears the previous default)"
+    )]
+    fn set_default_environment(&self, Parameters(params): Parameters<ProjectLinkParams>) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.set_default_env(&params.project_path, &params.env_name) {
+            Ok(msg) => {
+                crate::activity_log::log_activity(
+                    "mcp",
+                    "link:default",
+                    &format!(
+                        "{} -> {}",
+                        params.env_name,
+                        redact_path(&params.project_path)
+                    ),
+                );
+                msg
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
     #[tool(description = "Get the default environment for a project")]
     fn get_default_environment(&self, Parameters(params): Parameters<ProjectPathParam>) -> String {
         let db = self.db.lock().unwrap();
@@ -648,6 +802,34 @@ impl ZenMcpServer {
         }
     }
 
+    #[tool(description = "Get all projects an environment is linked to")]
+    fn get_projects_for_environment(&self, Parameters(params): Parameters<EnvNameParam>) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.get_env_projects(&params.env_name) {
+            Ok(projects) => {
+                let list: Vec<String> = projects
+                    .into_iter()
+                    .map(|(path, tag, is_default)| {
+                        let tag_str = tag.map(|t| format!(" [{}]", t)).unwrap_or_default();
+                        let default = if is_default { " (DEFAULT)" } else { "" };
+                        format!("• {}{}{}", redact_path(&path), tag_str, default)
+                    })
+                    .collect();
+                if list.is_empty() {
+                    format!(
+                        "Environment '{}' is not linked to any project",
+                        params.env_name
+                    )
+                } else {
+                    list.join("\n")
+                }
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
     #[tool(
         description = "Get detailed information about an environment including Python version, packages, ML frameworks"
     )]
@@ -704,11 +886,15 @@ impl ZenMcpServer {
     }
 
     #[tool(description = "Check environment health: package conflicts, outdated dependencies")]
-    fn get_environment_health(&self, Parameters(params): Parameters<EnvNameParam>) -> String {
+    fn get_environment_health(&self, Parameters(params): Parameters<HealthParams>) -> String {
         let db = self.db.lock().unwrap();
         let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
 
         match ops.check_health(&params.env_name) {
+            Ok(report) if params.summary_only.unwrap_or(false) => {
+                serde_json::to_string_pretty(&report.summary(&params.env_name))
+                    .unwrap_or_else(|e| format!("Error: {}", e))
+            }
             Ok(report) => report.to_text(&params.env_name),
             Err(e) => format!("Error: {}", e),
         }
@@ -823,12 +1009,25 @@ impl ZenMcpServer {
         }
     }
 
+    #[tool(
+        description = "Diff two environments: python version delta plus [changed], [only_in_first] and [only_in_second] package sections"
+    )]
+    fn diff_environments(&self, Parameters(params): Parameters<DiffEnvironmentsParams>) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.diff_envs(&params.first, &params.second) {
+            Ok(diff) => diff.to_text(),
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
     #[tool(description = "Get notes attached to an environment (purpose, description, reminders)")]
     fn get_environment_notes(&self, Parameters(params): Parameters<EnvNameParam>) -> String {
         let db = self.db.lock().unwrap();
         let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
 
-        match ops.list_comments(None, Some(&params.env_name)) {
+        match ops.list_comments(None, Some(&params.env_name), None) {
             Ok(comments) => {
                 if comments.is_empty() {
                     return format!("No notes for environment '{}'", params.env_name);
@@ -862,29 +1061,27 @@ impl ZenMcpServer {
     )]
     fn search_packages(&self, Parameters(params): Parameters<SearchPackagesParams>) -> String {
         let db = self.db.lock().unwrap();
-        match db.list_envs() {
-            Ok(envs) => {
-                let mut results = Vec::new();
-                for (name, path, ..) in &envs {
-                    let packages = crate::utils::get_packages(path);
-                    for pkg in packages {
-                        if pkg
-                            .name
-                            .to_lowercase()
-                            .contains(&params.query.to_lowercase())
-                        {
-                            let ver = pkg.version.unwrap_or_else(|| "?".to_string());
-                            results.push(format!("• {} → {} ({})", name, pkg.name, ver));
-                        }
-                    }
-                }
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+        if let Err(e) = ops.refresh_package_index(false) {
+            return format!("Error: {}", e);
+        }
+        match db.search_packages(&params.query, false) {
+            Ok(matches) => {
+                let results: Vec<String> = matches
+                    .into_iter()
+                    .map(|(name, pkg, ver)| {
+                        let ver = ver.unwrap_or_else(|| "?".to_string());
+                        format!("• {} → {} ({})", name, pkg, ver)
+                    })
+                    .collect();
                 if results.is_empty() {
                     format!("No packages matching '{}' found", params.query)
                 } else {
                     format!(
-                        "Packages matching '{}':\n{}",
+                        "Packages matching '{}' ({} total):\n{}",
                         params.query,
-                        results.join("\n")
+                        results.len(),
+                        truncate_lines(results, params.limit)
                     )
                 }
             }
@@ -910,40 +1107,34 @@ impl ZenMcpServer {
         };
 
         // Default to substring matching (strip any legacy glob chars)
-        // pip treats hyphens and underscores as equivalent
-        let normalize = |s: &str| s.to_lowercase().replace('-', "_");
-        let pattern = normalize(&pkg_query.replace('*', ""));
+        let pattern = pkg_query.replace('*', "");
 
-        match db.list_envs() {
-            Ok(envs) => {
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+        if let Err(e) = ops.refresh_package_index(false) {
+            return format!("Error: {}", e);
+        }
+        match db.search_packages(&pattern, false) {
+            Ok(matches) => {
                 let mut found = Vec::new();
-                for (name, path, ..) in &envs {
-                    let packages = crate::utils::get_packages(path);
-                    for pkg in packages {
-                        let pkg_norm = normalize(&pkg.name);
-
-                        // Substring match by default
-                        let name_match = pkg_norm.contains(&pattern);
-
-                        let version_match = match (&version_query, &pkg.version) {
-                            (Some(q), Some(v)) => {
-                                // Version query with "+" requires exact match (e.g., "2.10.0+cu130")
-                                // Without "+", match base version before the CUDA suffix
-                                if q.contains('+') {
-                                    v == q
-                                } else {
-                                    let base_ver = v.split('+').next().unwrap_or(v);
-                                    base_ver.starts_with(q.as_str())
-                                }
+                for (name, pkg_name, version) in matches {
+                    let version_match = match (&version_query, &version) {
+                        (Some(q), Some(v)) => {
+                            // Version query with "+" requires exact match (e.g., "2.10.0+cu130")
+                            // Without "+", match base version before the CUDA suffix
+                            if q.contains('+') {
+                                v == q
+                            } else {
+                                let base_ver = v.split('+').next().unwrap_or(v);
+                                base_ver.starts_with(q.as_str())
                             }
-                            (Some(_), None) => false,
-                            (None, _) => true,
-                        };
-
-                        if name_match && version_match {
-                            let ver = pkg.version.unwrap_or_else(|| "?".to_string());
-                            found.push(format!("• {} → {} ({})", name, pkg.name, ver));
                         }
+                        (Some(_), None) => false,
+                        (None, _) => true,
+                    };
+
+                    if version_match {
+                        let ver = version.unwrap_or_else(|| "?".to_string());
+                        found.push(format!("• {} → {} ({})", name, pkg_name, ver));
                     }
                 }
                 if found.is_empty() {
@@ -953,7 +1144,7 @@ impl ZenMcpServer {
                         "Found {} match(es) for '{}':\n{}",
                         found.len(),
                         params.query,
-                        found.join("\n")
+                        truncate_lines(found, params.limit)
                     )
                 }
             }
@@ -999,14 +1190,23 @@ impl ZenMcpServer {
                                     if pkg.is_editable { "yes" } else { "no" }
                                 ));
                                 if let Some(url) = &pkg.source_url {
-                                    details.push_str(&format!("**URL**: {}\n", url));
+                                    details.push_str(&format!(
+                                        "**URL**: {}\n",
+                                        crate::utils::redact_url(url)
+                                    ));
                                 }
                                 if let Some(commit) = &pkg.commit_id {
                                     details.push_str(&format!("**Commit**: {}\n", commit));
                                 }
-                                // Import name: only show when pip name is NOT in top_level.txt
-                                if let Some(ref import) = pkg.import_name {
-                                    details.push_str(&format!("**Import**: `{}`\n", import));
+                                // Import names: only shown when they aren't just the pip name
+                                if !pkg.import_names.is_empty() {
+                                    let imports: Vec<String> = pkg
+                                        .import_names
+                                        .iter()
+                                        .map(|i| format!("`{}`", i))
+                                        .collect();
+                                    details
+                                        .push_str(&format!("**Import**: {}\n", imports.join(", ")));
                                 }
                                 if let Some(epoch) = pkg.installed_at {
                                     use chrono::{Local, TimeZone};
@@ -1032,6 +1232,92 @@ impl ZenMcpServer {
         }
     }
 
+    #[tool(
+        description = "List saved environment templates (curated package stacks) with Python version and package count"
+    )]
+    fn list_templates(&self) -> String {
+        let db = self.db.lock().unwrap();
+        match db.get_all_templates_with_packages() {
+            Ok(templates) => {
+                if templates.is_empty() {
+                    return "No templates found.".to_string();
+                }
+                let mut output = String::from("Templates:\n");
+                for (name, version, python, packages) in &templates {
+                    output.push_str(&format!(
+                        "• {}:{} (Python {}) - {} package(s)\n",
+                        name,
+                        version,
+                        python,
+                        packages.len()
+                    ));
+                }
+                output
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(description = "Install a template's packages into an existing environment")]
+    fn apply_template(&self, Parameters(params): Parameters<ApplyTemplateParams>) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.apply_template(&params.env_name, &params.template) {
+            Ok(msg) => {
+                crate::activity_log::log_activity(
+                    "mcp",
+                    "template:apply",
+                    &format!("{} {}", params.env_name.as_str(), params.template),
+                );
+                msg
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Create a new environment from one or more templates (e.g. template='torch:2.10')"
+    )]
+    fn create_environment_from_template(
+        &self,
+        Parameters(params): Parameters<CreateFromTemplateParams>,
+    ) -> String {
+        let db = self.db.lock().unwrap();
+        let ops = crate::ops::ZenOps::new_plain(&db, self.home.clone());
+
+        match ops.create_env_from_template(&params.name, &params.template, params.python) {
+            Ok(msg) => {
+                crate::activity_log::log_activity(
+                    "mcp",
+                    "create",
+                    &format!("{} --template {}", params.name.as_str(), params.template),
+                );
+                msg
+            }
+            Err(e) => format!("Error: {}", e),
+        }
+    }
+
+    #[tool(
+        description = "Recent Zen operations (creates, installs, removals) from the activity log, oldest first"
+    )]
+    fn get_activity_log(&self, Parameters(params): Parameters<ActivityLogParams>) -> String {
+        let lines = params.lines.unwrap_or(25).clamp(1, 200);
+        let entries = crate::activity_log::read_log(lines, params.filter.as_deref());
+        if entries.is_empty() {
+            return match params.filter {
+                Some(f) => format!("No activity matching '{}'", f),
+                None => "No activity recorded yet".to_string(),
+            };
+        }
+        entries
+            .iter()
+            .map(|line| redact_paths_in(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
     #[tool(description = "Add a label to an environment (e.g., ml, dev, testing)")]
     fn add_label(&self, Parameters(params): Parameters<LabelParams>) -> String {
         let db = self.db.lock().unwrap();
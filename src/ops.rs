@@ -1,16 +1,19 @@
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::db::Database;
-use crate::types::{Diagnostic, EnvName, HealthDiagnostic, HealthLevel, HealthReport};
+use crate::db::{Database, PackageMetadata, SnapshotPackage, TemplatePackageRow};
+use crate::types::{Diagnostic, EnvDiff, EnvName, HealthDiagnostic, HealthLevel, HealthReport};
 use crate::utils;
+use colored::Colorize;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use owo_colors::OwoColorize;
 use rayon::prelude::*;
 use std::error::Error;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use uuid::Uuid;
 
+/// Built-in Python version used when `default_python` is not configured.
+pub const FALLBACK_PYTHON: &str = "3.12";
+
 /// Main operations layer for Zen.
 ///
 /// This struct coordinates between the database and the filesystem,
@@ -20,6 +23,20 @@ pub struct ZenOps<'a> {
     db: &'a Database,
     home: PathBuf,
     colored: bool,
+    /// Resolved once: `None` when uv is missing, disabled by `prefer_uv=false`, or `--no-uv`.
+    uv: Option<PathBuf>,
+}
+
+/// Installer tooling left out of snapshots (`template from-env`, `freeze`).
+const BOOTSTRAP_PACKAGES: &[&str] = &["pip", "setuptools", "uv", "wheel"];
+
+/// Output format of `ZenOps::freeze_env`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FreezeFormat {
+    /// `name==version` lines, like `pip freeze`.
+    Requirements,
+    /// A minimal `pyproject.toml` with a `[project]` table.
+    Pyproject,
 }
 
 /// Options for package installation (shared by CLI and MCP).
@@ -31,6 +48,8 @@ pub struct InstallOptions {
     pub upgrade: bool,
     pub editable: bool,
     pub dry_run: bool,
+    /// Re-runs after network errors (see `configured_install_retries`)
+    pub retries: u32,
 }
 
 impl<'a> ZenOps<'a> {
@@ -40,6 +59,7 @@ impl<'a> ZenOps<'a> {
             db,
             home,
             colored: true,
+            uv: resolve_uv(db),
         }
     }
 
@@ -49,9 +69,32 @@ impl<'a> ZenOps<'a> {
             db,
             home,
             colored: false,
+            uv: resolve_uv(db),
         }
     }
 
+    /// Forces the pip codepath regardless of uv availability (`--no-uv`).
+    pub fn without_uv(mut self) -> Self {
+        self.uv = None;
+        self
+    }
+
+    /// Python version for new environments when neither `--python` nor a
+    /// template specifies one: the `default_python` config, else 3.12.
+    pub fn default_python(&self) -> String {
+        self.db
+            .get_config("default_python")
+            .ok()
+            .flatten()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| FALLBACK_PYTHON.to_string())
+    }
+
+    /// Path to uv when it should be used for venv creation and installs.
+    pub fn uv_path(&self) -> Option<&Path> {
+        self.uv.as_deref()
+    }
+
     /// Returns a colored or plain success marker.
     fn ok_mark(&self) -> String {
         if self.colored {
@@ -77,6 +120,166 @@ impl<'a> ZenOps<'a> {
         self.db.list_envs()
     }
 
+    /// Finds venvs directly under the Zen home that are not yet registered.
+    ///
+    /// A directory counts as a venv when it has `bin/python` or `bin/python3`.
+    /// Returns (name, path) pairs; nothing is written to the database.
+    pub fn find_unregistered_envs(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut found = Vec::new();
+        let Ok(entries) = std::fs::read_dir(&self.home) else {
+            return Ok(found);
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let python_bin = path.join("bin/python");
+            let python3_bin = path.join("bin/python3");
+            if path.is_dir() && (python_bin.exists() || python3_bin.exists()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if self.db.get_env_id(&name)?.is_none() {
+                    found.push((name, path.to_string_lossy().to_string()));
+                }
+            }
+        }
+        found.sort();
+        Ok(found)
+    }
+
+    /// Registers every unregistered venv under the Zen home.
+    ///
+    /// Returns the (name, path) pairs that were added.
+    pub fn register_unregistered_envs(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let found = self.find_unregistered_envs()?;
+        if found.is_empty() {
+            return Ok(found);
+        }
+        // Read-only handles only reopen for writing when there is something to add;
+        // without write access discovery waits for the next writable run
+        let Ok(db) = self.db.writable() else {
+            return Ok(Vec::new());
+        };
+        for (name, path) in &found {
+            let py_ver = utils::read_python_version(path).unwrap_or_else(|| "unknown".to_string());
+            db.register_env(name, path, &py_ver)?;
+        }
+        Ok(found)
+    }
+
+    /// Returns registered environments whose directory no longer exists.
+    ///
+    /// Returns (name, path) pairs.
+    pub fn find_missing_envs(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        Ok(self
+            .db
+            .list_envs()?
+            .into_iter()
+            .filter(|(_, path, ..)| !Path::new(path).exists())
+            .map(|(name, path, ..)| (name, path))
+            .collect())
+    }
+
+    /// When each registered environment was last used.
+    ///
+    /// Prefers the recorded `last_used_at` (activate/run/install). Envs with no
+    /// history fall back to the newest mtime of the env directory and its
+    /// site-packages, so an old env never looks fresh just because it was never
+    /// activated through zen. The registration time is the last resort.
+    pub fn env_last_used(
+        &self,
+    ) -> Result<std::collections::HashMap<String, chrono::DateTime<chrono::Utc>>, Box<dyn Error>>
+    {
+        let recorded = self.db.get_last_used()?;
+        let mut result = std::collections::HashMap::new();
+        for (name, path, _, updated, _) in self.db.list_envs()? {
+            let dir_mtime = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .ok()
+                .map(|t| chrono::DateTime::<chrono::Utc>::from(t).timestamp_millis());
+            let fs_time = dir_mtime
+                .max(utils::site_packages_mtime(&path))
+                .and_then(chrono::DateTime::from_timestamp_millis);
+            let used = recorded
+                .get(&name)
+                .and_then(|ts| utils::parse_db_timestamp(ts))
+                .or(fs_time)
+                .or_else(|| utils::parse_db_timestamp(&updated));
+            if let Some(used) = used {
+                result.insert(name, used);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Environments not used for at least `days` days, oldest first.
+    ///
+    /// Returns (name, path, idle_days) triples.
+    pub fn find_stale_envs(&self, days: i64) -> Result<Vec<(String, String, i64)>, Box<dyn Error>> {
+        let last_used = self.env_last_used()?;
+        let now = chrono::Utc::now();
+        let mut stale: Vec<_> = self
+            .db
+            .list_envs()?
+            .into_iter()
+            .filter_map(|(name, path, ..)| {
+                let idle = (now - *last_used.get(&name)?).num_days();
+                (idle >= days).then_some((name, path, idle))
+            })
+            .collect();
+        stale.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        Ok(stale)
+    }
+
+    /// Registered environment names close to `name`, closest first, for
+    /// "did you mean" hints.
+    pub fn suggest_similar(&self, name: &str) -> Vec<String> {
+        let names: Vec<String> = match self.db.list_envs() {
+            Ok(envs) => envs.into_iter().map(|e| e.0).collect(),
+            Err(_) => return Vec::new(),
+        };
+        utils::similar_names(name, &names)
+    }
+
+    /// "Environment 'x' not found." with a "Did you mean: a, b?" hint when
+    /// close names exist.
+    pub fn env_not_found(&self, name: &str) -> String {
+        let similar = self.suggest_similar(name);
+        if similar.is_empty() {
+            format!("Environment '{}' not found.", name)
+        } else {
+            format!(
+                "Environment '{}' not found. Did you mean: {}?",
+                name,
+                similar.join(", ")
+            )
+        }
+    }
+
+    /// Brings the package index up to date for every registered environment.
+    ///
+    /// Environments whose site-packages mtime matches the indexed value are
+    /// skipped unless `force` is set. Returns the number of envs re-scanned.
+    pub fn refresh_package_index(&self, force: bool) -> Result<usize, Box<dyn Error>> {
+        let mut stale = Vec::new();
+        for (name, path, ..) in self.db.list_envs()? {
+            let mtime = utils::site_packages_mtime(&path);
+            if !force && mtime.is_some() && self.db.get_package_index_mtime(&name)? == mtime {
+                continue;
+            }
+            stale.push((name, path, mtime));
+        }
+        if stale.is_empty() {
+            return Ok(0);
+        }
+        // The index is a cache: without write access, searches use it as it is
+        let Ok(db) = self.db.writable() else {
+            return Ok(0);
+        };
+        for (name, path, mtime) in &stale {
+            let packages = utils::get_packages(path);
+            db.index_packages(name, *mtime, &packages)?;
+        }
+        Ok(stale.len())
+    }
+
     /// Removes an environment from the database and deletes it from disk.
     pub fn remove_env(&self, name: &EnvName) -> Result<String, Box<dyn Error>> {
         let envs = self.list_envs()?;
@@ -87,11 +290,12 @@ impl<'a> ZenOps<'a> {
             if path.exists() {
                 std::fs::remove_dir_all(&path)?;
             }
-            self.db.delete_env(name)?;
+            let cleanup = self.db.delete_env(name)?;
             Ok(format!(
-                "{} Environment '{}' removed from disk and registry.",
+                "{} Environment '{}' removed from disk and registry{}.",
                 self.ok_mark(),
-                name
+                name,
+                cleanup_suffix(&cleanup)
             ))
         } else {
             // Not in DB — check if orphaned directory exists on disk
@@ -104,18 +308,19 @@ impl<'a> ZenOps<'a> {
                     name
                 ))
             } else {
-                Err(format!("Environment '{}' not found.", name).into())
+                Err(self.env_not_found(name).into())
             }
         }
     }
 
     /// Removes an environment from the database only, keeping files on disk.
     pub fn untrack_env(&self, name: &EnvName) -> Result<String, Box<dyn Error>> {
-        self.db.delete_env(name)?;
+        let cleanup = self.db.delete_env(name)?;
         Ok(format!(
-            "{} Environment '{}' removed from registry (files kept on disk).",
+            "{} Environment '{}' removed from registry (files kept on disk){}.",
             self.ok_mark(),
-            name
+            name,
+            cleanup_suffix(&cleanup)
         ))
     }
 
@@ -135,12 +340,12 @@ impl<'a> ZenOps<'a> {
             .into());
         }
 
-        let py_version = python.unwrap_or_else(|| "3.12".to_string());
+        let py_version = python.unwrap_or_else(|| self.default_python());
 
         // Simplified creation logic (no templates for MCP MVP yet)
         std::fs::create_dir_all(&self.home)?;
 
-        let status = if let Ok(uv_path) = which::which("uv") {
+        let status = if let Some(uv_path) = self.uv_path() {
             std::process::Command::new(uv_path)
                 .arg("venv")
                 .arg(&env_path)
@@ -156,11 +361,13 @@ impl<'a> ZenOps<'a> {
         };
 
         if !status.status.success() {
-            return Err(format!(
-                "Failed to create venv: {:?}",
-                String::from_utf8_lossy(&status.stderr)
-            )
-            .into());
+            let stderr = String::from_utf8_lossy(&status.stderr);
+            let mut msg = format!("Failed to create venv: {}", stderr.trim());
+            if let Some(hint) = utils::missing_python_hint(&stderr, &py_version, self.uv.is_some())
+            {
+                msg.push_str(&format!("\n{}", hint));
+            }
+            return Err(msg.into());
         }
 
         let id = self
@@ -182,7 +389,7 @@ impl<'a> ZenOps<'a> {
         let (_, env_path, ..) = envs
             .iter()
             .find(|(n, ..)| n == env_name.as_str())
-            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+            .ok_or_else(|| self.env_not_found(env_name))?;
 
         let mut args: Vec<String> = vec!["pip".into(), "install".into()];
 
@@ -213,13 +420,25 @@ impl<'a> ZenOps<'a> {
 
         let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
-        let success = if which::which("uv").is_ok() {
-            utils::run_in_env_silent(env_path, "uv", &arg_refs)
-        } else {
-            utils::run_in_env_silent(env_path, "pip", &arg_refs[1..])
-        };
+        let mut retried = 0;
+        let outcome = utils::run_with_retries(
+            opts.retries,
+            || {
+                if self.uv_path().is_some() {
+                    utils::run_in_env_streamed(env_path, "uv", &arg_refs, utils::StreamMode::Quiet)
+                } else {
+                    utils::run_in_env_streamed(
+                        env_path,
+                        "pip",
+                        &arg_refs[1..],
+                        utils::StreamMode::Quiet,
+                    )
+                }
+            },
+            |attempt, _| retried = attempt,
+        );
 
-        if success {
+        if outcome.success {
             let env_id = self.db.get_env_id(env_name)?.unwrap();
             let installed = utils::get_packages(env_path);
             for pkg_name in &packages {
@@ -227,7 +446,7 @@ impl<'a> ZenOps<'a> {
                 let base_name = if pkg_name.ends_with(".whl") || pkg_name.contains('/') {
                     // Try to find the installed name from the actual packages scan
                     continue;
-                } else if pkg_name.starts_with("torch-cu") {
+                } else if pkg_name.starts_with("torch-cu") || pkg_name == "torch-cpu" {
                     "torch"
                 } else {
                     pkg_name.as_str()
@@ -237,12 +456,424 @@ impl<'a> ZenOps<'a> {
                     self.db.log_package(env_id, &pkg.name, ver, "pypi")?;
                 }
             }
-            Ok(format!("Successfully installed: {:?}", packages))
+            let note = if retried > 0 {
+                format!(
+                    " (after {} network retr{})",
+                    retried,
+                    if retried == 1 { "y" } else { "ies" }
+                )
+            } else {
+                String::new()
+            };
+            Ok(format!("Successfully installed: {:?}{}", packages, note))
+        } else if retried > 0 {
+            Err(format!("Installation failed after {} network retries", retried).into())
         } else {
             Err("Installation failed".into())
         }
     }
 
+    /// Installs the packages of one or more templates into an existing environment.
+    ///
+    /// `template_str` uses the `--from` syntax (`name:version,name:version`).
+    /// Packages are grouped by their stored install args (e.g. `--index-url`)
+    /// so each index is hit once; wheels whose file is missing are skipped.
+    pub fn apply_template(
+        &self,
+        env_name: &EnvName,
+        template_str: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let envs = self.list_envs()?;
+        let (_, env_path, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+
+        let mut report = Vec::new();
+        for part in utils::parse_template_string(template_str) {
+            let t_id = self
+                .db
+                .get_template_id(&part.name, &part.version)?
+                .ok_or_else(|| format!("Template '{}:{}' not found", part.name, part.version))?;
+
+            let mut groups: Vec<(Option<String>, Vec<String>)> = Vec::new();
+            let mut skipped = Vec::new();
+            let packages = self.db.get_template_packages(t_id)?;
+            let total = packages.len();
+            for (p_name, p_ver, is_pinned, itype, install_args, _step, extras, version_spec) in
+                packages
+            {
+                let requirement = utils::with_extras(&p_name, extras.as_deref());
+                let (spec, group_key) = if itype == "wheel" {
+                    match install_args {
+                        Some(ref wheel) if Path::new(wheel).exists() => (wheel.clone(), None),
+                        _ => {
+                            skipped.push(p_name);
+                            continue;
+                        }
+                    }
+                } else if let Some(spec) =
+                    utils::template_source_spec(&requirement, &itype, install_args.as_deref())
+                {
+                    (spec, None)
+                } else {
+                    let spec = utils::template_requirement(
+                        &requirement,
+                        &p_ver,
+                        is_pinned,
+                        version_spec.as_deref(),
+                    );
+                    (spec, install_args)
+                };
+                match groups.iter_mut().find(|(k, _)| *k == group_key) {
+                    Some((_, specs)) => specs.push(spec),
+                    None => groups.push((group_key, vec![spec])),
+                }
+            }
+
+            for (group_args, specs) in &groups {
+                let mut cmd_args = vec!["pip", "install"];
+                if let Some(args_str) = group_args {
+                    cmd_args.extend(args_str.split_whitespace());
+                }
+                cmd_args.extend(specs.iter().map(String::as_str));
+                let ok = if self.uv_path().is_some() {
+                    utils::run_in_env_silent(env_path, "uv", &cmd_args)
+                } else {
+                    utils::run_in_env_silent(env_path, "pip", &cmd_args[1..])
+                };
+                if !ok {
+                    return Err(format!(
+                        "Installing template '{}:{}' failed ({})",
+                        part.name,
+                        part.version,
+                        specs.join(" ")
+                    )
+                    .into());
+                }
+            }
+
+            let mut line = format!(
+                "{} Applied '{}:{}' ({} package(s))",
+                self.ok_mark(),
+                part.name,
+                part.version,
+                total - skipped.len()
+            );
+            if !skipped.is_empty() {
+                line.push_str(&format!(", skipped missing wheels: {}", skipped.join(", ")));
+            }
+            report.push(line);
+        }
+        Ok(report.join("\n"))
+    }
+
+    /// Snapshots an environment's installed packages into a new template.
+    ///
+    /// The inverse of `apply_template`. Packages in the audit log (installed
+    /// explicitly through zen) are pinned; the rest are kept unpinned as
+    /// resolver-chosen dependencies. Git and editable installs keep their
+    /// source in `install_args`. Bootstrap tooling (pip, setuptools, uv, wheel)
+    /// is left out.
+    pub fn template_from_env(
+        &self,
+        env_name: &EnvName,
+        t_name: &str,
+        t_ver: &str,
+        force: bool,
+    ) -> Result<String, Box<dyn Error>> {
+        let envs = self.list_envs()?;
+        let (_, env_path, env_python, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+
+        if !force && self.db.get_template_id(t_name, t_ver)?.is_some() {
+            return Err(format!(
+                "Template '{}:{}' already exists (use --force to overwrite)",
+                t_name, t_ver
+            )
+            .into());
+        }
+
+        let env_id = self
+            .db
+            .get_env_id(env_name)?
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        let explicit: std::collections::HashSet<String> = self
+            .db
+            .get_logged_packages(env_id)?
+            .iter()
+            .map(|n| utils::normalize_package_name(n))
+            .collect();
+
+        // Templates record major.minor, e.g. "3.12"
+        let python = utils::read_python_version(env_path).unwrap_or_else(|| env_python.clone());
+        let python = python.split('.').take(2).collect::<Vec<_>>().join(".");
+
+        let mut packages = utils::get_packages(env_path);
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut rows: Vec<TemplatePackageRow> = Vec::new();
+        for pkg in &packages {
+            let norm = utils::normalize_package_name(&pkg.name);
+            if BOOTSTRAP_PACKAGES.contains(&norm.as_str()) {
+                continue;
+            }
+            let source = pkg.source_url.as_deref();
+            let (itype, install_args) = match source {
+                Some(url) if pkg.is_editable && url.starts_with("file://") => (
+                    "editable",
+                    Some(url.trim_start_matches("file://").to_string()),
+                ),
+                Some(url) if pkg.install_source.as_deref() == Some("git") => {
+                    let mut vcs = if url.starts_with("git+") {
+                        url.to_string()
+                    } else {
+                        format!("git+{}", url)
+                    };
+                    if let Some(commit) = &pkg.commit_id {
+                        vcs.push_str(&format!("@{}", commit));
+                    }
+                    ("git", Some(vcs))
+                }
+                _ => ("pypi", None),
+            };
+            let is_pinned = explicit.contains(&norm);
+            let version = pkg.version.as_deref().unwrap_or("unknown");
+            let version_spec = is_pinned.then(|| format!("=={}", version));
+            rows.push((
+                pkg.name.clone(),
+                version.to_string(),
+                is_pinned,
+                itype.to_string(),
+                install_args,
+                0,
+                None,
+                version_spec,
+            ));
+        }
+        // Written in one go, so --force never leaves the old template half-replaced
+        self.db.replace_template(t_name, t_ver, &python, &rows)?;
+        let pinned = rows.iter().filter(|r| r.2).count();
+
+        Ok(format!(
+            "{} Template '{}:{}' created from '{}' ({} package(s), {} pinned, Python {})",
+            self.ok_mark(),
+            t_name,
+            t_ver,
+            env_name,
+            rows.len(),
+            pinned,
+            python
+        ))
+    }
+
+    /// Creates a template straight from a requirements file, without a session venv.
+    ///
+    /// Exact `==` pins become pinned packages; anything else is stored unpinned
+    /// with its specifier as `version_spec`. Index options apply to every
+    /// package, as they do in pip. Returns the summary and the lines that were
+    /// skipped because a template cannot represent them.
+    pub fn template_from_requirements(
+        &self,
+        t_name: &str,
+        t_ver: &str,
+        python: &str,
+        content: &str,
+        force: bool,
+    ) -> Result<(String, Vec<String>), Box<dyn Error>> {
+        let parsed = utils::parse_requirements(content);
+        if parsed.requirements.is_empty() && parsed.editables.is_empty() {
+            return Err("No requirements found in file".into());
+        }
+        if !force && self.db.get_template_id(t_name, t_ver)?.is_some() {
+            return Err(format!(
+                "Template '{}:{}' already exists (use --force to overwrite)",
+                t_name, t_ver
+            )
+            .into());
+        }
+
+        let index_args = (!parsed.index_args.is_empty()).then(|| parsed.index_args.join(" "));
+        let mut skipped = parsed.unsupported;
+        let mut rows: Vec<TemplatePackageRow> = Vec::new();
+        for requirement in &parsed.requirements {
+            // Direct references: only VCS URLs have a template install type
+            if let Some((name, url)) = requirement.split_once('@') {
+                let url = url.trim();
+                if !url.starts_with("git+") {
+                    skipped.push(requirement.clone());
+                    continue;
+                }
+                let name = name.trim();
+                rows.push((
+                    utils::requirement_name(name),
+                    "latest".to_string(),
+                    false,
+                    "git".to_string(),
+                    Some(url.to_string()),
+                    0,
+                    utils::requirement_extras(name),
+                    None,
+                ));
+                continue;
+            }
+            let name = utils::requirement_name(requirement);
+            let spec = utils::requirement_specifier(requirement);
+            let exact = spec
+                .as_deref()
+                .and_then(|s| s.strip_prefix("=="))
+                .filter(|v| !v.contains([',', '*']));
+            rows.push((
+                name,
+                exact.unwrap_or("latest").to_string(),
+                exact.is_some(),
+                "pypi".to_string(),
+                index_args.clone(),
+                0,
+                utils::requirement_extras(requirement),
+                spec.clone(),
+            ));
+        }
+        for path in &parsed.editables {
+            let name = Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone());
+            rows.push((
+                name,
+                "latest".to_string(),
+                false,
+                "editable".to_string(),
+                Some(path.clone()),
+                0,
+                None,
+                None,
+            ));
+        }
+        self.db.replace_template(t_name, t_ver, python, &rows)?;
+        let pinned = rows.iter().filter(|r| r.2).count();
+
+        Ok((
+            format!(
+                "{} Template '{}:{}' created from requirements ({} package(s), {} pinned, Python {})",
+                self.ok_mark(),
+                t_name,
+                t_ver,
+                rows.len(),
+                pinned,
+                python
+            ),
+            skipped,
+        ))
+    }
+
+    /// Freezes an environment's installed packages as requirements or pyproject.
+    ///
+    /// Git installs become direct references (`name @ git+url@commit`). Editable
+    /// and local installs have no portable spelling in `dependencies`, so the
+    /// pyproject output lists them as comments instead.
+    pub fn freeze_env(
+        &self,
+        env_name: &EnvName,
+        format: FreezeFormat,
+    ) -> Result<String, Box<dyn Error>> {
+        let envs = self.list_envs()?;
+        let (_, env_path, env_python, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+
+        let mut packages = utils::get_packages(env_path);
+        packages.sort_by_key(|p| p.name.to_lowercase());
+        let mut deps = Vec::new();
+        let mut local = Vec::new();
+        for pkg in &packages {
+            if BOOTSTRAP_PACKAGES.contains(&utils::normalize_package_name(&pkg.name).as_str()) {
+                continue;
+            }
+            match pkg.source_url.as_deref() {
+                Some(url) if url.starts_with("file://") && format == FreezeFormat::Pyproject => {
+                    let path = url.trim_start_matches("file://");
+                    local.push(format!("{} ({})", pkg.name, path));
+                }
+                _ => deps.push(pinned_requirement(pkg)),
+            }
+        }
+
+        if format == FreezeFormat::Requirements {
+            let mut out = format!("# Frozen by zen from '{}'\n", env_name);
+            for dep in deps {
+                out.push_str(&dep);
+                out.push('\n');
+            }
+            return Ok(out);
+        }
+
+        #[derive(serde::Serialize)]
+        struct Project {
+            name: String,
+            version: String,
+            #[serde(rename = "requires-python")]
+            requires_python: String,
+            dependencies: Vec<String>,
+        }
+        #[derive(serde::Serialize)]
+        struct PyProject {
+            project: Project,
+        }
+
+        let python = utils::read_python_version(env_path).unwrap_or_else(|| env_python.clone());
+        let major_minor = python.split('.').take(2).collect::<Vec<_>>().join(".");
+        let doc = PyProject {
+            project: Project {
+                name: env_name.to_string(),
+                version: "0.1.0".to_string(),
+                requires_python: format!(">={}", major_minor),
+                dependencies: deps,
+            },
+        };
+
+        let mut out = format!("# Generated by zen from environment '{}'\n", env_name);
+        if !local.is_empty() {
+            out.push_str("# Editable/local packages can't be expressed as plain dependencies:\n");
+            for entry in &local {
+                out.push_str(&format!("#   {}\n", entry));
+            }
+        }
+        out.push('\n');
+        out.push_str(&toml::to_string_pretty(&doc)?);
+        Ok(out)
+    }
+
+    /// Creates an environment and applies templates to it in one step.
+    ///
+    /// Without an explicit `python`, the first template's Python version is used.
+    pub fn create_env_from_template(
+        &self,
+        name: &EnvName,
+        template_str: &str,
+        python: Option<String>,
+    ) -> Result<String, Box<dyn Error>> {
+        let parts = utils::parse_template_string(template_str);
+        let first = parts.first().ok_or("No template given")?;
+        let templates = self.db.list_templates()?;
+        let tpl_python = templates
+            .iter()
+            .find(|(n, v, _)| *n == first.name && *v == first.version)
+            .map(|(_, _, py)| py.clone())
+            .ok_or_else(|| format!("Template '{}:{}' not found", first.name, first.version))?;
+
+        let created = self.create_env(name, python.or(Some(tpl_python)))?;
+        let applied = self.apply_template(name, template_str)?;
+        let source: Vec<String> = parts
+            .iter()
+            .map(|p| format!("{}:{}", p.name, p.version))
+            .collect();
+        self.db.set_source_template(name, &source.join(","))?;
+        Ok(format!("{}\n{}", created, applied))
+    }
+
     /// Uninstalls packages from an environment using uv or pip.
     pub fn uninstall_packages(
         &self,
@@ -253,7 +884,7 @@ impl<'a> ZenOps<'a> {
         let (_, env_path, ..) = envs
             .iter()
             .find(|(n, ..)| n == env_name.as_str())
-            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+            .ok_or_else(|| self.env_not_found(env_name))?;
 
         let mut args: Vec<String> = vec!["pip".into(), "uninstall".into()];
         for pkg in &packages {
@@ -262,7 +893,7 @@ impl<'a> ZenOps<'a> {
 
         let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
-        let success = if which::which("uv").is_ok() {
+        let success = if self.uv_path().is_some() {
             utils::run_in_env_silent(env_path, "uv", &arg_refs)
         } else {
             // pip needs -y for non-interactive
@@ -275,6 +906,9 @@ impl<'a> ZenOps<'a> {
         };
 
         if success {
+            // Uninstalled packages no longer belong to any install group
+            self.db
+                .remove_group_packages(env_name.as_str(), &packages)?;
             Ok(format!("Successfully uninstalled: {:?}", packages))
         } else {
             Err("Uninstall failed".into())
@@ -294,7 +928,7 @@ impl<'a> ZenOps<'a> {
         let (_, env_path, ..) = envs
             .iter()
             .find(|(n, ..)| n == env_name.as_str())
-            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+            .ok_or_else(|| self.env_not_found(env_name))?;
 
         let env_path = std::path::Path::new(env_path);
         let bin_path = env_path.join("bin");
@@ -309,6 +943,7 @@ impl<'a> ZenOps<'a> {
         let path = std::env::var("PATH").unwrap_or_default();
         let output = std::process::Command::new(&program)
             .args(&cmd[1..])
+            .envs(self.db.list_env_vars(env_name)?)
             .env("PATH", format!("{}:{}", bin_path.display(), path))
             .env("VIRTUAL_ENV", env_path)
             .output()?;
@@ -334,6 +969,10 @@ impl<'a> ZenOps<'a> {
         tag: Option<&str>,
         is_default: bool,
     ) -> Result<String, Box<dyn Error>> {
+        if !Path::new(project_path).is_dir() {
+            return Err(format!("Project directory '{}' does not exist", project_path).into());
+        }
+        let project_path = &crate::db::normalize_project_path(project_path);
         self.db
             .associate_project(project_path, env_name, tag, is_default)?;
         Ok(format!(
@@ -342,6 +981,48 @@ impl<'a> ZenOps<'a> {
         ))
     }
 
+    /// Removes the link between a project and an environment.
+    pub fn unlink_project(
+        &self,
+        project_path: &str,
+        env_name: &EnvName,
+    ) -> Result<String, Box<dyn Error>> {
+        let env_id = self
+            .db
+            .get_env_id(env_name)?
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        if !self.db.remove_project_association(project_path, env_id)? {
+            return Err(format!("'{}' is not linked to project {}", env_name, project_path).into());
+        }
+        Ok(format!(
+            "Unlinked '{}' from project {}",
+            env_name, project_path
+        ))
+    }
+
+    /// Makes a linked environment the project's default, clearing the previous one.
+    pub fn set_default_env(
+        &self,
+        project_path: &str,
+        env_name: &EnvName,
+    ) -> Result<String, Box<dyn Error>> {
+        let env_id = self
+            .db
+            .get_env_id(env_name)?
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        if !self.db.set_default_environment(project_path, env_id)? {
+            return Err(format!(
+                "'{}' is not linked to project {} (link it first with associate_project)",
+                env_name, project_path
+            )
+            .into());
+        }
+        Ok(format!(
+            "'{}' is now the default environment for project {}",
+            env_name, project_path
+        ))
+    }
+
     /// Returns all environments associated with a project path.
     ///
     /// Returns a vector of (env_name, env_path, tag, is_default) tuples.
@@ -352,6 +1033,25 @@ impl<'a> ZenOps<'a> {
         self.db.get_project_environments(project_path)
     }
 
+    /// Returns all project paths associated with an environment.
+    ///
+    /// Returns a vector of (project_path, tag, is_default) tuples, default links first.
+    pub fn get_env_projects(
+        &self,
+        env_name: &str,
+    ) -> Result<Vec<(String, Option<String>, bool)>, Box<dyn Error>> {
+        let env_id = self
+            .db
+            .get_env_id(env_name)?
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        Ok(self
+            .db
+            .get_projects_for_env(env_id)?
+            .into_iter()
+            .map(|(path, tag, is_default, ..)| (path, tag, is_default))
+            .collect())
+    }
+
     /// Returns the default environment name for a given project path.
     pub fn get_default_env(&self, project_path: &str) -> Result<Option<String>, Box<dyn Error>> {
         self.db.get_default_environment(project_path)
@@ -385,22 +1085,31 @@ impl<'a> ZenOps<'a> {
     }
 
     /// Logs a comment to an environment or the current project.
+    ///
+    /// `tag` is a user-chosen category (e.g. "todo"); untagged comments keep
+    /// the automatic `Env: <name>` / `General` marker.
     pub fn log_comment(
         &self,
         env_name: Option<&EnvName>,
         message: &str,
+        tag: Option<&str>,
     ) -> Result<String, Box<dyn Error>> {
         let project_path = std::env::current_dir()?.to_str().unwrap_or(".").to_string();
         let uuid = Uuid::new_v4().to_string();
 
-        let (env_id, tag) = if let Some(name) = env_name {
+        let (env_id, auto_tag) = if let Some(name) = env_name {
             (self.db.get_env_id(name)?, format!("Env: {}", name))
         } else {
             (None, "General".to_string())
         };
 
-        self.db
-            .add_comment(&uuid, &project_path, env_id, message, Some(&tag))?;
+        self.db.add_comment(
+            &uuid,
+            &project_path,
+            env_id,
+            message,
+            Some(tag.unwrap_or(&auto_tag)),
+        )?;
 
         let msg = if let Some(name) = env_name {
             if self.colored {
@@ -425,11 +1134,14 @@ impl<'a> ZenOps<'a> {
         Ok(msg)
     }
 
-    /// Lists all comments for a project or environment.
+    /// Lists all comments for a project or environment, optionally by tag.
+    ///
+    /// Only user-chosen tags are returned; automatic markers come back as `None`.
     pub fn list_comments(
         &self,
         project_path: Option<&str>,
         env_name: Option<&EnvName>,
+        tag: Option<&str>,
     ) -> Result<
         Vec<(
             String,
@@ -447,18 +1159,57 @@ impl<'a> ZenOps<'a> {
             None
         };
 
-        let db_comments = self.db.list_comments(project_path, env_id)?;
-        let mut results = Vec::new();
+        let db_comments = self.db.list_comments(project_path, env_id, tag)?;
+        self.resolve_comments(db_comments)
+    }
 
+    /// Searches comment text across all environments (case-insensitive).
+    ///
+    /// Returns the same tuples as `list_comments`, newest first.
+    pub fn search_comments(
+        &self,
+        term: &str,
+    ) -> Result<
+        Vec<(
+            String,
+            String,
+            Option<String>,
+            String,
+            Option<String>,
+            String,
+        )>,
+        Box<dyn Error>,
+    > {
+        let db_comments = self.db.search_comments(term)?;
+        self.resolve_comments(db_comments)
+    }
+
+    /// Replaces env IDs with names and drops automatic tag markers.
+    #[allow(clippy::type_complexity)]
+    fn resolve_comments(
+        &self,
+        db_comments: Vec<(String, String, Option<i64>, String, Option<String>, String)>,
+    ) -> Result<
+        Vec<(
+            String,
+            String,
+            Option<String>,
+            String,
+            Option<String>,
+            String,
+        )>,
+        Box<dyn Error>,
+    > {
+        let mut results = Vec::new();
         for (uuid, pp, eid, msg, tag, ts) in db_comments {
             let env_display_name = if let Some(id) = eid {
                 self.db.get_env_name_by_id(id)?
             } else {
                 None
             };
+            let tag = tag.filter(|t| !is_auto_note_tag(t));
             results.push((uuid, pp, env_display_name, msg, tag, ts));
         }
-
         Ok(results)
     }
 
@@ -506,12 +1257,26 @@ impl<'a> ZenOps<'a> {
     > {
         let mut envs = self.db.list_envs()?;
 
-        // FILTERING (substring match — consistent with `zen find`)
+        // FILTERING (glob when it has wildcards, else substring — consistent with `zen find`)
         if let Some(pattern) = filter {
-            let pattern_lower = pattern.to_lowercase();
-            envs.retain(|(name, ..)| name.to_lowercase().contains(&pattern_lower));
+            if utils::is_glob(pattern) {
+                envs.retain(|(name, ..)| utils::glob_match(pattern, name));
+            } else {
+                let pattern_lower = pattern.to_lowercase();
+                envs.retain(|(name, ..)| name.to_lowercase().contains(&pattern_lower));
+            }
         }
 
+        // "recent" orders by last activate/run/install, falling back to registration time
+        let last_used = if sort_by == Some("recent") {
+            self.db.get_last_used()?
+        } else {
+            Default::default()
+        };
+        let used_at = |name: &String, updated: &String| -> String {
+            last_used.get(name).unwrap_or(updated).clone()
+        };
+
         // SORTING (Favorites always first, then requested order)
         envs.sort_by(|a, b| {
             // First by favorite status (true comes first)
@@ -520,6 +1285,7 @@ impl<'a> ZenOps<'a> {
                     // Then by requested field
                     match sort_by {
                         Some("date") => b.3.cmp(&a.3),
+                        Some("recent") => used_at(&b.0, &b.3).cmp(&used_at(&a.0, &a.3)),
                         _ => a.0.to_lowercase().cmp(&b.0.to_lowercase()),
                     }
                 }
@@ -533,8 +1299,20 @@ impl<'a> ZenOps<'a> {
         }
 
         let mut results = Vec::new();
-        for (name, path, py_ver, updated, is_fav) in envs {
+        // Opened on first write, since `list` runs on a read-only handle
+        let write_db = std::cell::OnceCell::new();
+        for (name, path, mut py_ver, updated, is_fav) in envs {
             let exists = Path::new(&path).join("bin").join("python").exists();
+            // Envs registered without a pyvenv.cfg (conda, pyenv) get resolved once and stored
+            if exists
+                && py_ver == "unknown"
+                && let Some(resolved) = utils::read_python_version(&path)
+            {
+                if let Some(db) = write_db.get_or_init(|| self.db.writable().ok()) {
+                    db.set_python_version(&name, &resolved).ok();
+                }
+                py_ver = resolved;
+            }
             results.push((name, path, py_ver, exists, updated, is_fav));
         }
 
@@ -569,7 +1347,7 @@ impl<'a> ZenOps<'a> {
 
                 pb.set_message(format!("Scanning {}", name));
 
-                // Get real python version from pyvenv.cfg
+                // Get real python version from pyvenv.cfg (or conda-meta)
                 let py_ver =
                     utils::read_python_version(path).unwrap_or_else(|| "unknown".to_string());
 
@@ -645,6 +1423,181 @@ impl<'a> ZenOps<'a> {
         Ok(out)
     }
 
+    /// Computes a structured package diff between two environments.
+    pub fn diff_envs(&self, first: &EnvName, second: &EnvName) -> Result<EnvDiff, Box<dyn Error>> {
+        let envs = self.db.list_envs()?;
+        let lookup = |name: &EnvName| {
+            envs.iter()
+                .find(|(n, ..)| n == name.as_str())
+                .map(|(_, path, py, ..)| {
+                    let pkgs = utils::get_packages(path)
+                        .into_iter()
+                        .map(|p| (p.name, p.version))
+                        .collect::<Vec<_>>();
+                    (py.clone(), pkgs)
+                })
+                .ok_or_else(|| self.env_not_found(name))
+        };
+        let (py1, pkgs1) = lookup(first)?;
+        let (py2, pkgs2) = lookup(second)?;
+        Ok(EnvDiff::compute(
+            (first.as_str(), &py1, pkgs1),
+            (second.as_str(), &py2, pkgs2),
+        ))
+    }
+
+    /// Diffs an environment against the template it was built from.
+    ///
+    /// `template_ref` uses the `--from` syntax; with several templates the
+    /// later ones override earlier packages, as in `zen create`. The template
+    /// is the first side of the diff, so `only_in_first` lists packages
+    /// missing from the env and `only_in_second` lists additions. Unpinned
+    /// template packages match any installed version, and the bootstrap
+    /// packages are ignored unless the template lists them.
+    pub fn compare_to_template(
+        &self,
+        env_name: &EnvName,
+        template_ref: &str,
+    ) -> Result<EnvDiff, Box<dyn Error>> {
+        let envs = self.db.list_envs()?;
+        let (_, env_path, env_py, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        let installed: std::collections::HashMap<String, Option<String>> =
+            utils::get_packages(env_path)
+                .into_iter()
+                .map(|p| (utils::normalize_package_name(&p.name), p.version))
+                .collect();
+
+        let mut labels = Vec::new();
+        let mut t_py = None;
+        let mut template_pkgs = Vec::new();
+        for part in utils::parse_template_string(template_ref) {
+            let t_id = self
+                .db
+                .get_template_id(&part.name, &part.version)?
+                .ok_or_else(|| format!("Template '{}:{}' not found", part.name, part.version))?;
+            // The first template decides the python version, as in `zen create`
+            if t_py.is_none() {
+                t_py = self.db.get_template_by_id(t_id)?.map(|(.., py)| py);
+            }
+            for (name, version, is_pinned, ..) in self.db.get_template_packages(t_id)? {
+                let norm = utils::normalize_package_name(&name);
+                let version = match installed.get(&norm) {
+                    Some(v) if !is_pinned => v.clone(),
+                    _ => Some(version),
+                };
+                template_pkgs.push((norm, version));
+            }
+            labels.push(format!("{}:{}", part.name, part.version));
+        }
+        let t_py = t_py.unwrap_or_default();
+        // Dependencies pulled in by the template's packages aren't drift either
+        let graph = utils::dependency_graph(env_path);
+        let mut implied = std::collections::HashSet::new();
+        let mut stack: Vec<&str> = template_pkgs.iter().map(|(n, _)| n.as_str()).collect();
+        while let Some(key) = stack.pop() {
+            for dep in graph
+                .get(key)
+                .map(|n| n.requires.as_slice())
+                .unwrap_or_default()
+            {
+                if implied.insert(dep.as_str()) {
+                    stack.push(dep);
+                }
+            }
+        }
+        let env_pkgs = installed
+            .into_iter()
+            .filter(|(n, _)| {
+                template_pkgs.iter().any(|(t, _)| t == n)
+                    || !(BOOTSTRAP_PACKAGES.contains(&n.as_str()) || implied.contains(n.as_str()))
+            })
+            .collect();
+        // Templates record the minor version only ("3.12"), so only flag a
+        // python mismatch when the env's version doesn't extend it.
+        let env_py = if env_py.starts_with(&t_py) {
+            t_py.clone()
+        } else {
+            env_py.clone()
+        };
+        Ok(EnvDiff::compute(
+            (&labels.join(","), &t_py, template_pkgs),
+            (env_name.as_str(), &env_py, env_pkgs),
+        ))
+    }
+
+    /// Saves an environment's current package set as snapshot `label`.
+    ///
+    /// Returns the number of packages captured.
+    pub fn create_snapshot(
+        &self,
+        env_name: &EnvName,
+        label: &str,
+    ) -> Result<usize, Box<dyn Error>> {
+        let envs = self.db.list_envs()?;
+        let (_, env_path, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        let packages = snapshot_packages(&utils::get_packages(env_path));
+        self.db.create_snapshot(env_name, label, &packages)?;
+        Ok(packages.len())
+    }
+
+    /// Works out what restoring snapshot `label` would change.
+    pub fn plan_snapshot_restore(
+        &self,
+        env_name: &EnvName,
+        label: &str,
+    ) -> Result<SnapshotPlan, Box<dyn Error>> {
+        let envs = self.db.list_envs()?;
+        let (_, env_path, ..) = envs
+            .iter()
+            .find(|(n, ..)| n == env_name.as_str())
+            .ok_or_else(|| self.env_not_found(env_name))?;
+        let snapshot = self
+            .db
+            .get_snapshot(env_name, label)?
+            .ok_or_else(|| format!("No snapshot '{}' for '{}'", label, env_name))?;
+        Ok(snapshot_plan(&snapshot, &utils::get_packages(env_path)))
+    }
+
+    /// Applies a restore plan: removals first, then (re)installs.
+    pub fn restore_snapshot(
+        &self,
+        env_name: &EnvName,
+        plan: &SnapshotPlan,
+    ) -> Result<(), Box<dyn Error>> {
+        if !plan.remove.is_empty() {
+            self.uninstall_packages(env_name, plan.remove.clone())?;
+        }
+        let retries = configured_install_retries(self.db);
+        if !plan.install.is_empty() {
+            self.install_packages(
+                env_name,
+                plan.install.clone(),
+                InstallOptions {
+                    retries,
+                    ..Default::default()
+                },
+            )?;
+        }
+        if !plan.install_editable.is_empty() {
+            self.install_packages(
+                env_name,
+                plan.install_editable.clone(),
+                InstallOptions {
+                    editable: true,
+                    retries,
+                    ..Default::default()
+                },
+            )?;
+        }
+        Ok(())
+    }
+
     /// Runs a full health check on an environment.
     ///
     /// Checks: python binary, site-packages, CUDA consistency, dependency conflicts.
@@ -653,7 +1606,7 @@ impl<'a> ZenOps<'a> {
         let (_, path, ..) = envs
             .iter()
             .find(|(n, ..)| n == env_name.as_str())
-            .ok_or_else(|| format!("Environment '{}' not found", env_name))?;
+            .ok_or_else(|| self.env_not_found(env_name))?;
 
         let env_path = std::path::Path::new(path);
         let mut report = HealthReport::default();
@@ -683,15 +1636,20 @@ impl<'a> ZenOps<'a> {
             report.push(HealthDiagnostic::PythonMissing);
         }
 
-        // 2. site-packages directory exists
-        if utils::get_site_packages_path(env_path).is_some() {
+        // 2. site-packages exists and holds package metadata.
+        // Only `uv venv` legitimately leaves it empty; stdlib venv always seeds pip.
+        let packages = utils::get_packages(path);
+        if utils::get_site_packages_path(env_path).is_none() {
+            report.push(HealthDiagnostic::SitePackagesMissing);
+        } else if !packages.is_empty() {
             report.push(HealthDiagnostic::SitePackagesOk);
+        } else if utils::created_by_uv(env_path) {
+            report.push(HealthDiagnostic::SitePackagesEmpty);
         } else {
-            report.push(HealthDiagnostic::SitePackagesMissing);
+            report.push(HealthDiagnostic::SitePackagesNoMetadata);
         }
 
-        // 3. Package scan + CUDA version consistency
-        let packages = utils::get_packages(path);
+        // 3. CUDA version consistency
 
         let mut cuda_versions: std::collections::HashMap<String, Vec<String>> =
             std::collections::HashMap::new();
@@ -736,6 +1694,34 @@ impl<'a> ZenOps<'a> {
             });
         }
 
+        // Torch builds should match the variant later installs pull from
+        if let Some(variant) = self.db.get_cuda_variant(env_name.as_str())? {
+            let mismatched: Vec<String> = packages
+                .iter()
+                .filter(|p| matches!(p.name.as_str(), "torch" | "torchvision" | "torchaudio"))
+                .filter_map(|p| {
+                    let ver = p.version.as_deref()?;
+                    let suffix = ver.split_once('+').map(|(_, s)| s).unwrap_or("");
+                    (suffix != variant).then(|| format!("{}=={}", p.name, ver))
+                })
+                .collect();
+            if !mismatched.is_empty() {
+                report.push(HealthDiagnostic::CudaVariantMismatch {
+                    variant,
+                    packages: mismatched,
+                });
+            }
+        }
+
+        // numpy 2.x breaks torch wheels built against the NumPy 1.x ABI
+        if let Some((numpy, torch)) = utils::numpy_torch_abi_mismatch(env_path, &packages) {
+            report.push(HealthDiagnostic::NumpyTorchAbi { numpy, torch });
+        }
+
+        for diagnostic in editable_source_issues(&packages) {
+            report.push(diagnostic);
+        }
+
         // 4. Native dependency check (no subprocess — learned from pip & uv)
         let dep_issues = utils::check_dependencies(env_path);
         if dep_issues.is_empty() {
@@ -789,10 +1775,184 @@ impl<'a> ZenOps<'a> {
     }
 }
 
+/// Requirement that reinstalls exactly this build of an installed package.
+///
+/// Git installs become direct references (`name @ git+url@commit`), local
+/// installs `name @ file://...` and editables `-e path`; the rest `name==version`.
+fn pinned_requirement(pkg: &PackageMetadata) -> String {
+    match pkg.source_url.as_deref() {
+        Some(url) if url.starts_with("file://") && pkg.is_editable => {
+            format!("-e {}", url.trim_start_matches("file://"))
+        }
+        Some(url) if url.starts_with("file://") => format!("{} @ {}", pkg.name, url),
+        Some(url) if pkg.install_source.as_deref() == Some("git") => {
+            let mut vcs = if url.starts_with("git+") {
+                url.to_string()
+            } else {
+                format!("git+{}", url)
+            };
+            if let Some(commit) = &pkg.commit_id {
+                vcs.push_str(&format!("@{}", commit));
+            }
+            format!("{} @ {}", pkg.name, vcs)
+        }
+        _ => format!("{}=={}", pkg.name, pkg.version.as_deref().unwrap_or("0")),
+    }
+}
+
+/// Changes that bring an environment back to a snapshot.
+#[derive(Debug, Default, PartialEq)]
+pub struct SnapshotPlan {
+    /// Requirements that are missing or installed at another version.
+    pub install: Vec<String>,
+    /// Editable sources to reinstall with `-e`.
+    pub install_editable: Vec<String>,
+    /// Installed packages the snapshot doesn't have.
+    pub remove: Vec<String>,
+}
+
+impl SnapshotPlan {
+    /// True when the environment already matches the snapshot.
+    pub fn is_empty(&self) -> bool {
+        self.install.is_empty() && self.install_editable.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Snapshot entries for an environment's installed packages, sorted by name.
+///
+/// pip, setuptools, uv and wheel are left out, like in `zen freeze`.
+pub fn snapshot_packages(installed: &[PackageMetadata]) -> Vec<SnapshotPackage> {
+    let mut packages: Vec<SnapshotPackage> = installed
+        .iter()
+        .filter(|p| !BOOTSTRAP_PACKAGES.contains(&utils::normalize_package_name(&p.name).as_str()))
+        .map(|p| SnapshotPackage {
+            name: p.name.clone(),
+            version: p.version.clone().unwrap_or_else(|| "0".to_string()),
+            requirement: pinned_requirement(p),
+        })
+        .collect();
+    packages.sort_by_key(|p| p.name.to_lowercase());
+    packages
+}
+
+/// Requirements that rebuild an environment's packages elsewhere (`zen create --copy-from`).
+///
+/// Returns `(requirements, editable_paths)`. With `strict` every index package
+/// is pinned `name==version`; otherwise it becomes `name>=version` (local
+/// build tags dropped) so the copy gets the newest matching release. Git,
+/// local and editable installs always point at the same source.
+pub fn copy_requirements(
+    installed: &[PackageMetadata],
+    strict: bool,
+) -> (Vec<String>, Vec<String>) {
+    let mut requirements = Vec::new();
+    let mut editables = Vec::new();
+    for pkg in snapshot_packages(installed) {
+        if let Some(path) = pkg.requirement.strip_prefix("-e ") {
+            editables.push(path.to_string());
+        } else if strict || pkg.requirement != format!("{}=={}", pkg.name, pkg.version) {
+            requirements.push(pkg.requirement);
+        } else {
+            let base = pkg.version.split('+').next().unwrap_or(&pkg.version);
+            requirements.push(format!("{}>={}", pkg.name, base));
+        }
+    }
+    (requirements, editables)
+}
+
+/// Diffs a snapshot against what is installed now.
+///
+/// Bootstrap tools are never removed, even when the snapshot predates them.
+pub fn snapshot_plan(snapshot: &[SnapshotPackage], installed: &[PackageMetadata]) -> SnapshotPlan {
+    let current = snapshot_packages(installed);
+    let mut plan = SnapshotPlan::default();
+    for want in snapshot {
+        let norm = utils::normalize_package_name(&want.name);
+        let matches = current.iter().any(|have| {
+            utils::normalize_package_name(&have.name) == norm
+                && have.version == want.version
+                && have.requirement == want.requirement
+        });
+        if matches {
+            continue;
+        }
+        match want.requirement.strip_prefix("-e ") {
+            Some(path) => plan.install_editable.push(path.to_string()),
+            None => plan.install.push(want.requirement.clone()),
+        }
+    }
+    for have in &current {
+        let norm = utils::normalize_package_name(&have.name);
+        if !snapshot
+            .iter()
+            .any(|want| utils::normalize_package_name(&want.name) == norm)
+        {
+            plan.remove.push(have.name.clone());
+        }
+    }
+    plan
+}
+
+/// Network retries for installs when `--retries` is not given.
+pub const DEFAULT_INSTALL_RETRIES: u32 = 2;
+
+/// Reads the `install_retries` config key, falling back to [`DEFAULT_INSTALL_RETRIES`].
+pub fn configured_install_retries(db: &Database) -> u32 {
+    db.get_config("install_retries")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_INSTALL_RETRIES)
+}
+
+/// Finds uv on PATH unless the `prefer_uv` config key is set to `false`.
+fn resolve_uv(db: &Database) -> Option<PathBuf> {
+    let preferred = db.get_config("prefer_uv").ok().flatten();
+    if preferred.as_deref() == Some("false") {
+        return None;
+    }
+    which::which("uv").ok()
+}
+
+/// Markers zen writes on comments itself (`Env: <name>`, `General`, `note`),
+/// as opposed to user-chosen tags.
+fn is_auto_note_tag(tag: &str) -> bool {
+    tag.starts_with("Env: ") || tag == "General" || tag == "note"
+}
+
+/// Formats an `EnvCleanup` as a ` (3 links, 2 labels cleaned)` suffix, or nothing.
+fn cleanup_suffix(cleanup: &crate::db::EnvCleanup) -> String {
+    cleanup
+        .describe()
+        .map(|d| format!(" ({})", d))
+        .unwrap_or_default()
+}
+
+/// Editable installs whose source directory is gone or no longer a project.
+///
+/// Reads the `file://` URL pip/uv recorded in `direct_url.json`.
+fn editable_source_issues(packages: &[PackageMetadata]) -> Vec<HealthDiagnostic> {
+    packages
+        .iter()
+        .filter_map(|pkg| {
+            let path = utils::editable_source_path(pkg)?;
+            let package = pkg.name.clone();
+            if !path.exists() {
+                Some(HealthDiagnostic::EditableSourceMissing { package, path })
+            } else if !utils::is_python_project(&path) {
+                Some(HealthDiagnostic::EditableSourceNotProject { package, path })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
 /// Quick health check on an environment path — returns just the overall level.
 ///
 /// Used by `zen list` for inline health indicators. No DB access needed.
-/// Checks: python binary, CUDA consistency, dependency conflicts.
+/// Checks: python binary, site-packages contents, CUDA consistency, numpy/torch ABI,
+/// editable sources, dependency conflicts.
 pub fn check_health_quick(env_path: &std::path::Path) -> HealthLevel {
     // 1. Python binary
     let python_bin = env_path.join("bin/python");
@@ -807,13 +1967,17 @@ pub fn check_health_quick(env_path: &std::path::Path) -> HealthLevel {
         return HealthLevel::Fail;
     }
 
-    // 2. site-packages
+    // 2. site-packages — present, and empty only for a fresh uv venv
     if utils::get_site_packages_path(env_path).is_none() {
         return HealthLevel::Fail;
     }
+    let packages = utils::get_packages(env_path);
+    let is_empty = packages.is_empty();
+    if is_empty && !utils::created_by_uv(env_path) {
+        return HealthLevel::Fail;
+    }
 
     // 3. CUDA consistency (fast — uses already-scanned packages)
-    let packages = utils::get_packages(env_path);
     let mut cuda_suffixes: std::collections::HashSet<String> = std::collections::HashSet::new();
     for pkg in &packages {
         if let Some(ver) = &pkg.version
@@ -828,6 +1992,8 @@ pub fn check_health_quick(env_path: &std::path::Path) -> HealthLevel {
     let has_mixed_cuda = cuda_suffixes.iter().filter(|s| s.starts_with("cu")).count() > 1;
     let has_cpu_and_cuda =
         cuda_suffixes.contains("cpu") && cuda_suffixes.iter().any(|s| s.starts_with("cu"));
+    let has_abi_mismatch = utils::numpy_torch_abi_mismatch(env_path, &packages).is_some();
+    let has_broken_editable = !editable_source_issues(&packages).is_empty();
 
     // 4. Dependency check — categorize by severity
     let dep_issues = utils::check_dependencies(env_path);
@@ -838,9 +2004,14 @@ pub fn check_health_quick(env_path: &std::path::Path) -> HealthLevel {
         .iter()
         .any(|i| matches!(i, utils::DepIssue::Missing { .. }));
 
-    if has_mixed_cuda || has_cpu_and_cuda || has_conflicts {
+    if has_mixed_cuda
+        || has_cpu_and_cuda
+        || has_abi_mismatch
+        || has_broken_editable
+        || has_conflicts
+    {
         HealthLevel::Warn
-    } else if has_missing {
+    } else if has_missing || is_empty {
         HealthLevel::Info
     } else {
         HealthLevel::Pass
@@ -7,8 +7,130 @@
 //! when running in MCP mode.
 //!
 //! Inspired by uv's `Printer` enum (Silent/Quiet/Default/Verbose/NoProgress).
+//!
+//! The palette is theme-aware: `Tone` names a role (accent, warning, …) and
+//! the active `Theme` decides the actual RGB, so call sites never hardcode
+//! colors. `init_theme` also honors `NO_COLOR` and `--no-color`.
+//!
+//! `OutputFormat` (`--output`) lets row-oriented commands print JSON or CSV
+//! instead of their usual tables, through `render_records`.
+
+use colored::{ColoredString, Colorize};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+/// Terminal color theme, set via `zen config theme <dark|light|mono>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Bright pastels for dark backgrounds (default).
+    Dark,
+    /// Deeper tones that stay readable on light backgrounds.
+    Light,
+    /// No colors at all.
+    Mono,
+}
+
+impl Theme {
+    /// Parses a theme name (case-insensitive).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dark" => Some(Theme::Dark),
+            "light" => Some(Theme::Light),
+            "mono" | "none" => Some(Theme::Mono),
+            _ => None,
+        }
+    }
+}
+
+static THEME: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the active theme.
+pub fn theme() -> Theme {
+    match THEME.load(Ordering::Relaxed) {
+        1 => Theme::Light,
+        2 => Theme::Mono,
+        _ => Theme::Dark,
+    }
+}
+
+/// Sets the active theme. `Mono` also switches off all `colored` output.
+pub fn set_theme(theme: Theme) {
+    let value = match theme {
+        Theme::Dark => 0,
+        Theme::Light => 1,
+        Theme::Mono => 2,
+    };
+    THEME.store(value, Ordering::Relaxed);
+    if theme == Theme::Mono {
+        colored::control::set_override(false);
+    }
+}
+
+/// Resolves the theme at startup from the `theme` config value, `--no-color`
+/// and the `NO_COLOR` convention (any non-empty value disables color).
+pub fn init_theme(configured: Option<&str>, no_color: bool) {
+    let no_color_env = std::env::var("NO_COLOR").is_ok_and(|v| !v.is_empty());
+    let theme = if no_color || no_color_env {
+        Theme::Mono
+    } else {
+        configured.and_then(Theme::parse).unwrap_or(Theme::Dark)
+    };
+    set_theme(theme);
+}
+
+/// Semantic color roles of the Zen palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tone {
+    /// Zen Blue — success, healthy, primary accent.
+    Accent,
+    /// Peace Pink — minor issues, info.
+    Minor,
+    /// Stressed Orange — warnings, drift.
+    Warn,
+    /// Teal — dashboard chrome, counts.
+    Muted,
+    /// Gold — favorites.
+    Fav,
+}
+
+impl Tone {
+    /// RGB for this role under the active theme.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match (theme(), self) {
+            (Theme::Light, Tone::Accent) => (0, 110, 180),
+            (Theme::Light, Tone::Minor) => (190, 60, 120),
+            (Theme::Light, Tone::Warn) => (190, 90, 0),
+            (Theme::Light, Tone::Muted) => (30, 110, 110),
+            (Theme::Light, Tone::Fav) => (160, 120, 0),
+            (_, Tone::Accent) => (100, 200, 255),
+            (_, Tone::Minor) => (255, 182, 193),
+            (_, Tone::Warn) => (255, 140, 0),
+            (_, Tone::Muted) => (100, 160, 160),
+            (_, Tone::Fav) => (255, 215, 0),
+        }
+    }
+}
+
+/// Paints text with a palette role instead of a hardcoded RGB.
+pub trait Themed {
+    /// Foreground color for `tone`.
+    fn tone(self, tone: Tone) -> ColoredString;
+    /// Background color for `tone`.
+    fn on_tone(self, tone: Tone) -> ColoredString;
+}
+
+impl<T: Colorize> Themed for T {
+    fn tone(self, tone: Tone) -> ColoredString {
+        let (r, g, b) = tone.rgb();
+        self.truecolor(r, g, b)
+    }
 
-use owo_colors::OwoColorize;
+    fn on_tone(self, tone: Tone) -> ColoredString {
+        let (r, g, b) = tone.rgb();
+        self.on_truecolor(r, g, b)
+    }
+}
 
 /// Controls all zen terminal output.
 ///
@@ -36,21 +158,21 @@ impl Printer {
     /// Print a success message (Zen Blue ✓ prefix).
     pub fn success(&self, msg: &str) {
         if *self == Self::Default {
-            println!("  {} {}", "✓".truecolor(100, 200, 255), msg);
+            println!("  {} {}", "✓".tone(Tone::Accent), msg);
         }
     }
 
     /// Print an info message (Peace Pink △ prefix).
     pub fn info(&self, msg: &str) {
         if *self == Self::Default {
-            println!("  {} {}", "△".truecolor(255, 182, 193), msg);
+            println!("  {} {}", "△".tone(Tone::Minor), msg);
         }
     }
 
     /// Print a warning message (Stressed Orange ⚠ prefix).
     pub fn warning(&self, msg: &str) {
         if *self == Self::Default {
-            eprintln!("  {} {}", "⚠".truecolor(255, 140, 0), msg);
+            eprintln!("  {} {}", "⚠".tone(Tone::Warn), msg);
         }
     }
 
@@ -75,3 +197,108 @@ impl Printer {
         }
     }
 }
+
+/// How row-oriented commands (`list`, `diff`, `find`, `inspect`) print, via `--output`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// The usual human-readable output
+    #[default]
+    Table,
+    /// An array with one object per row
+    Json,
+    /// A header line, then one line per row (for spreadsheets)
+    Csv,
+}
+
+/// Renders rows (one value per header) as JSON or CSV.
+///
+/// Returns `None` for `Table`, which each command draws itself. In CSV, null
+/// is an empty field and arrays are joined with `;`.
+pub fn render_records(
+    format: OutputFormat,
+    headers: &[&str],
+    rows: &[Vec<serde_json::Value>],
+) -> Option<String> {
+    match format {
+        OutputFormat::Table => None,
+        OutputFormat::Json => {
+            let records: Vec<serde_json::Map<String, serde_json::Value>> = rows
+                .iter()
+                .map(|row| {
+                    headers
+                        .iter()
+                        .map(|h| h.to_string())
+                        .zip(row.iter().cloned())
+                        .collect()
+                })
+                .collect();
+            Some(serde_json::to_string_pretty(&records).unwrap_or_default() + "\n")
+        }
+        OutputFormat::Csv => {
+            let mut out = csv_line(headers.iter().map(|h| h.to_string()));
+            for row in rows {
+                out.push_str(&csv_line(row.iter().map(csv_text)));
+            }
+            Some(out)
+        }
+    }
+}
+
+/// The text of one CSV field.
+fn csv_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => items.iter().map(csv_text).collect::<Vec<_>>().join(";"),
+        other => other.to_string(),
+    }
+}
+
+/// One CSV line; fields with commas, quotes or line breaks are quoted (RFC 4180).
+fn csv_line(fields: impl Iterator<Item = String>) -> String {
+    let fields: Vec<String> = fields
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f
+            }
+        })
+        .collect();
+    fields.join(",") + "\n"
+}
+
+/// A stderr spinner for slow scans like the `zen list` pre-scan.
+///
+/// Hidden when stderr isn't a terminal (indicatif's default) and under the
+/// `Mono` theme (`--no-color` / `NO_COLOR`); cleared when finished or dropped.
+pub struct Spinner(ProgressBar);
+
+impl Spinner {
+    /// Starts spinning with `message`.
+    pub fn start(message: &str) -> Self {
+        if theme() == Theme::Mono {
+            return Self(ProgressBar::hidden());
+        }
+        let pb = ProgressBar::new_spinner();
+        if let Ok(style) = ProgressStyle::default_spinner().template("{spinner:.cyan} {msg}") {
+            pb.set_style(style);
+        }
+        pb.set_message(message.to_string());
+        pb.enable_steady_tick(Duration::from_millis(80));
+        Self(pb)
+    }
+
+    /// Stops the spinner and clears its line before results are printed.
+    pub fn finish(self) {
+        self.0.finish_and_clear();
+    }
+}
+
+impl Drop for Spinner {
+    fn drop(&mut self) {
+        if !self.0.is_finished() {
+            self.0.finish_and_clear();
+        }
+    }
+}
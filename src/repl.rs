@@ -21,7 +21,9 @@ use crate::utils;
 pub struct Pkg {
     pub name: String,
     pub version: String,
-    pub install_type: String, // "pypi" or "wheel"
+    pub install_type: String,         // "pypi" or "wheel"
+    pub extras: Option<String>,       // e.g. "cuda,audio" for pkg[cuda,audio]
+    pub version_spec: Option<String>, // e.g. "==2.1.0" or ">=1.26,<2"
 }
 
 #[derive(Clone)]
@@ -295,7 +297,7 @@ pub fn print_status(steps: &[Step], template_name: &str, template_version: &str)
         for pkg in &step.packages {
             println!(
                 "    {:<24}{:<20}{}",
-                pkg.name,
+                utils::with_extras(&pkg.name, pkg.extras.as_deref()),
                 pkg.version,
                 pkg.install_type.dimmed()
             );
@@ -426,17 +428,16 @@ pub fn handle_add(
             name: pkg_name,
             version,
             install_type: "wheel".to_string(),
+            extras: None,
+            version_spec: None,
         });
     } else {
         for pkg in &pkgs_to_install {
-            let base_name = pkg
-                .split(&['>', '<', '=', '!', '~'][..])
-                .next()
-                .unwrap_or(pkg);
+            let base_name = utils::requirement_name(pkg);
             let show_args: Vec<&str> = if use_uv {
-                vec!["pip", "show", base_name]
+                vec!["pip", "show", &base_name]
             } else {
-                vec!["show", base_name]
+                vec!["show", &base_name]
             };
             let (_ok, stdout, _stderr) =
                 utils::run_in_env_capture(env_path, if use_uv { "uv" } else { "pip" }, &show_args);
@@ -447,9 +448,11 @@ pub fn handle_add(
                 .unwrap_or_else(|| "unknown".to_string());
             println!("  {} {} {}", "✓".green(), base_name, version.dimmed());
             new_pkgs.push(Pkg {
-                name: base_name.to_string(),
+                name: base_name,
+                version_spec: Some(format!("=={}", version)),
                 version,
                 install_type: "pypi".to_string(),
+                extras: utils::requirement_extras(pkg),
             });
         }
     }
@@ -553,6 +556,8 @@ pub fn handle_save(
                 &pkg.install_type,
                 step.install_args.as_deref(),
                 step_idx as i64,
+                pkg.extras.as_deref(),
+                pkg.version_spec.as_deref(),
             )?;
         }
     }
@@ -573,7 +578,7 @@ pub fn load_steps_from_db(db: &Database, template_id: i64) -> Vec<Step> {
     let mut steps: Vec<Step> = Vec::new();
     if let Ok(packages) = db.get_template_packages(template_id) {
         let mut current_step: i64 = -1;
-        for (p_name, p_ver, _pinned, itype, iargs, step_num) in &packages {
+        for (p_name, p_ver, _pinned, itype, iargs, step_num, extras, version_spec) in &packages {
             if *step_num != current_step {
                 current_step = *step_num;
                 steps.push(Step {
@@ -586,6 +591,8 @@ pub fn load_steps_from_db(db: &Database, template_id: i64) -> Vec<Step> {
                     name: p_name.clone(),
                     version: p_ver.clone(),
                     install_type: itype.clone(),
+                    extras: extras.clone(),
+                    version_spec: version_spec.clone(),
                 });
             }
         }
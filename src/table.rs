@@ -7,7 +7,9 @@
 
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL_CONDENSED;
-use comfy_table::{ContentArrangement, Table};
+use comfy_table::{Cell, Color, ContentArrangement, Table};
+
+use crate::types::HealthLevel;
 
 /// Creates a new styled table with consistent formatting.
 ///
@@ -28,3 +30,183 @@ pub fn new_table_with_headers(headers: Vec<&str>) -> Table {
     table.set_header(headers);
     table
 }
+
+/// The colored health symbol shown in `zen list` tables.
+pub fn health_cell(health: HealthLevel) -> Cell {
+    match health {
+        HealthLevel::Pass => Cell::new("✓").fg(Color::Rgb {
+            r: 100,
+            g: 200,
+            b: 255,
+        }),
+        HealthLevel::Info => Cell::new("△").fg(Color::Rgb {
+            r: 255,
+            g: 182,
+            b: 193,
+        }),
+        HealthLevel::Warn => Cell::new("!").fg(Color::Red),
+        HealthLevel::Fail => Cell::new("✗").fg(Color::Red),
+    }
+}
+
+/// Layout used by `zen list`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFormat {
+    Minimal,
+    Compact,
+    Wide,
+}
+
+impl ListFormat {
+    /// Picks a layout for the given terminal width.
+    ///
+    /// Below 100 columns → Minimal, 100–180 → Compact, above 180 → Wide.
+    /// No width (pipes, non-TTY) falls back to Minimal.
+    pub fn for_width(width: Option<u16>) -> Self {
+        match width {
+            Some(w) if w < 100 => ListFormat::Minimal,
+            Some(w) if w <= 180 => ListFormat::Compact,
+            Some(_) => ListFormat::Wide,
+            None => ListFormat::Minimal,
+        }
+    }
+}
+
+/// A column `zen list --columns` can show.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListColumn {
+    Name,
+    Python,
+    Health,
+    LastUsed,
+    Path,
+    Size,
+    /// Version of a tracked stack package (the `stack_info` config).
+    Package(String),
+}
+
+impl ListColumn {
+    pub fn header(&self) -> &str {
+        match self {
+            ListColumn::Name => "Name",
+            ListColumn::Python => "Py",
+            ListColumn::Health => "Health",
+            ListColumn::LastUsed => "Last used",
+            ListColumn::Path => "Path",
+            ListColumn::Size => "Size",
+            ListColumn::Package(name) => name,
+        }
+    }
+}
+
+/// Parses a `--columns` spec such as `name,py,health,torch,size`.
+///
+/// `minimal`, `compact` and `wide` expand to the columns of those layouts, so
+/// `wide,size` works. Package columns must be in `tracked`. Repeats are dropped.
+pub fn parse_list_columns(spec: &str, tracked: &[&str]) -> Result<Vec<ListColumn>, String> {
+    let mut columns = Vec::new();
+    for word in spec.split(',').map(str::trim).filter(|w| !w.is_empty()) {
+        let expanded = match word.to_lowercase().as_str() {
+            "name" => vec![ListColumn::Name],
+            "py" | "python" => vec![ListColumn::Python],
+            "health" => vec![ListColumn::Health],
+            "last-used" | "last_used" | "used" => vec![ListColumn::LastUsed],
+            "path" => vec![ListColumn::Path],
+            "size" => vec![ListColumn::Size],
+            "minimal" | "compact" => [ListColumn::Name, ListColumn::Python, ListColumn::Health]
+                .into_iter()
+                .chain(
+                    tracked
+                        .iter()
+                        .take(2)
+                        .map(|k| ListColumn::Package(k.to_string())),
+                )
+                .collect(),
+            "wide" => [
+                ListColumn::Name,
+                ListColumn::Python,
+                ListColumn::Health,
+                ListColumn::LastUsed,
+                ListColumn::Path,
+            ]
+            .into_iter()
+            .chain(tracked.iter().map(|k| ListColumn::Package(k.to_string())))
+            .collect(),
+            other => match tracked.iter().find(|k| k.eq_ignore_ascii_case(other)) {
+                Some(key) => vec![ListColumn::Package(key.to_string())],
+                None => {
+                    let mut known = vec!["name", "py", "health", "last-used", "path", "size"];
+                    known.extend(tracked);
+                    return Err(format!(
+                        "Unknown column '{}'. Columns: {} (or the presets minimal, compact, wide)",
+                        word,
+                        known.join(", ")
+                    ));
+                }
+            },
+        };
+        for column in expanded {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+    }
+    if columns.is_empty() {
+        return Err("No columns given".to_string());
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_format_for_width() {
+        assert_eq!(ListFormat::for_width(None), ListFormat::Minimal);
+        assert_eq!(ListFormat::for_width(Some(40)), ListFormat::Minimal);
+        assert_eq!(ListFormat::for_width(Some(99)), ListFormat::Minimal);
+        assert_eq!(ListFormat::for_width(Some(100)), ListFormat::Compact);
+        assert_eq!(ListFormat::for_width(Some(180)), ListFormat::Compact);
+        assert_eq!(ListFormat::for_width(Some(181)), ListFormat::Wide);
+        assert_eq!(ListFormat::for_width(Some(300)), ListFormat::Wide);
+    }
+
+    #[test]
+    fn test_parse_list_columns() {
+        let tracked = ["torch", "numpy", "transformers"];
+        let pkg = |k: &str| ListColumn::Package(k.to_string());
+        assert_eq!(
+            parse_list_columns("name, PY ,health,numpy,size", &tracked).unwrap(),
+            vec![
+                ListColumn::Name,
+                ListColumn::Python,
+                ListColumn::Health,
+                pkg("numpy"),
+                ListColumn::Size,
+            ]
+        );
+        assert_eq!(
+            parse_list_columns("compact,path,name", &tracked).unwrap(),
+            vec![
+                ListColumn::Name,
+                ListColumn::Python,
+                ListColumn::Health,
+                pkg("torch"),
+                pkg("numpy"),
+                ListColumn::Path,
+            ]
+        );
+        assert_eq!(
+            parse_list_columns("wide", &tracked).unwrap().len(),
+            5 + tracked.len()
+        );
+        let err = parse_list_columns("name,pandas", &tracked).unwrap_err();
+        assert!(
+            err.contains("'pandas'") && err.contains("transformers"),
+            "{}",
+            err
+        );
+        assert!(parse_list_columns(" , ", &tracked).is_err());
+    }
+}
@@ -191,7 +191,9 @@ pub trait Diagnostic {
 /// Severity level for health check results.
 ///
 /// Ordered by severity: Pass < Info < Warn < Fail.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+/// Serializes as lowercase `pass` / `info` / `warn` / `fail`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum HealthLevel {
     /// Environment is fully healthy.
     #[default]
@@ -246,6 +248,10 @@ pub enum HealthDiagnostic {
     SitePackagesOk,
     /// site-packages directory is missing.
     SitePackagesMissing,
+    /// site-packages exists but no packages yet (fresh `uv venv`).
+    SitePackagesEmpty,
+    /// site-packages has no readable .dist-info (half-created venv).
+    SitePackagesNoMetadata,
     /// All CUDA packages use the same backend.
     CudaConsistent { suffix: String },
     /// Mixed CUDA versions detected.
@@ -258,6 +264,17 @@ pub enum HealthDiagnostic {
     MissingDependencies { count: usize, details: String },
     /// Version conflicts (warn-level).
     VersionConflicts { count: usize, details: String },
+    /// numpy 2.x next to a torch built against NumPy 1.x.
+    NumpyTorchAbi { numpy: String, torch: String },
+    /// PyTorch packages built for a different variant than the env records.
+    CudaVariantMismatch {
+        variant: String,
+        packages: Vec<String>,
+    },
+    /// Editable install whose source directory was moved or deleted.
+    EditableSourceMissing { package: String, path: PathBuf },
+    /// Editable install whose source directory no longer holds a Python project.
+    EditableSourceNotProject { package: String, path: PathBuf },
 }
 
 impl Diagnostic for HealthDiagnostic {
@@ -270,11 +287,25 @@ impl Diagnostic for HealthDiagnostic {
             }
             Self::SitePackagesOk => "site-packages OK".to_string(),
             Self::SitePackagesMissing => "site-packages directory missing".to_string(),
+            Self::SitePackagesEmpty => {
+                "site-packages empty (fresh venv, nothing installed)".to_string()
+            }
+            Self::SitePackagesNoMetadata => {
+                "site-packages has no package metadata (half-created venv?) — recreate it"
+                    .to_string()
+            }
             Self::CudaConsistent { suffix } => {
                 format!("CUDA consistency OK (all packages use +{})", suffix)
             }
             Self::CudaMismatch { details } => details.clone(),
             Self::CpuCudaConflict { details } => details.clone(),
+            Self::CudaVariantMismatch { variant, packages } => format!(
+                "Environment is {} but {} {} a different build (reinstall with: zen install torch-{})",
+                variant,
+                packages.join(", "),
+                if packages.len() == 1 { "is" } else { "are" },
+                variant
+            ),
             Self::DependenciesOk => "Dependencies OK (all Requires-Dist satisfied)".to_string(),
             Self::MissingDependencies { count, details } => {
                 format!(
@@ -292,6 +323,22 @@ impl Diagnostic for HealthDiagnostic {
                     details
                 )
             }
+            Self::NumpyTorchAbi { numpy, torch } => format!(
+                "numpy {} is incompatible with torch {} (built against NumPy 1.x)\n    \
+                 fix: pin \"numpy<2\" or upgrade torch to >=2.4",
+                numpy, torch
+            ),
+            Self::EditableSourceMissing { package, path } => format!(
+                "editable package {} points to missing path {} (imports will fail; \
+                 reinstall with zen install -e <new path>)",
+                package,
+                path.display()
+            ),
+            Self::EditableSourceNotProject { package, path } => format!(
+                "editable package {} points to {}, which no longer holds a Python project",
+                package,
+                path.display()
+            ),
         }
     }
 
@@ -301,13 +348,18 @@ impl Diagnostic for HealthDiagnostic {
             | Self::SitePackagesOk
             | Self::CudaConsistent { .. }
             | Self::DependenciesOk => HealthLevel::Pass,
-            Self::MissingDependencies { .. } => HealthLevel::Info,
+            Self::MissingDependencies { .. } | Self::SitePackagesEmpty => HealthLevel::Info,
             Self::CudaMismatch { .. }
             | Self::CpuCudaConflict { .. }
-            | Self::VersionConflicts { .. } => HealthLevel::Warn,
-            Self::PythonMissing | Self::BrokenSymlink { .. } | Self::SitePackagesMissing => {
-                HealthLevel::Fail
-            }
+            | Self::VersionConflicts { .. }
+            | Self::NumpyTorchAbi { .. }
+            | Self::CudaVariantMismatch { .. }
+            | Self::EditableSourceMissing { .. }
+            | Self::EditableSourceNotProject { .. } => HealthLevel::Warn,
+            Self::PythonMissing
+            | Self::BrokenSymlink { .. }
+            | Self::SitePackagesMissing
+            | Self::SitePackagesNoMetadata => HealthLevel::Fail,
         }
     }
 }
@@ -339,6 +391,19 @@ impl HealthReport {
             .unwrap_or(HealthLevel::Pass)
     }
 
+    /// Machine-friendly summary: overall level plus item counts per level.
+    pub fn summary(&self, env_name: &str) -> HealthSummary {
+        let count = |level: HealthLevel| self.items.iter().filter(|d| d.level() == level).count();
+        HealthSummary {
+            env_name: env_name.to_string(),
+            overall: self.overall().to_string(),
+            pass: count(HealthLevel::Pass),
+            info: count(HealthLevel::Info),
+            warn: count(HealthLevel::Warn),
+            fail: count(HealthLevel::Fail),
+        }
+    }
+
     /// Format as plain text for MCP/programmatic use.
     pub fn to_text(&self, env_name: &str) -> String {
         let mut out = format!("Health: {}\n", env_name);
@@ -348,6 +413,133 @@ impl HealthReport {
         out.push_str(&format!("\nOverall: {}", self.overall()));
         out
     }
+
+    /// Format as pretty JSON for CI gating: `overall` plus each item's level
+    /// and message, with levels as `pass` / `info` / `warn` / `fail`.
+    pub fn to_json(&self, env_name: &str) -> String {
+        let items: Vec<_> = self
+            .items
+            .iter()
+            .map(|d| serde_json::json!({ "level": d.level(), "message": d.message() }))
+            .collect();
+        let value = serde_json::json!({
+            "env_name": env_name,
+            "overall": self.overall(),
+            "items": items,
+        });
+        format!("{:#}", value)
+    }
+}
+
+/// Serializable health summary for gating decisions without parsing prose.
+///
+/// `overall` uses the `HealthLevel` display names: OK, MINOR, DRIFT, BROKEN.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct HealthSummary {
+    pub env_name: String,
+    pub overall: String,
+    pub pass: usize,
+    pub info: usize,
+    pub warn: usize,
+    pub fail: usize,
+}
+
+// =============================================================================
+// EnvDiff — structured two-way package diff
+// =============================================================================
+
+/// Structured package diff between two environments.
+///
+/// Package names are lowercased so `PyYAML` and `pyyaml` compare equal.
+/// Every section is sorted by package name.
+#[derive(Debug, Default, PartialEq)]
+pub struct EnvDiff {
+    pub first: String,
+    pub second: String,
+    pub python: (String, String),
+    /// Packages present in both with differing versions: (name, v1, v2).
+    pub changed: Vec<(String, String, String)>,
+    /// Packages only in the first environment: (name, version).
+    pub only_in_first: Vec<(String, String)>,
+    /// Packages only in the second environment: (name, version).
+    pub only_in_second: Vec<(String, String)>,
+}
+
+impl EnvDiff {
+    /// Diff two `(name, version)` package lists.
+    pub fn compute(
+        first: (&str, &str, Vec<(String, Option<String>)>),
+        second: (&str, &str, Vec<(String, Option<String>)>),
+    ) -> Self {
+        use std::collections::BTreeMap;
+        let to_map = |pkgs: Vec<(String, Option<String>)>| -> BTreeMap<String, String> {
+            pkgs.into_iter()
+                .map(|(n, v)| (n.to_lowercase(), v.unwrap_or_else(|| "?".into())))
+                .collect()
+        };
+        let pkgs1 = to_map(first.2);
+        let pkgs2 = to_map(second.2);
+
+        let mut diff = EnvDiff {
+            first: first.0.to_string(),
+            second: second.0.to_string(),
+            python: (first.1.to_string(), second.1.to_string()),
+            ..Default::default()
+        };
+        for (name, v1) in &pkgs1 {
+            match pkgs2.get(name) {
+                Some(v2) if v1 != v2 => diff.changed.push((name.clone(), v1.clone(), v2.clone())),
+                Some(_) => {}
+                None => diff.only_in_first.push((name.clone(), v1.clone())),
+            }
+        }
+        for (name, v2) in &pkgs2 {
+            if !pkgs1.contains_key(name) {
+                diff.only_in_second.push((name.clone(), v2.clone()));
+            }
+        }
+        diff
+    }
+
+    /// True when python versions and package sets are identical.
+    pub fn is_empty(&self) -> bool {
+        self.python.0 == self.python.1
+            && self.changed.is_empty()
+            && self.only_in_first.is_empty()
+            && self.only_in_second.is_empty()
+    }
+
+    /// Format as plain, line-oriented sections for MCP/programmatic use.
+    pub fn to_text(&self) -> String {
+        let (py1, py2) = &self.python;
+        let mut out = format!("Diff: {} vs {}\n", self.first, self.second);
+        if self.is_empty() {
+            out.push_str(&format!("python: {} (same)\nidentical", py1));
+            return out;
+        }
+        if py1 == py2 {
+            out.push_str(&format!("python: {} (same)\n", py1));
+        } else {
+            out.push_str(&format!("python: {} -> {}\n", py1, py2));
+        }
+
+        out.push_str(&format!("\n[changed] {}\n", self.changed.len()));
+        for (name, v1, v2) in &self.changed {
+            out.push_str(&format!("{} {} -> {}\n", name, v1, v2));
+        }
+        out.push_str(&format!("\n[only_in_first] {}\n", self.only_in_first.len()));
+        for (name, v) in &self.only_in_first {
+            out.push_str(&format!("{}=={}\n", name, v));
+        }
+        out.push_str(&format!(
+            "\n[only_in_second] {}\n",
+            self.only_in_second.len()
+        ));
+        for (name, v) in &self.only_in_second {
+            out.push_str(&format!("{}=={}\n", name, v));
+        }
+        out.trim_end().to_string()
+    }
 }
 
 // =============================================================================
@@ -422,6 +614,39 @@ mod tests {
 
         report.push(HealthDiagnostic::PythonMissing);
         assert_eq!(report.overall(), HealthLevel::Fail);
+
+        let summary = report.summary("env");
+        assert_eq!(summary.overall, "BROKEN");
+        assert_eq!(
+            (summary.pass, summary.info, summary.warn, summary.fail),
+            (1, 1, 0, 1)
+        );
+    }
+
+    #[test]
+    fn test_health_report_to_json() {
+        let mut report = HealthReport::default();
+        report.push(HealthDiagnostic::SitePackagesOk);
+        report.push(HealthDiagnostic::VersionConflicts {
+            count: 1,
+            details: "    foo".to_string(),
+        });
+        let json: serde_json::Value = serde_json::from_str(&report.to_json("env")).unwrap();
+        assert_eq!(json["env_name"], "env");
+        assert_eq!(json["overall"], "warn");
+        assert_eq!(json["items"][0]["level"], "pass");
+        assert_eq!(json["items"][0]["message"], "site-packages OK");
+        assert_eq!(json["items"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_numpy_torch_abi_is_warning() {
+        let d = HealthDiagnostic::NumpyTorchAbi {
+            numpy: "2.0.1".to_string(),
+            torch: "2.2.0".to_string(),
+        };
+        assert_eq!(d.level(), HealthLevel::Warn);
+        assert!(d.message().contains("numpy<2"));
     }
 
     #[test]
@@ -435,5 +660,46 @@ mod tests {
         let d = HealthDiagnostic::PythonMissing;
         assert!(d.message().contains("missing"));
         assert_eq!(d.level(), HealthLevel::Fail);
+
+        let d = HealthDiagnostic::EditableSourceMissing {
+            package: "mylib".to_string(),
+            path: PathBuf::from("/src/mylib"),
+        };
+        assert!(
+            d.message()
+                .starts_with("editable package mylib points to missing path /src/mylib")
+        );
+        assert_eq!(d.level(), HealthLevel::Warn);
+    }
+
+    #[test]
+    fn test_env_diff_sections() {
+        let pkgs = |v: &[(&str, &str)]| {
+            v.iter()
+                .map(|(n, ver)| (n.to_string(), Some(ver.to_string())))
+                .collect::<Vec<_>>()
+        };
+        let diff = EnvDiff::compute(
+            (
+                "a",
+                "3.11",
+                pkgs(&[("numpy", "1.26"), ("PyYAML", "6.0"), ("rich", "13.0")]),
+            ),
+            (
+                "b",
+                "3.12",
+                pkgs(&[("numpy", "2.0"), ("pyyaml", "6.0"), ("torch", "2.3")]),
+            ),
+        );
+        assert_eq!(
+            diff.changed,
+            vec![("numpy".into(), "1.26".into(), "2.0".into())]
+        );
+        assert_eq!(diff.only_in_first, vec![("rich".into(), "13.0".into())]);
+        assert_eq!(diff.only_in_second, vec![("torch".into(), "2.3".into())]);
+        assert!(!diff.is_empty());
+        let text = diff.to_text();
+        assert!(text.contains("python: 3.11 -> 3.12"));
+        assert!(text.contains("[changed] 1\nnumpy 1.26 -> 2.0"));
     }
 }
@@ -94,6 +94,53 @@ pub fn validate_cuda_version(version: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Validates an environment variable name (`[A-Za-z_][A-Za-z0-9_]*`).
+///
+/// Keys are exported by the shell hook, so anything else could break or
+/// inject into the generated shell code.
+pub fn validate_env_var_key(key: &str) -> Result<(), String> {
+    let mut chars = key.chars();
+    let valid_start = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_');
+    if !valid_start || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(format!(
+            "Invalid variable name '{}' (use letters, digits and _, not starting with a digit)",
+            key
+        ));
+    }
+    Ok(())
+}
+
+/// Validates a value for a config key Zen knows the format of.
+///
+/// Keys with free-form values (and unknown keys) accept anything.
+pub fn validate_config_value(key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "theme" if crate::printer::Theme::parse(value).is_none() => Err(format!(
+            "Invalid theme '{}'. Use dark, light, or mono.",
+            value
+        )),
+        "default_python" => validate_python_version(value)
+            .map_err(|e| format!("Invalid default_python '{}': {}", value, e)),
+        "install_retries" if value.parse::<u32>().is_err() => Err(format!(
+            "Invalid install_retries '{}'. Use a whole number (0 disables retries).",
+            value
+        )),
+        "log_format" if !matches!(value, "text" | "json") => {
+            Err(format!("Invalid log_format '{}'. Use text or json.", value))
+        }
+        "log_max_size" if crate::activity_log::parse_size(value).is_none() => Err(format!(
+            "Invalid log_max_size '{}'. Use a size like 1MB, 500KB or a byte count.",
+            value
+        )),
+        "prefer_uv" if !matches!(value, "true" | "false") => {
+            Err(format!("Invalid prefer_uv '{}'. Use true or false.", value))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Validates a file path for safety.
 ///
 /// Ensures the path doesn't escape expected boundaries.
@@ -133,6 +180,25 @@ mod tests {
         assert!(validate_name("$(whoami)", "Environment").is_err());
     }
 
+    #[test]
+    fn test_config_values() {
+        assert!(validate_config_value("theme", "mono").is_ok());
+        assert!(validate_config_value("theme", "neon").is_err());
+        assert!(validate_config_value("default_python", "3.11").is_ok());
+        assert!(validate_config_value("default_python", "three").is_err());
+        assert!(validate_config_value("install_retries", "0").is_ok());
+        assert!(validate_config_value("log_max_size", "2MB").is_ok());
+        assert!(validate_config_value("log_max_size", "500kb").is_ok());
+        assert!(validate_config_value("log_max_size", "0").is_err());
+        assert!(validate_config_value("log_max_size", "big").is_err());
+        assert!(validate_config_value("log_max_size", "18446744073709551615MB").is_err());
+        assert!(validate_config_value("log_format", "json").is_ok());
+        assert!(validate_config_value("log_format", "xml").is_err());
+        assert!(validate_config_value("install_retries", "-1").is_err());
+        assert!(validate_config_value("prefer_uv", "yes").is_err());
+        assert!(validate_config_value("env_home", "anything").is_ok());
+    }
+
     #[test]
     fn test_python_version() {
         assert!(validate_python_version("3.12").is_ok());
@@ -151,4 +217,14 @@ mod tests {
         assert!(validate_cuda_version("9.0").is_err());
         assert!(validate_cuda_version("abc").is_err());
     }
+
+    #[test]
+    fn test_env_var_key() {
+        assert!(validate_env_var_key("HF_HOME").is_ok());
+        assert!(validate_env_var_key("_private1").is_ok());
+        assert!(validate_env_var_key("").is_err());
+        assert!(validate_env_var_key("1ABC").is_err());
+        assert!(validate_env_var_key("A-B").is_err());
+        assert!(validate_env_var_key("A;rm").is_err());
+    }
 }
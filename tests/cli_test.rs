@@ -7,6 +7,8 @@
 
 use std::process::Command;
 
+mod common;
+
 /// Helper: run zen with an isolated config + env home.
 fn zen_cmd(tmp: &std::path::Path, args: &[&str]) -> std::process::Output {
     Command::new(env!("CARGO_BIN_EXE_zen"))
@@ -26,6 +28,15 @@ fn all_output(output: &std::process::Output) -> String {
     )
 }
 
+/// Helper: register a fake env at `elsewhere/<name>` via `zen add`.
+fn add_fake_env(tmp: &std::path::Path, name: &str) -> std::path::PathBuf {
+    let dir = tmp.join("elsewhere").join(name);
+    common::fake_env(&dir, &[]);
+    let add = zen_cmd(tmp, &["add", dir.to_str().unwrap()]);
+    assert!(add.status.success(), "add: {}", all_output(&add));
+    dir
+}
+
 // ── Version & Help ──────────────────────────────────────────────
 
 #[test]
@@ -77,6 +88,19 @@ fn test_cli_create_and_list() {
     assert!(out.contains("cli-test"), "list missing env: {}", out);
 }
 
+#[test]
+fn test_cli_create_no_bootstrap() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let create = zen_cmd(tmp.path(), &["create", "bare-env", "--no-bootstrap"]);
+    let out = all_output(&create);
+    assert!(create.status.success(), "create failed: {}", out);
+    assert!(!out.contains("Bootstrap"), "bootstrap ran: {}", out);
+
+    let list = zen_cmd(tmp.path(), &["list", "-1"]);
+    assert!(all_output(&list).contains("bare-env"), "env not registered");
+}
+
 // ── Create + Info (real venv) ───────────────────────────────────
 
 #[test]
@@ -170,6 +194,35 @@ fn test_cli_note_lifecycle() {
         "note list: {}",
         all_output(&list)
     );
+
+    // Tagged notes filter with --tag
+    let tagged = zen_cmd(
+        tmp.path(),
+        &["note", "add", "Pin numpy", "note-env", "--tag", "todo"],
+    );
+    assert!(
+        tagged.status.success(),
+        "tagged add: {}",
+        all_output(&tagged)
+    );
+    let list = all_output(&zen_cmd(
+        tmp.path(),
+        &["note", "list", "note-env", "--tag", "todo"],
+    ));
+    assert!(
+        list.contains("Pin numpy") && list.contains("todo"),
+        "{}",
+        list
+    );
+    assert!(!list.contains("Test note"), "{}", list);
+
+    let search = all_output(&zen_cmd(tmp.path(), &["note", "search", "NUMPY"]));
+    assert!(
+        search.contains("Pin numpy") && search.contains("note-env"),
+        "{}",
+        search
+    );
+    assert!(!search.contains("Test note"), "{}", search);
 }
 
 // ── Invalid name rejected ───────────────────────────────────────
@@ -200,6 +253,287 @@ fn test_cli_health() {
     assert!(health.status.success(), "health: {}", all_output(&health));
 }
 
+#[test]
+fn test_cli_health_exit_code_and_json() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    // Half-created venv: interpreter present, site-packages empty
+    let env_dir = add_fake_env(tmp.path(), "half-env");
+    std::fs::write(env_dir.join("pyvenv.cfg"), "version = 3.12.1\n").unwrap();
+
+    let health = zen_cmd(tmp.path(), &["health", "half-env", "--json"]);
+    assert_eq!(health.status.code(), Some(1), "{}", all_output(&health));
+    let json: serde_json::Value = serde_json::from_slice(&health.stdout).unwrap();
+    assert_eq!(json["overall"], "fail");
+    assert!(json["items"].as_array().unwrap().len() >= 2);
+    let text = zen_cmd(tmp.path(), &["health", "half-env"]);
+    assert_eq!(text.status.code(), Some(1), "{}", all_output(&text));
+    assert!(all_output(&text).contains("BROKEN"));
+
+    // Seeded with pip it passes
+    let site = env_dir.join("lib/python3.12/site-packages");
+    common::write_dist_info(&site, "pip", "24.0", "");
+    let health = zen_cmd(tmp.path(), &["health", "half-env", "--json", "--strict"]);
+    assert!(health.status.success(), "{}", all_output(&health));
+    let json: serde_json::Value = serde_json::from_slice(&health.stdout).unwrap();
+    assert_eq!(json["overall"], "pass");
+}
+
+// ── Favorites ───────────────────────────────────────────────────
+
+#[test]
+fn test_cli_favorite_and_list_favorites() {
+    let tmp = tempfile::tempdir().unwrap();
+    for name in ["fav-a", "fav-b"] {
+        add_fake_env(tmp.path(), name);
+    }
+
+    let fav = zen_cmd(tmp.path(), &["favorite", "fav-b"]);
+    assert!(fav.status.success(), "favorite: {}", all_output(&fav));
+    let list = all_output(&zen_cmd(tmp.path(), &["list", "-1", "--favorites"]));
+    assert!(
+        list.contains("fav-b") && !list.contains("fav-a"),
+        "{}",
+        list
+    );
+
+    let unfav = zen_cmd(tmp.path(), &["unfavorite", "fav-b"]);
+    assert!(unfav.status.success(), "unfavorite: {}", all_output(&unfav));
+    let list = all_output(&zen_cmd(tmp.path(), &["list", "-1", "--favorites"]));
+    assert!(!list.contains("fav-b"), "{}", list);
+
+    let missing = zen_cmd(tmp.path(), &["favorite", "no-such-env"]);
+    assert!(!missing.status.success(), "starred a missing env");
+}
+
+// ── List health filters ─────────────────────────────────────────
+
+#[test]
+fn test_cli_list_broken_and_drift() {
+    let tmp = tempfile::tempdir().unwrap();
+    let fake_env = |name: &str, dists: &[(&str, &str, &str)]| {
+        let dir = tmp.path().join("elsewhere").join(name);
+        common::fake_env(&dir, dists);
+        let add = zen_cmd(tmp.path(), &["add", dir.to_str().unwrap()]);
+        assert!(add.status.success(), "add: {}", all_output(&add));
+    };
+    fake_env("ok-env", &[("pip", "24.0", "")]);
+    fake_env("broken-env", &[]);
+    fake_env(
+        "drift-env",
+        &[
+            ("app", "1.0", "Requires-Dist: lib>=2\n"),
+            ("lib", "1.0", ""),
+        ],
+    );
+
+    let list = |flags: &[&str]| {
+        let mut args = vec!["list", "-1"];
+        args.extend(flags);
+        String::from_utf8_lossy(&zen_cmd(tmp.path(), &args).stdout).to_string()
+    };
+    assert_eq!(list(&["--broken"]).trim(), "broken-env");
+    assert_eq!(list(&["--drift"]).trim(), "drift-env");
+    let both = list(&["--broken", "--drift"]);
+    assert!(both.contains("broken-env") && both.contains("drift-env"));
+    assert!(!both.contains("ok-env"), "{}", both);
+
+    // Composes with the name pattern (substring or glob)
+    assert_eq!(list(&["--broken", "*-env"]).trim(), "broken-env");
+    assert!(list(&["--drift", "ok*"]).trim().is_empty());
+}
+
+// ── Wildcard env names ──────────────────────────────────────────
+
+#[test]
+fn test_cli_glob_label_health_and_rm() {
+    let tmp = tempfile::tempdir().unwrap();
+    for name in ["glob-torch-a", "glob-torch-b", "glob-other"] {
+        add_fake_env(tmp.path(), name);
+    }
+
+    let label = zen_cmd(tmp.path(), &["label", "add", "ml", "*torch*"]);
+    assert!(label.status.success(), "label: {}", all_output(&label));
+    let run = zen_cmd(tmp.path(), &["list", "-1", "--label", "ml"]);
+    let out = String::from_utf8_lossy(&run.stdout).to_string();
+    assert_eq!(
+        out.lines().collect::<Vec<_>>(),
+        ["glob-torch-a", "glob-torch-b"]
+    );
+
+    // Health over a glob: one JSON array, exit code from the worst env
+    let health = zen_cmd(tmp.path(), &["health", "glob-torch-?", "--json"]);
+    assert_eq!(health.status.code(), Some(1), "{}", all_output(&health));
+    let json: serde_json::Value = serde_json::from_slice(&health.stdout).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+
+    // rm lists the matches and needs --yes for the whole set
+    let dry = all_output(&zen_cmd(tmp.path(), &["rm", "*torch*", "--dry-run"]));
+    assert!(
+        dry.contains("2 environment(s)") && dry.contains("glob-torch-b"),
+        "{}",
+        dry
+    );
+    let rm = zen_cmd(tmp.path(), &["rm", "*torch*", "--cached", "--yes"]);
+    assert!(rm.status.success(), "rm: {}", all_output(&rm));
+    let list = all_output(&zen_cmd(tmp.path(), &["list", "-1"]));
+    assert!(
+        !list.contains("glob-torch") && list.contains("glob-other"),
+        "{}",
+        list
+    );
+
+    let none = zen_cmd(tmp.path(), &["rm", "*nomatch*", "--yes"]);
+    assert!(!none.status.success(), "empty glob should fail");
+}
+
+// ── Template export/import ──────────────────────────────────────
+
+#[test]
+fn test_cli_template_import_refuses_overwrite() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("ml-base.toml");
+    std::fs::write(
+        &file,
+        r#"[template]
+name = "ml-base"
+version = "v1"
+python = "3.12"
+
+[[step]]
+install_args = "--index-url https://example.com/simple --pre"
+packages = [{ name = "numpy", version = "2.1.0" }]
+"#,
+    )
+    .unwrap();
+    let file = file.to_str().unwrap();
+
+    let first = zen_cmd(tmp.path(), &["template", "import", file]);
+    assert!(first.status.success(), "import: {}", all_output(&first));
+
+    let again = zen_cmd(tmp.path(), &["template", "import", file]);
+    assert!(!again.status.success(), "overwrote without --force");
+    assert!(all_output(&again).contains("--force"));
+
+    let forced = zen_cmd(tmp.path(), &["template", "import", file, "--force"]);
+    assert!(forced.status.success(), "--force: {}", all_output(&forced));
+
+    // install_args round-trip verbatim, not just the index URL
+    let out = tmp.path().join("out.toml");
+    let export = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "export",
+            "ml-base:v1",
+            "-o",
+            out.to_str().unwrap(),
+        ],
+    );
+    assert!(export.status.success(), "export: {}", all_output(&export));
+    let exported = std::fs::read_to_string(&out).unwrap();
+    assert!(exported.contains("--pre"), "{}", exported);
+    assert!(exported.contains("numpy"), "{}", exported);
+}
+
+#[test]
+fn test_cli_template_extras_round_trip() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("audio.toml");
+    std::fs::write(
+        &file,
+        r#"[template]
+name = "audio"
+version = "v1"
+python = "3.12"
+
+[[step]]
+packages = [{ name = "soundfile", version = "0.12.1", extras = ["numpy", "cffi"] }]
+"#,
+    )
+    .unwrap();
+
+    let import = zen_cmd(tmp.path(), &["template", "import", file.to_str().unwrap()]);
+    assert!(import.status.success(), "import: {}", all_output(&import));
+
+    let inspect = zen_cmd(tmp.path(), &["template", "inspect", "audio:v1"]);
+    assert!(
+        all_output(&inspect).contains("soundfile[numpy,cffi]"),
+        "{}",
+        all_output(&inspect)
+    );
+
+    let out = tmp.path().join("out.toml");
+    let export = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "export",
+            "audio:v1",
+            "-o",
+            out.to_str().unwrap(),
+        ],
+    );
+    assert!(export.status.success(), "export: {}", all_output(&export));
+    let exported = std::fs::read_to_string(&out).unwrap();
+    assert!(exported.contains("extras"), "{}", exported);
+    assert!(exported.contains("cffi"), "{}", exported);
+}
+
+#[test]
+fn test_cli_template_version_ranges() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("ranges.toml");
+    std::fs::write(
+        &file,
+        r#"[template]
+name = "ranges"
+version = "v1"
+python = "3.12"
+
+[[step]]
+packages = [
+    { name = "numpy", version = ">=1.26, <2" },
+    { name = "scipy", version = "1.13.0", version_spec = "~=1.13" },
+    { name = "pandas", version = "2.2.2" },
+]
+"#,
+    )
+    .unwrap();
+
+    let import = zen_cmd(tmp.path(), &["template", "import", file.to_str().unwrap()]);
+    assert!(import.status.success(), "import: {}", all_output(&import));
+
+    let inspect = all_output(&zen_cmd(tmp.path(), &["template", "inspect", "ranges:v1"]));
+    assert!(inspect.contains(">=1.26,<2"), "{}", inspect);
+    assert!(inspect.contains("~=1.13"), "{}", inspect);
+    assert!(inspect.contains("2.2.2"), "{}", inspect);
+
+    let out = tmp.path().join("out.toml");
+    let export = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "export",
+            "ranges:v1",
+            "-o",
+            out.to_str().unwrap(),
+        ],
+    );
+    assert!(export.status.success(), "export: {}", all_output(&export));
+    let exported = std::fs::read_to_string(&out).unwrap();
+    assert!(
+        exported.contains("version_spec = \">=1.26,<2\""),
+        "{}",
+        exported
+    );
+    assert!(
+        exported.contains("version_spec = \"~=1.13\""),
+        "{}",
+        exported
+    );
+}
+
 // ── Remove (real venv) ──────────────────────────────────────────
 
 #[test]
@@ -219,6 +553,132 @@ fn test_cli_create_and_remove() {
     );
 }
 
+/// Builds a dependency-free `tinydep` 0.1.0 wheel, so installs work offline.
+fn build_tiny_wheel(dir: &std::path::Path) -> std::path::PathBuf {
+    let wheel = dir.join("tinydep-0.1.0-py3-none-any.whl");
+    let built = Command::new("python3")
+        .args(["-c", "import sys, zipfile\nz = zipfile.ZipFile(sys.argv[1], 'w')\nz.writestr('tinydep/__init__.py', '')\nz.writestr('tinydep-0.1.0.dist-info/METADATA', 'Metadata-Version: 2.1\\nName: tinydep\\nVersion: 0.1.0\\n')\nz.writestr('tinydep-0.1.0.dist-info/WHEEL', 'Wheel-Version: 1.0\\nRoot-Is-Purelib: true\\nTag: py3-none-any\\n')\nz.writestr('tinydep-0.1.0.dist-info/RECORD', '')\nz.close()"])
+        .arg(&wheel)
+        .status()
+        .expect("python3 not available");
+    assert!(built.success());
+    wheel
+}
+
+/// A pure-Python wheel `name-version` with an optional `Requires-Dist`.
+fn build_wheel(
+    dir: &std::path::Path,
+    name: &str,
+    version: &str,
+    requires: Option<&str>,
+) -> std::path::PathBuf {
+    let wheel = dir.join(format!("{}-{}-py3-none-any.whl", name, version));
+    let script = "import sys, zipfile\n\
+        name, version, requires = sys.argv[2], sys.argv[3], sys.argv[4]\n\
+        dist = f'{name}-{version}.dist-info'\n\
+        meta = f'Metadata-Version: 2.1\\nName: {name}\\nVersion: {version}\\n'\n\
+        meta += f'Requires-Dist: {requires}\\n' if requires else ''\n\
+        z = zipfile.ZipFile(sys.argv[1], 'w')\n\
+        z.writestr(f'{name}/__init__.py', '')\n\
+        z.writestr(dist + '/METADATA', meta)\n\
+        z.writestr(dist + '/WHEEL', 'Wheel-Version: 1.0\\nRoot-Is-Purelib: true\\nTag: py3-none-any\\n')\n\
+        z.writestr(dist + '/RECORD', '')\n\
+        z.close()";
+    let built = Command::new("python3")
+        .args(["-c", script])
+        .arg(&wheel)
+        .args([name, version, requires.unwrap_or("")])
+        .status()
+        .expect("python3 not available");
+    assert!(built.success());
+    wheel
+}
+
+/// A project pip can install editable offline: `my-proj`, importing `mypkg`,
+/// built by an in-tree PEP 660 backend with no build requirements.
+fn write_editable_project(dir: &std::path::Path) -> std::path::PathBuf {
+    let project = dir.join("my-proj");
+    std::fs::create_dir_all(project.join("mypkg")).unwrap();
+    std::fs::write(project.join("mypkg/__init__.py"), "").unwrap();
+    std::fs::write(
+        project.join("pyproject.toml"),
+        "[build-system]\nrequires = []\nbuild-backend = \"backend\"\nbackend-path = [\".\"]\n\n\
+         [project]\nname = \"my-proj\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        project.join("backend.py"),
+        r#"import os, zipfile
+
+def build_editable(wheel_directory, config_settings=None, metadata_directory=None):
+    wheel = "my_proj-0.1.0-py3-none-any.whl"
+    dist = "my_proj-0.1.0.dist-info"
+    files = {
+        "my_proj.pth": os.path.dirname(os.path.abspath(__file__)) + "\n",
+        dist + "/METADATA": "Metadata-Version: 2.1\nName: my-proj\nVersion: 0.1.0\n",
+        dist + "/WHEEL": "Wheel-Version: 1.0\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        dist + "/top_level.txt": "mypkg\n",
+    }
+    with zipfile.ZipFile(os.path.join(wheel_directory, wheel), "w") as zf:
+        for path, text in files.items():
+            zf.writestr(path, text)
+        zf.writestr(dist + "/RECORD", "".join(p + ",,\n" for p in files) + dist + "/RECORD,,\n")
+    return wheel
+"#,
+    )
+    .unwrap();
+    project
+}
+
+#[test]
+fn test_cli_install_and_uninstall_group() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    let create = zen_cmd(tmp.path(), &["create", "grp-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let install = zen_cmd(
+        tmp.path(),
+        &[
+            "install",
+            "-n",
+            "grp-env",
+            "--group",
+            "dev",
+            "-q",
+            wheel.to_str().unwrap(),
+        ],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let info = all_output(&zen_cmd(tmp.path(), &["info", "grp-env"]));
+    assert!(
+        info.contains("Groups:") && info.contains("dev:"),
+        "{}",
+        info
+    );
+    assert!(info.contains("tinydep"), "{}", info);
+
+    let rm = zen_cmd(
+        tmp.path(),
+        &["uninstall", "-n", "grp-env", "--group", "dev"],
+    );
+    assert!(rm.status.success(), "uninstall: {}", all_output(&rm));
+    let info = all_output(&zen_cmd(tmp.path(), &["info", "grp-env"]));
+    assert!(!info.contains("Groups:"), "{}", info);
+
+    let missing = zen_cmd(
+        tmp.path(),
+        &["uninstall", "-n", "grp-env", "--group", "dev"],
+    );
+    assert!(!missing.status.success(), "missing group should fail");
+}
+
 // ── DB file permissions (Security L1) ───────────────────────────
 
 #[test]
@@ -242,3 +702,1850 @@ fn test_db_permissions_0600() {
         perms.mode() & 0o777
     );
 }
+
+// ── Garbage collection ──────────────────────────────────────────
+
+#[test]
+fn test_cli_gc_dry_run_and_apply() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    // Register an env, then delete its directory behind zen's back
+    let env_dir = add_fake_env(tmp.path(), "gc-env");
+    std::fs::remove_dir_all(&env_dir).unwrap();
+
+    // Dry run reports but keeps the row
+    let report = zen_cmd(tmp.path(), &["gc"]);
+    let out = all_output(&report);
+    assert!(out.contains("gc-env"), "gc report: {}", out);
+    let list = zen_cmd(tmp.path(), &["list", "-1"]);
+    assert!(all_output(&list).contains("gc-env"), "dry run removed env");
+
+    // --yes removes it
+    let apply = zen_cmd(tmp.path(), &["gc", "--yes"]);
+    assert!(apply.status.success(), "gc --yes: {}", all_output(&apply));
+    let list = zen_cmd(tmp.path(), &["list", "-1"]);
+    assert!(!all_output(&list).contains("gc-env"), "env still listed");
+}
+
+// ── Rm dry run ──────────────────────────────────────────────────
+
+#[test]
+fn test_cli_rm_dry_run_and_cleanup_summary() {
+    let tmp = tempfile::tempdir().unwrap();
+    let env_dir = add_fake_env(tmp.path(), "rm-env");
+    let label = zen_cmd(tmp.path(), &["label", "add", "ml", "rm-env"]);
+    assert!(label.status.success(), "label: {}", all_output(&label));
+
+    let preview = zen_cmd(tmp.path(), &["rm", "rm-env", "--dry-run"]);
+    let out = all_output(&preview);
+    assert!(preview.status.success(), "rm --dry-run: {}", out);
+    assert!(
+        out.contains("Labels:") && out.contains(" 1"),
+        "preview: {}",
+        out
+    );
+    assert!(out.contains("Links:"), "preview: {}", out);
+    assert!(env_dir.exists(), "dry run deleted the env directory");
+    let list = zen_cmd(tmp.path(), &["list", "-1"]);
+    assert!(
+        all_output(&list).contains("rm-env"),
+        "dry run untracked env"
+    );
+}
+
+#[test]
+fn test_cli_rm_glob_previews_and_fails_on_error() {
+    let tmp = tempfile::tempdir().unwrap();
+    for name in ["rmg-a", "rmg-b"] {
+        add_fake_env(tmp.path(), name);
+    }
+
+    // A glob preview shows the same per-env detail as a single name
+    let preview = all_output(&zen_cmd(tmp.path(), &["rm", "rmg-*", "--dry-run"]));
+    assert!(
+        preview.contains("Path:") && preview.contains("Would remove environment 'rmg-b'"),
+        "preview: {}",
+        preview
+    );
+
+    // A path that can't be deleted fails that env and the command
+    let broken = tmp.path().join("elsewhere/rmg-b");
+    std::fs::remove_dir_all(&broken).unwrap();
+    std::fs::write(&broken, "").unwrap();
+    let rm = zen_cmd(tmp.path(), &["rm", "rmg-*", "--yes"]);
+    assert_eq!(rm.status.code(), Some(1), "rm: {}", all_output(&rm));
+    assert!(!tmp.path().join("elsewhere/rmg-a").exists());
+}
+
+// ── Env vars ────────────────────────────────────────────────────
+
+#[test]
+fn test_cli_env_vars_set_run_export() {
+    let tmp = tempfile::tempdir().unwrap();
+    add_fake_env(tmp.path(), "vars-env");
+
+    let set = zen_cmd(
+        tmp.path(),
+        &["env", "set", "ZEN_T_VAR", "it's here", "vars-env"],
+    );
+    assert!(set.status.success(), "env set: {}", all_output(&set));
+    let bad = zen_cmd(tmp.path(), &["env", "set", "BAD-KEY", "x", "vars-env"]);
+    assert!(!bad.status.success(), "invalid key accepted");
+
+    let get = zen_cmd(tmp.path(), &["env", "get", "ZEN_T_VAR", "vars-env"]);
+    assert_eq!(String::from_utf8_lossy(&get.stdout).trim(), "it's here");
+
+    // zen run injects the variable into the child
+    let run = zen_cmd(tmp.path(), &["run", "vars-env", "printenv", "ZEN_T_VAR"]);
+    assert_eq!(String::from_utf8_lossy(&run.stdout).trim(), "it's here");
+
+    let export = zen_cmd(tmp.path(), &["env", "export", "vars-env"]);
+    let out = String::from_utf8_lossy(&export.stdout).to_string();
+    assert!(
+        out.contains("export ZEN_T_VAR='it'\\''s here'"),
+        "export: {}",
+        out
+    );
+
+    let unset = zen_cmd(tmp.path(), &["env", "unset", "ZEN_T_VAR", "vars-env"]);
+    assert!(unset.status.success(), "env unset: {}", all_output(&unset));
+    let get = zen_cmd(tmp.path(), &["env", "get", "ZEN_T_VAR", "vars-env"]);
+    assert!(!get.status.success(), "variable still set after unset");
+}
+
+// ── Run across envs ─────────────────────────────────────────────
+
+#[test]
+fn test_cli_run_all_with_label_and_summary() {
+    let tmp = tempfile::tempdir().unwrap();
+    for name in ["run-a", "run-b"] {
+        add_fake_env(tmp.path(), name);
+    }
+    zen_cmd(tmp.path(), &["label", "add", "ml", "run-b"]);
+
+    let ok = zen_cmd(tmp.path(), &["run", "--all", "true"]);
+    let out = all_output(&ok);
+    assert!(ok.status.success(), "run --all: {}", out);
+    assert!(out.contains("run-a") && out.contains("run-b"), "{}", out);
+    assert!(out.contains("2 passed, 0 failed"), "{}", out);
+
+    let scoped = zen_cmd(tmp.path(), &["run", "--all", "--label", "ml", "true"]);
+    let out = all_output(&scoped);
+    assert!(
+        !out.contains("run-a") && out.contains("1 passed"),
+        "{}",
+        out
+    );
+
+    // Stops at the first failure unless --continue-on-error
+    let fail = zen_cmd(tmp.path(), &["run", "--all", "false"]);
+    let out = all_output(&fail);
+    assert!(!fail.status.success());
+    assert!(out.contains("0 passed, 1 failed, 1 not run"), "{}", out);
+    let cont = zen_cmd(
+        tmp.path(),
+        &["run", "--all", "--continue-on-error", "false"],
+    );
+    assert!(all_output(&cont).contains("0 passed, 2 failed"));
+}
+
+// ── Status JSON ─────────────────────────────────────────────────
+
+#[test]
+fn test_cli_status_json() {
+    let tmp = tempfile::tempdir().unwrap();
+    let out = zen_cmd(tmp.path(), &["status", "--json"]);
+    assert!(out.status.success(), "status: {}", all_output(&out));
+
+    let report: serde_json::Value =
+        serde_json::from_slice(&out.stdout).expect("status --json is not valid JSON");
+    assert_eq!(report["managed_envs"], 0);
+    assert_eq!(report["mode"], "normal");
+    assert!(report["schema_version"].as_i64().unwrap() >= 1);
+    assert!(report["db_path"].as_str().unwrap().ends_with("zen.db"));
+}
+
+// ── Color control ───────────────────────────────────────────────
+
+#[test]
+fn test_cli_no_color() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    let flag = zen_cmd(tmp.path(), &["--no-color", "status"]);
+    assert!(flag.status.success(), "status: {}", all_output(&flag));
+    assert!(
+        !all_output(&flag).contains('\x1b'),
+        "--no-color output has ANSI escapes"
+    );
+
+    let env = Command::new(env!("CARGO_BIN_EXE_zen"))
+        .arg("status")
+        .env("HOME", tmp.path())
+        .env("ZEN_HOME", tmp.path().join("envs"))
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    assert!(
+        !all_output(&env).contains('\x1b'),
+        "NO_COLOR output has ANSI escapes"
+    );
+
+    zen_cmd(tmp.path(), &["config", "theme", "mono"]);
+    let mono = zen_cmd(tmp.path(), &["status"]);
+    assert!(
+        !all_output(&mono).contains('\x1b'),
+        "mono theme output has ANSI escapes"
+    );
+}
+
+// ── Config validation ───────────────────────────────────────────
+
+#[test]
+fn test_cli_config_unknown_key_and_unset() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    // Typo is rejected with a suggestion
+    let typo = zen_cmd(tmp.path(), &["config", "stack-info", "torch"]);
+    assert!(!typo.status.success(), "unknown key accepted");
+    assert!(all_output(&typo).contains("stack_info"), "no suggestion");
+
+    // --force allows it, --unset removes it
+    let forced = zen_cmd(tmp.path(), &["config", "stack-info", "torch", "--force"]);
+    assert!(forced.status.success(), "force: {}", all_output(&forced));
+    let unset = zen_cmd(tmp.path(), &["config", "--unset", "stack-info"]);
+    assert!(unset.status.success(), "unset: {}", all_output(&unset));
+    let get = zen_cmd(tmp.path(), &["config", "stack-info"]);
+    assert!(all_output(&get).contains("not set"), "key still set");
+}
+
+// ── env_home config ─────────────────────────────────────────────
+
+#[test]
+fn test_cli_env_home_config() {
+    let tmp = tempfile::tempdir().unwrap();
+    let custom = tmp.path().join("custom-envs");
+    std::fs::create_dir_all(custom.join("cfg-env/bin")).unwrap();
+    std::fs::write(custom.join("cfg-env/bin/python"), "").unwrap();
+
+    let set = zen_cmd(
+        tmp.path(),
+        &["config", "env_home", custom.to_str().unwrap()],
+    );
+    assert!(set.status.success(), "config: {}", all_output(&set));
+
+    // No ZEN_HOME → the config value is the home, so the env is discovered
+    let list = Command::new(env!("CARGO_BIN_EXE_zen"))
+        .args(["list", "-1"])
+        .env("HOME", tmp.path())
+        .env_remove("ZEN_HOME")
+        .output()
+        .unwrap();
+    assert!(
+        all_output(&list).contains("cfg-env"),
+        "env_home ignored: {}",
+        all_output(&list)
+    );
+
+    // ZEN_HOME still wins over the config
+    let status = zen_cmd(tmp.path(), &["status", "--json"]);
+    let report: serde_json::Value = serde_json::from_slice(&status.stdout).unwrap();
+    assert!(report["zen_home"].as_str().unwrap().ends_with("envs"));
+    assert!(!report["zen_home"].as_str().unwrap().contains("custom"));
+}
+
+// ── Reset scopes ────────────────────────────────────────────────
+
+#[test]
+fn test_cli_reset_db_only_keeps_config() {
+    let tmp = tempfile::tempdir().unwrap();
+    add_fake_env(tmp.path(), "reset-env");
+
+    zen_cmd(tmp.path(), &["config", "stack_info", "torch"]);
+    let label = zen_cmd(tmp.path(), &["label", "add", "ml", "reset-env"]);
+    assert!(label.status.success(), "label: {}", all_output(&label));
+
+    let reset = zen_cmd(tmp.path(), &["reset", "--yes", "--db-only"]);
+    assert!(reset.status.success(), "reset: {}", all_output(&reset));
+
+    let get = zen_cmd(tmp.path(), &["config", "stack_info"]);
+    assert!(
+        all_output(&get).contains("torch"),
+        "config lost on --db-only"
+    );
+    let labels = zen_cmd(tmp.path(), &["label", "list", "--all"]);
+    assert!(
+        !all_output(&labels).contains("ml"),
+        "registry survived reset"
+    );
+
+    let reset = zen_cmd(tmp.path(), &["reset", "--yes", "--config-only"]);
+    assert!(reset.status.success(), "reset: {}", all_output(&reset));
+    let get = zen_cmd(tmp.path(), &["config", "stack_info"]);
+    assert!(
+        all_output(&get).contains("not set"),
+        "config kept on --config-only"
+    );
+}
+
+#[test]
+fn test_cli_reset_respects_db_override() {
+    let tmp = tempfile::tempdir().unwrap();
+    let custom_db = tmp.path().join("custom.db");
+    let zen_dojo = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zen"))
+            .args(args)
+            .env("HOME", tmp.path())
+            .env("ZEN_HOME", tmp.path().join("envs"))
+            .env("ZEN_DOJO", &custom_db)
+            .output()
+            .unwrap()
+    };
+
+    zen_dojo(&["config", "stack_info", "torch"]);
+    assert!(custom_db.exists(), "ZEN_DOJO database not created");
+
+    let reset = zen_dojo(&["reset", "--yes"]);
+    assert!(reset.status.success(), "reset: {}", all_output(&reset));
+    assert!(
+        all_output(&reset).contains("custom.db"),
+        "reset targeted the wrong file: {}",
+        all_output(&reset)
+    );
+    let get = zen_dojo(&["config", "stack_info"]);
+    assert!(all_output(&get).contains("not set"), "database not reset");
+}
+
+// ── Dynamic completion ──────────────────────────────────────────
+
+#[test]
+fn test_cli_dynamic_completion_candidates() {
+    let tmp = tempfile::tempdir().unwrap();
+
+    zen_cmd(tmp.path(), &["create", "comp-alpha"]);
+    zen_cmd(tmp.path(), &["create", "comp-beta"]);
+    zen_cmd(tmp.path(), &["label", "add", "gpu", "comp-beta"]);
+
+    let envs = zen_cmd(tmp.path(), &["__complete", "envs", "comp-a"]);
+    assert!(envs.status.success(), "__complete: {}", all_output(&envs));
+    let stdout = String::from_utf8_lossy(&envs.stdout);
+    assert_eq!(stdout.lines().collect::<Vec<_>>(), vec!["comp-alpha"]);
+
+    let labels = zen_cmd(tmp.path(), &["__complete", "labels"]);
+    assert_eq!(String::from_utf8_lossy(&labels.stdout).trim(), "gpu");
+
+    for shell in ["bash", "zsh", "fish"] {
+        let hook = zen_cmd(tmp.path(), &["hook", shell]);
+        assert!(
+            String::from_utf8_lossy(&hook.stdout).contains("__complete"),
+            "{} hook lacks dynamic completion",
+            shell
+        );
+    }
+}
+
+// ── Link porcelain ──────────────────────────────────────────────
+
+#[test]
+fn test_cli_link_list_porcelain() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path().join("project");
+    std::fs::create_dir_all(project.join("sub")).unwrap();
+    let project_str = project.to_str().unwrap();
+
+    zen_cmd(tmp.path(), &["create", "link-env"]);
+    let add = zen_cmd(tmp.path(), &["link", "add", "link-env", project_str]);
+    assert!(add.status.success(), "link add: {}", all_output(&add));
+
+    // A subdirectory inherits the parent's link without any prose
+    let sub = project.join("sub");
+    let list = zen_cmd(
+        tmp.path(),
+        &["link", "list", "--porcelain", sub.to_str().unwrap()],
+    );
+    assert!(list.status.success(), "link list: {}", all_output(&list));
+    let stdout = String::from_utf8_lossy(&list.stdout);
+    let fields: Vec<&str> = stdout.trim_end_matches('\n').split('\t').collect();
+    assert_eq!(fields.len(), 7, "unexpected line: {:?}", stdout);
+    assert_eq!(fields[0], "link-env");
+    assert_eq!(fields[3], "1");
+    assert_eq!(fields[4], "user");
+}
+
+// ── Which ───────────────────────────────────────────────────────
+
+#[test]
+fn test_cli_which() {
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(tmp.path(), &["create", "which-env"]);
+
+    let which = zen_cmd(tmp.path(), &["which", "which-env"]);
+    assert!(which.status.success(), "which: {}", all_output(&which));
+    let stdout = String::from_utf8_lossy(&which.stdout);
+    assert!(
+        stdout.trim().ends_with("which-env/bin/python"),
+        "which: {}",
+        stdout
+    );
+
+    let pip = zen_cmd(tmp.path(), &["which", "which-env", "pip"]);
+    assert!(
+        String::from_utf8_lossy(&pip.stdout)
+            .trim()
+            .ends_with("bin/pip")
+    );
+
+    let missing = zen_cmd(tmp.path(), &["which", "which-env", "no-such-tool"]);
+    assert!(!missing.status.success());
+    assert!(all_output(&missing).contains("not found"));
+}
+
+#[test]
+fn test_cli_path() {
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(tmp.path(), &["create", "path-env"]);
+
+    let path = zen_cmd(tmp.path(), &["path", "path-env"]);
+    assert!(path.status.success(), "path: {}", all_output(&path));
+    let expected = tmp.path().join("envs").join("path-env");
+    assert_eq!(
+        String::from_utf8_lossy(&path.stdout).trim(),
+        expected.to_str().unwrap()
+    );
+
+    let from_venv = Command::new(env!("CARGO_BIN_EXE_zen"))
+        .args(["path"])
+        .env("HOME", tmp.path())
+        .env("ZEN_HOME", tmp.path().join("envs"))
+        .env("VIRTUAL_ENV", "/opt/some/venv")
+        .output()
+        .unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&from_venv.stdout).trim(),
+        "/opt/some/venv"
+    );
+}
+
+// ── Install constraints ─────────────────────────────────────────
+
+#[test]
+fn test_cli_install_constraint() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+    zen_cmd(tmp.path(), &["create", "con-env"]);
+
+    let missing = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "con-env", "-c", "no-such.txt", "tinydep"],
+    );
+    assert!(!missing.status.success());
+    assert!(
+        all_output(&missing).contains("Constraint file"),
+        "{}",
+        all_output(&missing)
+    );
+
+    // The constraint reaches pip: it rejects the 0.1.0 wheel
+    let constraints = tmp.path().join("constraints.txt");
+    std::fs::write(&constraints, "tinydep==9.9\n").unwrap();
+    let blocked = zen_cmd(
+        tmp.path(),
+        &[
+            "install",
+            "-n",
+            "con-env",
+            "-q",
+            "--constraint",
+            constraints.to_str().unwrap(),
+            wheel.to_str().unwrap(),
+        ],
+    );
+    assert!(!blocked.status.success(), "{}", all_output(&blocked));
+}
+
+// ── Fuzzy env names ─────────────────────────────────────────────
+
+#[test]
+fn test_cli_activate_fuzzy_name() {
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(tmp.path(), &["create", "fuzzy_env"]);
+
+    // Separator/case slips resolve directly
+    let loose = zen_cmd(tmp.path(), &["activate", "FuzzyEnv", "--path-only"]);
+    assert!(loose.status.success(), "{}", all_output(&loose));
+    assert!(String::from_utf8_lossy(&loose.stdout).contains("fuzzy_env"));
+
+    // A typo asks first; without a terminal the prompt declines
+    let typo = zen_cmd(tmp.path(), &["activate", "fuzy_env", "--path-only"]);
+    assert!(!typo.status.success());
+    assert!(all_output(&typo).contains("Activate 'fuzzy_env'?"));
+    assert!(typo.stdout.is_empty());
+
+    // Other commands accept the loose spelling too
+    let info = zen_cmd(tmp.path(), &["info", "fuzzy-env"]);
+    assert!(all_output(&info).contains("Using 'fuzzy_env'"));
+}
+
+#[test]
+fn test_cli_not_found_suggests_similar() {
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(tmp.path(), &["create", "suggest-env"]);
+
+    for args in [
+        vec!["info", "sugest-env"],
+        vec!["rm", "sugest-env", "--yes"],
+        vec!["install", "-n", "sugest-env", "numpy"],
+        vec!["diff", "sugest-env", "suggest-env"],
+    ] {
+        let out = all_output(&zen_cmd(tmp.path(), &args));
+        assert!(
+            out.contains("Did you mean: suggest-env?"),
+            "{:?}: {}",
+            args,
+            out
+        );
+    }
+    let unrelated = all_output(&zen_cmd(tmp.path(), &["info", "zzz"]));
+    assert!(!unrelated.contains("Did you mean"), "{}", unrelated);
+}
+
+#[test]
+fn test_cli_template_from_requirements() {
+    let tmp = tempfile::tempdir().unwrap();
+    let reqs = tmp.path().join("requirements.txt");
+    std::fs::write(
+        &reqs,
+        "--extra-index-url https://example.com/simple\nnumpy==1.26.4\nrequests>=2.31\n-r base.txt\n",
+    )
+    .unwrap();
+    let reqs = reqs.to_str().unwrap();
+
+    let out = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "create",
+            "fromreq:1",
+            "--from-requirements",
+            reqs,
+        ],
+    );
+    let text = all_output(&out);
+    assert!(out.status.success(), "{}", text);
+    assert!(text.contains("2 package(s), 1 pinned"), "{}", text);
+    assert!(
+        text.contains("Skipped unsupported line: -r base.txt"),
+        "{}",
+        text
+    );
+
+    let inspect = all_output(&zen_cmd(tmp.path(), &["template", "inspect", "fromreq:1"]));
+    assert!(inspect.contains("numpy"), "{}", inspect);
+    assert!(inspect.contains("requests"), "{}", inspect);
+
+    // Existing templates are only replaced with --force
+    let again = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "create",
+            "fromreq:1",
+            "--from-requirements",
+            reqs,
+        ],
+    );
+    assert!(!again.status.success());
+    assert!(all_output(&again).contains("--force"));
+    let forced = zen_cmd(
+        tmp.path(),
+        &[
+            "template",
+            "create",
+            "fromreq:1",
+            "--from-requirements",
+            reqs,
+            "--force",
+        ],
+    );
+    assert!(forced.status.success(), "{}", all_output(&forced));
+}
+
+#[test]
+fn test_cli_create_templates_python_conflict() {
+    let tmp = tempfile::tempdir().unwrap();
+    let reqs = tmp.path().join("requirements.txt");
+    std::fs::write(&reqs, "six\n").unwrap();
+    let reqs = reqs.to_str().unwrap();
+    for (tpl, py) in [("py311:1", "3.11"), ("py312:1", "3.12")] {
+        let out = zen_cmd(
+            tmp.path(),
+            &[
+                "template",
+                "create",
+                tpl,
+                "--python",
+                py,
+                "--from-requirements",
+                reqs,
+            ],
+        );
+        assert!(out.status.success(), "{}", all_output(&out));
+    }
+
+    let out = zen_cmd(
+        tmp.path(),
+        &["create", "mixed", "--from", "py311:1,py312:1"],
+    );
+    let text = all_output(&out);
+    assert!(!out.status.success(), "{}", text);
+    assert!(text.contains("different Python versions"), "{}", text);
+    assert!(text.contains("py312:1 → Python 3.12"), "{}", text);
+    assert!(text.contains("--force-python"), "{}", text);
+    assert!(!tmp.path().join("envs").join("mixed").exists());
+}
+
+#[test]
+fn test_cli_cuda_list_and_unknown_alias() {
+    let tmp = tempfile::tempdir().unwrap();
+    let list = zen_cmd(tmp.path(), &["cuda", "list"]);
+    let text = all_output(&list);
+    assert!(list.status.success(), "{}", text);
+    for alias in ["torch-cu118", "torch-cu126", "torch-cu130"] {
+        assert!(text.contains(alias), "{}", text);
+    }
+    assert!(
+        text.contains("https://download.pytorch.org/whl/cpu"),
+        "{}",
+        text
+    );
+
+    zen_cmd(tmp.path(), &["create", "cuda_env", "--no-bootstrap"]);
+    let out = zen_cmd(tmp.path(), &["install", "-n", "cuda_env", "torch-cu999"]);
+    let text = all_output(&out);
+    assert!(!out.status.success());
+    assert!(text.contains("Unknown CUDA version '999'"), "{}", text);
+    assert!(text.contains("valid: cpu, 11.8"), "{}", text);
+}
+
+#[test]
+fn test_cli_cuda_list_includes_cpu_alias() {
+    let tmp = tempfile::tempdir().unwrap();
+    let text = all_output(&zen_cmd(tmp.path(), &["cuda", "list"]));
+    let cpu_row = text
+        .lines()
+        .find(|l| l.contains("whl/cpu"))
+        .unwrap_or_else(|| panic!("no cpu row: {}", text));
+    assert!(cpu_row.contains("torch-cpu"), "{}", cpu_row);
+}
+
+#[test]
+fn test_cli_config_edit() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(tmp.path(), &["config", "theme", "dark"]);
+    zen_cmd(tmp.path(), &["config", "install_retries", "3"]);
+
+    // First pass writes an invalid value; the reopened file carries the error
+    let editor = tmp.path().join("editor.sh");
+    std::fs::write(
+        &editor,
+        "#!/bin/sh\n\
+         if grep -q '^# ERROR' \"$1\"; then\n\
+           grep '^# ERROR' \"$1\" > \"$1.seen\"\n\
+           printf 'theme = \"mono\"\\ndefault_python = \"3.11\"\\n' > \"$1\"\n\
+         else\n\
+           printf 'theme = \"neon\"\\n' > \"$1\"\n\
+         fi\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_zen"))
+        .args(["config", "--edit"])
+        .env("HOME", tmp.path())
+        .env("ZEN_HOME", tmp.path().join("envs"))
+        .env("TMPDIR", tmp.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .output()
+        .unwrap();
+    let text = all_output(&out);
+    assert!(out.status.success(), "{}", text);
+    assert!(text.contains("Config updated: theme = mono"), "{}", text);
+    assert!(
+        text.contains("Config updated: default_python = 3.11"),
+        "{}",
+        text
+    );
+    assert!(text.contains("Config removed: install_retries"), "{}", text);
+
+    let seen = std::fs::read_dir(tmp.path())
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .find(|e| e.file_name().to_string_lossy().ends_with(".seen"))
+        .map(|e| std::fs::read_to_string(e.path()).unwrap())
+        .expect("editor was not reopened after the invalid value");
+    assert!(seen.contains("Invalid theme 'neon'"), "{}", seen);
+
+    let list = all_output(&zen_cmd(tmp.path(), &["config"]));
+    assert!(list.contains("theme = mono"), "{}", list);
+    assert!(!list.contains("install_retries"), "{}", list);
+}
+
+#[test]
+fn test_cli_env_aliases() {
+    let tmp = tempfile::tempdir().unwrap();
+    zen_cmd(
+        tmp.path(),
+        &["create", "project-x-torch-experiment", "--no-bootstrap"],
+    );
+    zen_cmd(tmp.path(), &["create", "other", "--no-bootstrap"]);
+
+    let add = zen_cmd(
+        tmp.path(),
+        &["alias", "add", "px", "project-x-torch-experiment"],
+    );
+    assert!(add.status.success(), "{}", all_output(&add));
+
+    // Aliases work through resolve_env_name and the exact-name lookups alike
+    let info = all_output(&zen_cmd(tmp.path(), &["info", "px"]));
+    assert!(info.contains("project-x-torch-experiment"), "{}", info);
+    assert!(info.contains("Aliases:    px"), "{}", info);
+    let which = zen_cmd(tmp.path(), &["which", "px"]);
+    assert!(
+        String::from_utf8_lossy(&which.stdout).contains("project-x-torch-experiment"),
+        "{}",
+        all_output(&which)
+    );
+    let activate = zen_cmd(tmp.path(), &["activate", "px", "--path-only"]);
+    assert!(
+        String::from_utf8_lossy(&activate.stdout).contains("project-x-torch-experiment"),
+        "{}",
+        all_output(&activate)
+    );
+
+    // No collisions with environment names, in either direction
+    let clash = zen_cmd(
+        tmp.path(),
+        &["alias", "add", "other", "project-x-torch-experiment"],
+    );
+    assert!(!clash.status.success());
+    assert!(all_output(&clash).contains("already an environment name"));
+    let create = zen_cmd(tmp.path(), &["create", "px"]);
+    assert!(!create.status.success());
+    assert!(all_output(&create).contains("is an alias for"));
+
+    let list = all_output(&zen_cmd(tmp.path(), &["alias", "list"]));
+    assert!(
+        list.contains("px") && list.contains("project-x-torch-experiment"),
+        "{}",
+        list
+    );
+    let rm = zen_cmd(tmp.path(), &["alias", "rm", "px"]);
+    assert!(rm.status.success());
+    let list = all_output(&zen_cmd(tmp.path(), &["alias", "list"]));
+    assert!(list.contains("No aliases defined."), "{}", list);
+}
+
+#[test]
+fn test_cli_install_only_if_missing() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    let create = zen_cmd(tmp.path(), &["create", "oim-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "oim-env", "-q", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    // Already present: pip never runs (an unreachable index would fail it)
+    let again = zen_cmd(
+        tmp.path(),
+        &[
+            "install",
+            "-n",
+            "oim-env",
+            "--only-if-missing",
+            "--index-url",
+            "http://127.0.0.1:9/simple",
+            "tinydep>=0.1",
+        ],
+    );
+    let out = all_output(&again);
+    assert!(again.status.success(), "{}", out);
+    assert!(
+        out.contains("Already installed, skipping: tinydep>=0.1 (0.1.0)"),
+        "{}",
+        out
+    );
+    assert!(out.contains("Nothing to install."), "{}", out);
+    assert!(!out.contains("Installing packages in"), "{}", out);
+
+    let conflict = zen_cmd(
+        tmp.path(),
+        &[
+            "install",
+            "-n",
+            "oim-env",
+            "--only-if-missing",
+            "-U",
+            "tinydep",
+        ],
+    );
+    assert!(!conflict.status.success(), "--upgrade should conflict");
+}
+
+#[test]
+fn test_cli_uninstall_glob() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    let create = zen_cmd(tmp.path(), &["create", "ug-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "ug-env", "-q", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let none = zen_cmd(tmp.path(), &["uninstall", "-n", "ug-env", "nvidia-*", "-y"]);
+    let out = all_output(&none);
+    assert!(none.status.success(), "{}", out);
+    assert!(
+        out.contains("No installed packages match 'nvidia-*'"),
+        "{}",
+        out
+    );
+    assert!(out.contains("Nothing to uninstall."), "{}", out);
+
+    // Without --yes and no terminal to confirm on, nothing is removed
+    let unconfirmed = zen_cmd(tmp.path(), &["uninstall", "-n", "ug-env", "tiny*"]);
+    let out = all_output(&unconfirmed);
+    assert!(out.contains("1 package(s) to uninstall"), "{}", out);
+    let frozen = all_output(&zen_cmd(tmp.path(), &["freeze", "ug-env"]));
+    assert!(frozen.contains("tinydep"), "{}", frozen);
+
+    let rm = zen_cmd(tmp.path(), &["uninstall", "-n", "ug-env", "TINY*", "--yes"]);
+    let out = all_output(&rm);
+    assert!(rm.status.success(), "{}", out);
+    assert!(out.contains("tinydep"), "{}", out);
+    let frozen = all_output(&zen_cmd(tmp.path(), &["freeze", "ug-env"]));
+    assert!(!frozen.contains("tinydep"), "{}", frozen);
+}
+
+#[test]
+fn test_cli_diff_markers_and_summary() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    for name in ["dm-a", "dm-b"] {
+        let create = zen_cmd(tmp.path(), &["create", name, "--no-bootstrap"]);
+        assert!(create.status.success(), "create: {}", all_output(&create));
+    }
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "dm-a", "-q", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let diff = zen_cmd(tmp.path(), &["diff", "dm-a", "dm-b", "--only-diff"]);
+    let out = all_output(&diff);
+    assert!(diff.status.success(), "{}", out);
+    assert!(out.lines().any(|l| l.starts_with("- tinydep")), "{}", out);
+    assert!(
+        out.contains("0 changed, 1 only in dm-a, 0 only in dm-b, "),
+        "{}",
+        out
+    );
+    assert!(out.contains(" shared identical"), "{}", out);
+
+    let reversed = all_output(&zen_cmd(tmp.path(), &["diff", "dm-b", "dm-a", "-d"]));
+    assert!(
+        reversed.lines().any(|l| l.starts_with("+ tinydep")),
+        "{}",
+        reversed
+    );
+}
+
+#[test]
+fn test_cli_diff_matrix() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    for name in ["mx-a", "mx-b", "mx-c"] {
+        let create = zen_cmd(tmp.path(), &["create", name, "--no-bootstrap"]);
+        assert!(create.status.success(), "create: {}", all_output(&create));
+    }
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "mx-b", "-q", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let diff = zen_cmd(tmp.path(), &["diff", "mx-a", "mx-b", "mx-c", "-d"]);
+    let out = all_output(&diff);
+    assert!(diff.status.success(), "{}", out);
+    assert!(out.contains("mx-c"), "{}", out);
+    assert!(out.contains("tinydep"), "{}", out);
+    assert!(out.contains("1 of "), "{}", out);
+    assert!(out.contains(" differ across 3 environments"), "{}", out);
+    // Only the differing row survives -d
+    let rows = out.lines().filter(|l| l.contains("0.1.0")).count();
+    assert_eq!(rows, 1, "{}", out);
+    assert!(!out.lines().any(|l| l.contains("│ pip ")), "{}", out);
+
+    let one = zen_cmd(tmp.path(), &["diff", "mx-a"]);
+    assert!(!one.status.success(), "a single env should be rejected");
+}
+
+#[test]
+fn test_cli_snapshot_create_restore() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+
+    let create = zen_cmd(tmp.path(), &["create", "snap-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "snap-env", "-q", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let snap = zen_cmd(tmp.path(), &["snapshot", "create", "snap-env", "good"]);
+    let out = all_output(&snap);
+    assert!(snap.status.success(), "{}", out);
+    assert!(
+        out.contains("Snapshot 'good' of 'snap-env' saved (1 package(s))"),
+        "{}",
+        out
+    );
+    let dup = zen_cmd(tmp.path(), &["snapshot", "create", "snap-env", "good"]);
+    assert!(!dup.status.success(), "duplicate label should fail");
+    let list = all_output(&zen_cmd(tmp.path(), &["snapshot", "list", "snap-env"]));
+    assert!(
+        list.contains("good") && list.contains("1 package(s)"),
+        "{}",
+        list
+    );
+
+    let rm = zen_cmd(tmp.path(), &["uninstall", "-n", "snap-env", "tinydep"]);
+    assert!(rm.status.success(), "uninstall: {}", all_output(&rm));
+
+    let dry = zen_cmd(
+        tmp.path(),
+        &["snapshot", "restore", "snap-env", "good", "--dry-run"],
+    );
+    let out = all_output(&dry);
+    assert!(dry.status.success(), "{}", out);
+    assert!(out.contains("+ tinydep @ file://"), "{}", out);
+    assert!(out.contains("Dry run"), "{}", out);
+
+    let restore = zen_cmd(
+        tmp.path(),
+        &["snapshot", "restore", "snap-env", "good", "-y"],
+    );
+    let out = all_output(&restore);
+    assert!(restore.status.success(), "{}", out);
+    let frozen = all_output(&zen_cmd(tmp.path(), &["freeze", "snap-env"]));
+    assert!(frozen.contains("tinydep"), "{}", frozen);
+
+    let again = all_output(&zen_cmd(
+        tmp.path(),
+        &["snapshot", "restore", "snap-env", "good"],
+    ));
+    assert!(
+        again.contains("already matches snapshot 'good'"),
+        "{}",
+        again
+    );
+
+    let missing = zen_cmd(tmp.path(), &["snapshot", "restore", "snap-env", "nope"]);
+    assert!(!missing.status.success(), "unknown label should fail");
+}
+
+#[test]
+fn test_cli_shell_subshell() {
+    use std::io::Write;
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "sh-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let set = zen_cmd(tmp.path(), &["env", "set", "SH_MARK", "on", "sh-env"]);
+    assert!(set.status.success(), "env set: {}", all_output(&set));
+
+    let shell = |extra_env: &[(&str, &str)], script: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_zen"))
+            .args(["shell", "sh-env"])
+            .env("HOME", tmp.path())
+            .env("ZEN_HOME", tmp.path().join("envs"))
+            .env("SHELL", "/bin/bash")
+            .env_remove("ZEN_SHELL")
+            .envs(extra_env.iter().copied())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(script.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let out = shell(
+        &[],
+        "echo \"VENV=$VIRTUAL_ENV MARK=$SH_MARK IN=$ZEN_SHELL\"\nexit 3\n",
+    );
+    let text = all_output(&out);
+    let env_path = tmp.path().join("envs/sh-env");
+    assert!(
+        text.contains(&format!("VENV={} MARK=on IN=sh-env", env_path.display())),
+        "{}",
+        text
+    );
+    assert!(text.contains("[zen]"), "{}", text);
+    assert!(text.contains("Left zen shell for 'sh-env'"), "{}", text);
+    assert_eq!(out.status.code(), Some(3), "{}", text);
+
+    let nested = shell(&[("ZEN_SHELL", "other")], "exit 0\n");
+    assert!(!nested.status.success());
+    assert!(
+        all_output(&nested).contains("Already in a zen shell for 'other'"),
+        "{}",
+        all_output(&nested)
+    );
+}
+
+#[test]
+fn test_cli_log_rotation() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log_dir = tmp.path().join(".config/zen");
+    std::fs::create_dir_all(&log_dir).unwrap();
+    let log = log_dir.join("zen.log");
+    let old: String = (0..40)
+        .map(|i| {
+            format!(
+                "2026-01-01 00:00:{:02} [cli] create old-env-{}\n",
+                i % 60,
+                i
+            )
+        })
+        .collect();
+    std::fs::write(&log, &old).unwrap();
+
+    let rotate = zen_cmd(tmp.path(), &["log", "--rotate"]);
+    assert!(rotate.status.success(), "{}", all_output(&rotate));
+    assert!(all_output(&rotate).contains("Log rotated."));
+    assert!(log_dir.join("zen.log.1").exists());
+
+    // A small cap rotates on the next write; reads span the generations
+    let set = zen_cmd(tmp.path(), &["config", "log_max_size", "1KB"]);
+    assert!(set.status.success(), "{}", all_output(&set));
+    let bad = zen_cmd(tmp.path(), &["config", "log_max_size", "huge"]);
+    assert!(!bad.status.success(), "invalid size should be rejected");
+    std::fs::write(&log, &old).unwrap();
+    let set = zen_cmd(tmp.path(), &["config", "theme", "dark"]);
+    assert!(set.status.success(), "{}", all_output(&set));
+    assert!(log_dir.join("zen.log.2").exists());
+    assert!(!log.exists() || std::fs::metadata(&log).unwrap().len() < 1000);
+
+    let read = all_output(&zen_cmd(tmp.path(), &["log", "old-env", "-n", "60"]));
+    assert!(read.contains("old-env-0\n"), "{}", read);
+    assert!(read.contains("(60 entries)"), "{}", read);
+
+    let clear = zen_cmd(tmp.path(), &["log", "--clear"]);
+    assert!(clear.status.success());
+    assert!(!log_dir.join("zen.log.1").exists());
+}
+
+#[test]
+fn test_cli_log_json_format() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log = tmp.path().join(".config/zen/zen.log");
+
+    // Written as text, before the format switch takes effect
+    let set = zen_cmd(tmp.path(), &["config", "log_format", "json"]);
+    assert!(set.status.success(), "{}", all_output(&set));
+    let bad = zen_cmd(tmp.path(), &["config", "log_format", "xml"]);
+    assert!(!bad.status.success(), "invalid format should be rejected");
+    let set = zen_cmd(tmp.path(), &["config", "theme", "light"]);
+    assert!(set.status.success(), "{}", all_output(&set));
+
+    let raw = std::fs::read_to_string(&log).unwrap();
+    let last = raw.lines().last().unwrap();
+    let parsed: serde_json::Value = serde_json::from_str(last).expect(last);
+    assert_eq!(parsed["source"], "cli");
+    assert_eq!(parsed["action"], "config");
+    assert_eq!(parsed["detail"], "theme = light");
+
+    let json = zen_cmd(tmp.path(), &["log", "--json"]);
+    let out = String::from_utf8_lossy(&json.stdout).to_string();
+    let entries: Vec<serde_json::Value> = out
+        .lines()
+        .map(|l| serde_json::from_str(l).expect(l))
+        .collect();
+    assert_eq!(entries.len(), 2, "{}", out);
+    assert_eq!(entries[0]["detail"], "log_format = json");
+    assert!(entries[0]["timestamp"].as_str().unwrap().len() == 19);
+
+    // Text view renders both formats the same way
+    let text = all_output(&zen_cmd(tmp.path(), &["log"]));
+    assert!(text.contains("[cli] config log_format = json"), "{}", text);
+    assert!(text.contains("[cli] config theme = light"), "{}", text);
+}
+
+#[test]
+fn test_cli_log_source_and_action_filters() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join(".config/zen");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("zen.log"),
+        "2026-01-01 10:00:00 [cli] install demo: requests\n\
+         2026-01-01 10:01:00 [mcp] install:retry demo: numpy\n\
+         {\"timestamp\":\"2026-01-01 10:02:00\",\"source\":\"mcp\",\"action\":\"rm\",\"detail\":\"demo\"}\n\
+         2026-01-01 10:03:00 [cli] installer-note mentions install\n",
+    )
+    .unwrap();
+
+    let out = all_output(&zen_cmd(tmp.path(), &["log", "--source", "mcp"]));
+    assert!(out.contains("install:retry demo: numpy"), "{}", out);
+    assert!(out.contains("[mcp] rm demo"), "{}", out);
+    assert!(!out.contains("[cli]"), "{}", out);
+
+    let out = all_output(&zen_cmd(tmp.path(), &["log", "--action", "install"]));
+    assert!(out.contains("[cli] install demo: requests"), "{}", out);
+    assert!(out.contains("install:retry"), "{}", out);
+    assert!(!out.contains("installer-note"), "{}", out);
+    assert!(!out.contains("[mcp] rm"), "{}", out);
+
+    let out = all_output(&zen_cmd(
+        tmp.path(),
+        &["log", "--source", "cli", "--action", "install"],
+    ));
+    assert!(out.contains("requests"), "{}", out);
+    assert!(!out.contains("numpy"), "{}", out);
+
+    let bad = zen_cmd(tmp.path(), &["log", "--source", "web"]);
+    assert!(!bad.status.success());
+}
+
+#[test]
+fn test_cli_info_link() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = tmp.path().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    let project = project.canonicalize().unwrap();
+    let project_str = project.to_str().unwrap();
+
+    let create = zen_cmd(tmp.path(), &["create", "linked-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let out = all_output(&zen_cmd(tmp.path(), &["info", "linked-env", "--link"]));
+    assert!(out.contains("Links:") && out.contains("none"), "{}", out);
+
+    let add = zen_cmd(tmp.path(), &["link", "add", "linked-env", project_str]);
+    assert!(add.status.success(), "link add: {}", all_output(&add));
+
+    let out = all_output(&zen_cmd(tmp.path(), &["info", "linked-env", "--link"]));
+    assert!(out.contains("Linked projects:"), "{}", out);
+    assert!(out.contains(project_str), "{}", out);
+    assert!(out.contains("[default]"), "{}", out);
+
+    // Without the flag, links stay out of the summary
+    let out = all_output(&zen_cmd(tmp.path(), &["info", "linked-env"]));
+    assert!(!out.contains("Linked projects:"), "{}", out);
+}
+
+#[test]
+fn test_cli_export_scoped_sections() {
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "scoped-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let tpl = tmp.path().join("tpl.toml");
+    std::fs::write(
+        &tpl,
+        r#"[template]
+name = "shareable"
+version = "v1"
+python = "3.12"
+
+[[step]]
+packages = [{ name = "numpy", version = "2.1.0" }]
+"#,
+    )
+    .unwrap();
+    let import = zen_cmd(tmp.path(), &["template", "import", tpl.to_str().unwrap()]);
+    assert!(import.status.success(), "{}", all_output(&import));
+
+    let read = |name: &str, flag: &str| {
+        let file = tmp.path().join(name);
+        let out = zen_cmd(tmp.path(), &["export", "-f", file.to_str().unwrap(), flag]);
+        assert!(out.status.success(), "{}", all_output(&out));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        (file, json)
+    };
+
+    let (tpl_file, json) = read("templates.json", "--templates-only");
+    assert!(json.get("environments").is_none(), "{}", json);
+    assert_eq!(json["templates"][0]["name"], "shareable");
+    assert!(!json.to_string().contains("scoped-env"));
+
+    let (_, json) = read("envs.json", "--envs-only");
+    assert!(json.get("templates").is_none(), "{}", json);
+    assert_eq!(json["environments"][0][0], "scoped-env");
+
+    let both = zen_cmd(tmp.path(), &["export", "--templates-only", "--envs-only"]);
+    assert!(!both.status.success());
+
+    // A templates-only file imports cleanly into a fresh registry
+    let fresh = tempfile::tempdir().unwrap();
+    let import = zen_cmd(fresh.path(), &["import", tpl_file.to_str().unwrap()]);
+    assert!(import.status.success(), "{}", all_output(&import));
+    assert!(
+        all_output(&import).contains("Imported 0 environment(s) and 1 template(s)"),
+        "{}",
+        all_output(&import)
+    );
+    let list = all_output(&zen_cmd(fresh.path(), &["template", "list"]));
+    assert!(list.contains("shareable"), "{}", list);
+}
+
+#[test]
+fn test_cli_import_merge_and_overwrite() {
+    let tmp = tempfile::tempdir().unwrap();
+    let tpl = |version: &str| {
+        format!(
+            r#"{{"name":"shared","version":"v1","python_version":"3.12","packages":[["numpy","{}",true,"pip",null,0,null,null]]}}"#,
+            version
+        )
+    };
+    let write = |name: &str, env_path: &str, numpy: &str| {
+        let file = tmp.path().join(name);
+        std::fs::write(
+            &file,
+            format!(
+                r#"{{"environments":[["kept","{}","3.12","",false]],"templates":[{}]}}"#,
+                env_path,
+                tpl(numpy)
+            ),
+        )
+        .unwrap();
+        file
+    };
+
+    let first = write("first.json", "/data/kept", "2.1.0");
+    let import = zen_cmd(tmp.path(), &["import", first.to_str().unwrap()]);
+    assert!(all_output(&import).contains("Imported 1 environment(s) and 1 template(s)"));
+
+    // Same content again: nothing to do, no conflicts
+    let again = all_output(&zen_cmd(tmp.path(), &["import", first.to_str().unwrap()]));
+    assert!(
+        again.contains("Imported 0 environment(s) and 0 template(s)"),
+        "{}",
+        again
+    );
+    assert!(!again.contains("Kept"), "{}", again);
+
+    // Different path and packages: merge keeps ours and reports both
+    let second = write("second.json", "/elsewhere/kept", "1.26.4");
+    let merged = all_output(&zen_cmd(tmp.path(), &["import", second.to_str().unwrap()]));
+    assert!(merged.contains("Kept 2 existing entries"), "{}", merged);
+    assert!(merged.contains("registered at /data/kept"), "{}", merged);
+    assert!(merged.contains("template 'shared:v1'"), "{}", merged);
+    let which = all_output(&zen_cmd(tmp.path(), &["template", "inspect", "shared:v1"]));
+    assert!(
+        which.contains("2.1.0") && !which.contains("1.26.4"),
+        "{}",
+        which
+    );
+
+    let overwritten = zen_cmd(
+        tmp.path(),
+        &["import", second.to_str().unwrap(), "--overwrite"],
+    );
+    assert!(
+        all_output(&overwritten).contains("Imported 1 environment(s) and 1 template(s)"),
+        "{}",
+        all_output(&overwritten)
+    );
+    // The template is replaced, not appended to
+    let which = all_output(&zen_cmd(tmp.path(), &["template", "inspect", "shared:v1"]));
+    assert!(
+        which.contains("1.26.4") && !which.contains("2.1.0"),
+        "{}",
+        which
+    );
+
+    let both = zen_cmd(
+        tmp.path(),
+        &["import", first.to_str().unwrap(), "--merge", "--overwrite"],
+    );
+    assert!(!both.status.success());
+}
+
+#[test]
+fn test_cli_db_vacuum_and_check() {
+    let tmp = tempfile::tempdir().unwrap();
+    for i in 0..5 {
+        let name = format!("churn{}", i);
+        zen_cmd(tmp.path(), &["create", &name, "--no-bootstrap"]);
+        zen_cmd(tmp.path(), &["rm", &name, "--yes"]);
+    }
+
+    let vacuum = zen_cmd(tmp.path(), &["db", "vacuum"]);
+    assert!(vacuum.status.success(), "{}", all_output(&vacuum));
+    let out = all_output(&vacuum);
+    assert!(out.contains("Vacuumed") && out.contains("→"), "{}", out);
+
+    let check = zen_cmd(tmp.path(), &["db", "check"]);
+    assert!(check.status.success(), "{}", all_output(&check));
+    assert!(all_output(&check).contains("integrity ok"));
+}
+
+#[test]
+fn test_cli_install_editable_from_project_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    let project = write_editable_project(tmp.path());
+    let canonical = project.canonicalize().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "ed-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let zen_in = |dir: &std::path::Path, args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_zen"))
+            .args(args)
+            .current_dir(dir)
+            .env("HOME", tmp.path())
+            .env("ZEN_HOME", tmp.path().join("envs"))
+            .output()
+            .unwrap()
+    };
+
+    // Bare -e inside the project installs the project itself
+    let install = zen_in(&project, &["install", "-n", "ed-env", "-e"]);
+    let out = all_output(&install);
+    assert!(install.status.success(), "{}", out);
+    assert!(
+        out.contains(&format!(
+            "Editable: my-proj 0.1.0 (import mypkg) ← {}",
+            canonical.display()
+        )),
+        "{}",
+        out
+    );
+
+    // A relative path from elsewhere resolves to the same project
+    let again = zen_in(tmp.path(), &["install", "-n", "ed-env", "-e", "./my-proj"]);
+    assert!(again.status.success(), "{}", all_output(&again));
+    assert!(all_output(&again).contains("Editable: my-proj"));
+
+    let list = all_output(&zen_cmd(tmp.path(), &["inspect", "ed-env"]));
+    assert!(list.contains("my-proj"), "{}", list);
+
+    // Nothing to guess from in a plain directory
+    let empty = tmp.path().join("empty");
+    std::fs::create_dir_all(&empty).unwrap();
+    let none = zen_in(&empty, &["install", "-n", "ed-env", "-e"]);
+    assert!(!none.status.success());
+    assert!(
+        all_output(&none).contains("pyproject.toml"),
+        "{}",
+        all_output(&none)
+    );
+}
+
+#[test]
+fn test_cli_info_compare_template() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+    let tpl = tmp.path().join("tpl.toml");
+    std::fs::write(
+        &tpl,
+        r#"[template]
+name = "baseline"
+version = "v1"
+python = "3.12"
+
+[[step]]
+packages = [{ name = "tinydep", version = "0.2.0" }, { name = "numpy", version = "2.1.0" }]
+"#,
+    )
+    .unwrap();
+    let import = zen_cmd(tmp.path(), &["template", "import", tpl.to_str().unwrap()]);
+    assert!(import.status.success(), "import: {}", all_output(&import));
+
+    let create = zen_cmd(tmp.path(), &["create", "drift-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "drift-env", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let out = all_output(&zen_cmd(
+        tmp.path(),
+        &["info", "drift-env", "--compare-template", "baseline:v1"],
+    ));
+    assert!(
+        out.contains("Template:") && out.contains("baseline:v1"),
+        "{}",
+        out
+    );
+    assert!(out.contains("- numpy 2.1.0 (missing)"), "{}", out);
+    assert!(out.contains("~ tinydep 0.2.0 → 0.1.0"), "{}", out);
+    // Bootstrap packages are not drift
+    assert!(!out.contains("+ pip"), "{}", out);
+
+    let missing = zen_cmd(
+        tmp.path(),
+        &["info", "drift-env", "--compare-template", "nope:v1"],
+    );
+    assert!(!missing.status.success());
+    assert!(
+        all_output(&missing).contains("Template 'nope:v1' not found"),
+        "{}",
+        all_output(&missing)
+    );
+}
+
+#[test]
+fn test_cli_create_records_source_template() {
+    let tmp = tempfile::tempdir().unwrap();
+    let tpl = tmp.path().join("tpl.toml");
+    std::fs::write(
+        &tpl,
+        "[template]\nname = \"base\"\nversion = \"v1\"\npython = \"3.12\"\n\n[[step]]\npackages = []\n",
+    )
+    .unwrap();
+    let import = zen_cmd(tmp.path(), &["template", "import", tpl.to_str().unwrap()]);
+    assert!(import.status.success(), "import: {}", all_output(&import));
+
+    let create = zen_cmd(
+        tmp.path(),
+        &["create", "from-tpl", "--from", "base:v1", "--no-bootstrap"],
+    );
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let create = zen_cmd(tmp.path(), &["create", "plain", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let out = all_output(&zen_cmd(tmp.path(), &["info", "from-tpl"]));
+    assert!(out.contains("Created from: base:v1"), "{}", out);
+    let out = all_output(&zen_cmd(tmp.path(), &["info", "plain"]));
+    assert!(!out.contains("Created from:"), "{}", out);
+
+    // A bare --compare-template falls back to the recorded source
+    let out = all_output(&zen_cmd(
+        tmp.path(),
+        &["info", "from-tpl", "--compare-template"],
+    ));
+    assert!(out.contains("Template:   base:v1"), "{}", out);
+    let out = all_output(&zen_cmd(
+        tmp.path(),
+        &["info", "plain", "--compare-template"],
+    ));
+    assert!(out.contains("none recorded"), "{}", out);
+
+    // The source survives an export/import round trip
+    let file = tmp.path().join("registry.json");
+    let export = zen_cmd(tmp.path(), &["export", "-f", file.to_str().unwrap()]);
+    assert!(export.status.success(), "export: {}", all_output(&export));
+    let other = tempfile::tempdir().unwrap();
+    let import = zen_cmd(other.path(), &["import", file.to_str().unwrap()]);
+    assert!(import.status.success(), "import: {}", all_output(&import));
+    let out = all_output(&zen_cmd(other.path(), &["info", "from-tpl"]));
+    assert!(out.contains("Created from: base:v1"), "{}", out);
+}
+
+#[test]
+fn test_cli_create_copy_from() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+    let create = zen_cmd(tmp.path(), &["create", "source-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "source-env", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let copy = zen_cmd(
+        tmp.path(),
+        &[
+            "create",
+            "copy-env",
+            "--copy-from",
+            "source-env",
+            "--strict",
+        ],
+    );
+    let out = all_output(&copy);
+    assert!(copy.status.success(), "copy: {}", out);
+    assert!(
+        out.contains("Copying 1 package(s) from 'source-env'"),
+        "{}",
+        out
+    );
+    let dist = tmp
+        .path()
+        .join("envs/copy-env/lib")
+        .read_dir()
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path()
+        .join("site-packages/tinydep-0.1.0.dist-info");
+    assert!(dist.exists(), "{}", out);
+
+    let itself = zen_cmd(
+        tmp.path(),
+        &["create", "copy-env", "--copy-from", "copy-env", "--rm"],
+    );
+    assert!(!itself.status.success());
+    assert!(all_output(&itself).contains("onto itself"));
+
+    let missing = zen_cmd(tmp.path(), &["create", "other", "--copy-from", "nope"]);
+    assert!(!missing.status.success());
+    assert!(all_output(&missing).contains("Environment 'nope' not found"));
+}
+
+#[test]
+fn test_cli_landing_screen_aligns_descriptions() {
+    let tmp = tempfile::tempdir().unwrap();
+    let out = zen_cmd(tmp.path(), &[]);
+    let text = String::from_utf8_lossy(&out.stderr).to_string();
+    assert!(text.contains("Getting Started"), "{}", text);
+    // Every command row starts its description in the same column
+    let columns: std::collections::HashSet<usize> = [
+        ("setup stack-info", "Configure tracked packages"),
+        ("list", "List all environments"),
+        ("inspect", "Inspect a package in an environment"),
+        ("template", "Manage environment templates"),
+    ]
+    .iter()
+    .map(|(cmd, desc)| {
+        let line = text
+            .lines()
+            .find(|l| l.trim_start().starts_with(cmd) && l.contains(desc))
+            .unwrap_or_else(|| panic!("no row for {}: {}", cmd, text));
+        line.find(desc).unwrap()
+    })
+    .collect();
+    assert_eq!(columns.len(), 1, "{}", text);
+}
+
+#[test]
+fn test_cli_output_json_and_csv() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+    for env in ["out-a", "out-b"] {
+        let create = zen_cmd(tmp.path(), &["create", env]);
+        assert!(create.status.success(), "create: {}", all_output(&create));
+    }
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "out-a", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let stdout = |args: &[&str]| {
+        let out = zen_cmd(tmp.path(), args);
+        assert!(out.status.success(), "{:?}: {}", args, all_output(&out));
+        String::from_utf8_lossy(&out.stdout).to_string()
+    };
+
+    let list: serde_json::Value =
+        serde_json::from_str(&stdout(&["list", "--output", "json"])).unwrap();
+    assert_eq!(list[0]["name"], "out-a");
+    assert_eq!(list[1]["health"], "pass");
+
+    let csv = stdout(&["find", "tinydep", "--output", "csv"]);
+    assert_eq!(csv, "env,package,version\nout-a,tinydep,0.1.0\n");
+
+    let inspect: serde_json::Value = serde_json::from_str(&stdout(&[
+        "inspect", "out-a", "tinydep", "--output", "json",
+    ]))
+    .unwrap();
+    assert_eq!(inspect[0]["version"], "0.1.0");
+    assert_eq!(inspect[0]["source"], "local");
+
+    let diff = stdout(&["diff", "out-a", "out-b", "-d", "--output", "csv"]);
+    assert_eq!(diff, "package,out-a,out-b\ntinydep,0.1.0,\n");
+
+    let tree = zen_cmd(
+        tmp.path(),
+        &["inspect", "out-a", "--tree", "--output", "json"],
+    );
+    assert!(!tree.status.success());
+}
+
+#[test]
+fn test_cli_find_count_and_by_env() {
+    let tmp = tempfile::tempdir().unwrap();
+    for env in ["count-a", "count-b"] {
+        let create = zen_cmd(tmp.path(), &["create", env]);
+        assert!(create.status.success(), "create: {}", all_output(&create));
+    }
+
+    let out = all_output(&zen_cmd(tmp.path(), &["find", "pip", "--exact", "--count"]));
+    assert_eq!(out.trim(), "2 environments contain 'pip'");
+    let out = all_output(&zen_cmd(tmp.path(), &["find", "no-such-pkg", "--count"]));
+    assert_eq!(out.trim(), "0 environments contain 'no-such-pkg'");
+
+    let out = all_output(&zen_cmd(
+        tmp.path(),
+        &["find", "pip", "--exact", "--by-env"],
+    ));
+    let lines: Vec<&str> = out.lines().collect();
+    assert!(lines[0].starts_with("count-a (1)"), "{}", out);
+    assert!(lines[1].starts_with("  pip → "), "{}", out);
+    assert!(lines[2].starts_with("count-b (1)"), "{}", out);
+
+    let both = zen_cmd(tmp.path(), &["find", "pip", "--count", "--by-env"]);
+    assert!(!both.status.success());
+}
+
+#[test]
+fn test_cli_inspect_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let wheel = build_tiny_wheel(tmp.path());
+    let create = zen_cmd(tmp.path(), &["create", "files-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let install = zen_cmd(
+        tmp.path(),
+        &["install", "-n", "files-env", wheel.to_str().unwrap()],
+    );
+    assert!(
+        install.status.success(),
+        "install: {}",
+        all_output(&install)
+    );
+
+    let inspect = zen_cmd(tmp.path(), &["inspect", "files-env", "tinydep", "--files"]);
+    let out = all_output(&inspect);
+    assert!(inspect.status.success(), "inspect: {}", out);
+    assert!(out.contains("Files:"), "{}", out);
+    assert!(out.contains("  tinydep/__init__.py"), "{}", out);
+    assert!(
+        out.contains("  tinydep-0.1.0.dist-info/METADATA"),
+        "{}",
+        out
+    );
+
+    // --files needs a package to list
+    let no_pkg = zen_cmd(tmp.path(), &["inspect", "files-env", "--files"]);
+    assert!(!no_pkg.status.success());
+}
+
+#[test]
+fn test_cli_verify() {
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "verify-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let clean = zen_cmd(tmp.path(), &["verify", "verify-env", "--hashes"]);
+    let out = all_output(&clean);
+    assert!(clean.status.success(), "verify: {}", out);
+    assert!(out.contains("match their RECORD"), "{}", out);
+
+    // Drop a file pip installed and tamper with another
+    let site = std::fs::read_dir(tmp.path().join("envs/verify-env/lib"))
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path()
+        .join("site-packages");
+    let pip_dist = std::fs::read_dir(&site)
+        .unwrap()
+        .flatten()
+        .find(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.starts_with("pip-") && name.ends_with(".dist-info")
+        })
+        .unwrap()
+        .path();
+    std::fs::remove_file(site.join("pip/__init__.py")).unwrap();
+    std::fs::write(pip_dist.join("INSTALLER"), "tampered\n").unwrap();
+
+    let files = zen_cmd(tmp.path(), &["verify", "verify-env"]);
+    let out = all_output(&files);
+    assert_eq!(files.status.code(), Some(1), "{}", out);
+    assert!(out.contains("1 missing"), "{}", out);
+    assert!(out.contains("pip/__init__.py"), "{}", out);
+    assert!(!out.contains("modified"), "{}", out);
+
+    let hashed = zen_cmd(tmp.path(), &["verify", "verify-env", "--hashes"]);
+    let out = all_output(&hashed);
+    assert_eq!(hashed.status.code(), Some(1), "{}", out);
+    assert!(out.contains("1 missing, 1 modified"), "{}", out);
+}
+
+#[test]
+fn test_cli_time_import() {
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "time-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let time = zen_cmd(tmp.path(), &["time", "time-env", "json", "-n", "3"]);
+    let out = all_output(&time);
+    assert!(time.status.success(), "time: {}", out);
+    assert!(out.contains("import json in 'time-env'"), "{}", out);
+    assert!(out.contains("cumulative"), "{}", out);
+    // Startup modules are already loaded, so they don't count
+    assert!(!out.contains("encodings"), "{}", out);
+
+    let missing = zen_cmd(tmp.path(), &["time", "time-env", "no_such_module"]);
+    assert!(!missing.status.success());
+    assert!(all_output(&missing).contains("ModuleNotFoundError"));
+
+    let code = zen_cmd(tmp.path(), &["time", "time-env", "os; print(1)"]);
+    assert_eq!(code.status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_install_reports_introduced_conflicts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let old = build_tiny_wheel(tmp.path());
+    let new = build_wheel(tmp.path(), "tinydep", "0.2.0", None);
+    let pinner = build_wheel(tmp.path(), "pinner", "0.1.0", Some("tinydep==0.1.0"));
+    let create = zen_cmd(tmp.path(), &["create", "conf-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let install = |args: &[&str]| {
+        let mut full = vec!["install", "-n", "conf-env", "-q"];
+        full.extend_from_slice(args);
+        let out = zen_cmd(tmp.path(), &full);
+        assert!(out.status.success(), "{:?}: {}", args, all_output(&out));
+        all_output(&out)
+    };
+    let out = install(&[old.to_str().unwrap(), pinner.to_str().unwrap()]);
+    assert!(!out.contains("introduced"), "{}", out);
+
+    let out = install(&[new.to_str().unwrap()]);
+    assert!(
+        out.contains("introduced: pinner requires tinydep==0.1.0, but 0.2.0 is installed"),
+        "{}",
+        out
+    );
+    assert!(out.contains("zen health conf-env"), "{}", out);
+
+    // An existing conflict isn't blamed on the next install
+    let out = install(&[new.to_str().unwrap()]);
+    assert!(!out.contains("introduced"), "{}", out);
+
+    install(&[old.to_str().unwrap()]);
+    let out = install(&["--no-check", new.to_str().unwrap()]);
+    assert!(!out.contains("introduced"), "{}", out);
+}
+
+#[test]
+fn test_cli_info_package_ages() {
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "age-env"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+    let info = zen_cmd(tmp.path(), &["info", "age-env"]);
+    let out = all_output(&info);
+    assert!(info.status.success(), "info: {}", out);
+    let line = out
+        .lines()
+        .find(|l| l.starts_with("Installed:"))
+        .unwrap_or_else(|| panic!("no Installed line: {}", out));
+    assert!(
+        line.contains("newest ") && line.contains("oldest "),
+        "{}",
+        line
+    );
+    assert!(line.contains("median just now"), "{}", line);
+}
+
+#[test]
+fn test_cli_list_columns() {
+    let tmp = tempfile::tempdir().unwrap();
+    let create = zen_cmd(tmp.path(), &["create", "cols-env", "--no-bootstrap"]);
+    assert!(create.status.success(), "create: {}", all_output(&create));
+
+    let list = zen_cmd(tmp.path(), &["list", "--columns", "name,size,numpy"]);
+    let out = all_output(&list);
+    assert!(list.status.success(), "list: {}", out);
+    let header = out.lines().find(|l| l.contains("Name")).unwrap();
+    assert!(
+        header.contains("Size") && header.contains("numpy"),
+        "{}",
+        out
+    );
+    assert!(!header.contains("Health"), "{}", out);
+    let row = out.lines().find(|l| l.contains("cols-env")).unwrap();
+    assert!(row.contains(" MB") || row.contains(" KB"), "{}", out);
+
+    let wide = zen_cmd(tmp.path(), &["list", "--columns", "wide"]);
+    assert!(all_output(&wide).contains("Last used"));
+
+    let unknown = zen_cmd(tmp.path(), &["list", "--columns", "name,pandas"]);
+    assert_eq!(unknown.status.code(), Some(2));
+    assert!(all_output(&unknown).contains("Unknown column 'pandas'"));
+}
+
+#[test]
+fn test_cli_setup_init_depth() {
+    let tmp = tempfile::tempdir().unwrap();
+    let projects = tmp.path().join("projects");
+    for venv in ["a/b/near-venv", "a/b/c/d/far-venv"] {
+        let bin = projects.join(venv).join("bin");
+        std::fs::create_dir_all(&bin).unwrap();
+        std::fs::write(bin.join("python"), "").unwrap();
+    }
+    let dir = projects.to_str().unwrap();
+
+    // No flag scans the default 3 levels
+    let init = zen_cmd(tmp.path(), &["setup", "init", dir, "--yes"]);
+    assert!(init.status.success(), "init: {}", all_output(&init));
+    let list = all_output(&zen_cmd(tmp.path(), &["list", "-1"]));
+    assert!(
+        list.contains("near-venv") && !list.contains("far-venv"),
+        "{}",
+        list
+    );
+
+    let init = zen_cmd(tmp.path(), &["setup", "init", dir, "--recursive", "--yes"]);
+    assert!(init.status.success(), "init: {}", all_output(&init));
+    let list = all_output(&zen_cmd(tmp.path(), &["list", "-1"]));
+    assert!(list.contains("far-venv"), "{}", list);
+}
@@ -0,0 +1,36 @@
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fixtures shared by the library and CLI tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Writes `<name>-<version>.dist-info/METADATA` under `site`, followed by any
+/// `extra` header lines (e.g. `Requires-Dist: ...`). Returns the dist-info dir.
+pub fn write_dist_info(site: &Path, name: &str, version: &str, extra: &str) -> PathBuf {
+    let dir = site.join(format!("{}-{}.dist-info", name, version));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("METADATA"),
+        format!(
+            "Metadata-Version: 2.1\nName: {}\nVersion: {}\n{}",
+            name, version, extra
+        ),
+    )
+    .unwrap();
+    dir
+}
+
+/// Lays out a fake venv at `env`: an empty `bin/python` and a python3.12
+/// `site-packages` holding one dist-info per `(name, version, extra)`.
+/// Returns the site-packages dir.
+pub fn fake_env(env: &Path, dists: &[(&str, &str, &str)]) -> PathBuf {
+    let site = env.join("lib/python3.12/site-packages");
+    fs::create_dir_all(env.join("bin")).unwrap();
+    fs::create_dir_all(&site).unwrap();
+    fs::write(env.join("bin/python"), "").unwrap();
+    for (name, version, extra) in dists {
+        write_dist_info(&site, name, version, extra);
+    }
+    site
+}